@@ -0,0 +1,61 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::PathBuf;
+
+const DEFAULT_SERVER: &str = "http://127.0.0.1:8080";
+
+/// `~/.config/shredlink/config.toml` (platform config dir). All keys optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CliConfig {
+    pub server: Option<String>,
+    pub api_key: Option<String>,
+    /// Secret key file for links sealed to you.
+    pub identity: Option<PathBuf>,
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("shredlink").join("config.toml"))
+}
+
+/// `~/.config/shredlink/identity`, written by `shredlink key gen`.
+pub fn default_identity_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("shredlink").join("identity"))
+}
+
+impl CliConfig {
+    /// Loads `path` if given (must exist), else the default path if present.
+    pub fn load(path: Option<&std::path::Path>) -> Result<Self> {
+        let path = match path {
+            Some(p) => p.to_path_buf(),
+            None => match default_path().filter(|p| p.exists()) {
+                Some(p) => p,
+                None => return Ok(Self::default()),
+            },
+        };
+        let raw = std::fs::read_to_string(&path)
+            .with_context(|| format!("cannot read config file {}", path.display()))?;
+        toml::from_str(&raw).with_context(|| format!("invalid config file {}", path.display()))
+    }
+
+    /// Server URL: `--server`/`SHREDLINK_SERVER` wins over the file, then the default.
+    pub fn server(&self, flag: Option<&str>) -> String {
+        flag.map(str::to_string)
+            .or_else(|| self.server.clone())
+            .unwrap_or_else(|| DEFAULT_SERVER.to_string())
+            .trim_end_matches('/')
+            .to_string()
+    }
+
+    /// API key: `--api-key`/`SHREDLINK_API_KEY` wins over the file.
+    pub fn api_key(&self, flag: Option<&str>) -> Option<String> {
+        flag.map(str::to_string).or_else(|| self.api_key.clone())
+    }
+
+    /// Identity file: `--identity`/`SHREDLINK_IDENTITY` wins over the file, then the default.
+    pub fn identity(&self, flag: Option<&std::path::Path>) -> Option<PathBuf> {
+        flag.map(std::path::Path::to_path_buf)
+            .or_else(|| self.identity.clone())
+            .or_else(default_identity_path)
+    }
+}
@@ -0,0 +1,520 @@
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+mod config;
+
+use config::CliConfig;
+use shredlink_client::{
+    envelope, open_text_as, shamir, Client, CreateRequest, CreateSecretRequest, Error as ApiError, SecretRequestStatus, ShareUrl, SplitRequest,
+};
+
+#[derive(Parser)]
+#[command(name = "shredlink", version, about = "Create and read ShredLink secrets from the command line")]
+struct Cli {
+    /// Server base URL (overrides the config file).
+    #[arg(long, short = 's', env = "SHREDLINK_SERVER", global = true)]
+    server: Option<String>,
+    /// API key for authenticated, higher-quota requests (overrides the config file).
+    #[arg(long, env = "SHREDLINK_API_KEY", global = true, hide_env_values = true)]
+    api_key: Option<String>,
+    /// Config file (default: ~/.config/shredlink/config.toml).
+    #[arg(long, env = "SHREDLINK_CLI_CONFIG", global = true)]
+    config: Option<PathBuf>,
+    /// Secret key file for links sealed to you (default: ~/.config/shredlink/identity).
+    #[arg(long, short = 'i', env = "SHREDLINK_IDENTITY", global = true)]
+    identity: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a link from a file or stdin and print its URL.
+    Send {
+        /// File to send; reads stdin when omitted or "-".
+        file: Option<PathBuf>,
+        /// Burn the link after the first view.
+        #[arg(long)]
+        once: bool,
+        /// Expiry such as 30m, 1h, 2d or 1w (bare numbers are minutes).
+        #[arg(long, short = 'e', value_parser = parse_expire, conflicts_with = "expires_at")]
+        expire: Option<u32>,
+        /// Expire at a fixed time (RFC 3339, e.g. 2025-03-01T18:00:00Z).
+        #[arg(long)]
+        expires_at: Option<String>,
+        /// Make the link readable only from this time on (RFC 3339); --expire counts from it.
+        #[arg(long)]
+        not_before: Option<String>,
+        /// Expire after this long without a read, e.g. 2h; each read resets it.
+        #[arg(long, value_parser = parse_expire)]
+        idle: Option<u32>,
+        /// URL that receives signed read-receipt events for this link.
+        #[arg(long)]
+        notify: Option<String>,
+        /// Email this address when the link is first opened or expires unread (needs an API key).
+        #[arg(long)]
+        notify_email: Option<String>,
+        /// Language of those emails: en or fa.
+        #[arg(long, requires = "notify_email", value_parser = ["en", "fa"])]
+        notify_lang: Option<String>,
+        /// Prompt for a password the recipient must enter.
+        #[arg(long, short = 'p')]
+        password: bool,
+        /// Burn the link after the first successful password unlock (implies --password).
+        #[arg(long)]
+        password_once: bool,
+        /// Seal to a recipient's public key (slpub_...) or @username, so only they can read it.
+        #[arg(long, short = 'r')]
+        recipient: Option<String>,
+        /// Give this person their own one-time link (repeat per person; needs an API key).
+        /// Prints one "name<TAB>URL" line each.
+        #[arg(long, conflicts_with_all = ["once", "password_once"])]
+        to: Vec<String>,
+        /// Only allow opening from this network (CIDR or IP; repeatable).
+        #[arg(long = "allow", value_name = "CIDR")]
+        allow: Vec<String>,
+        /// Send plaintext to the server instead of encrypting locally.
+        #[arg(long)]
+        no_encrypt: bool,
+    },
+    /// Read a link and write its content to stdout or a file.
+    Get {
+        /// Share URL (including any #key fragment) or bare token.
+        url: String,
+        /// Write to this file instead of stdout.
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+    },
+    /// Split a secret into N share links, any M of which recover it; prints one URL per line.
+    Split {
+        /// File to split; reads stdin when omitted or "-".
+        file: Option<PathBuf>,
+        /// Number of share links to create (N).
+        #[arg(long, short = 'n')]
+        shares: u8,
+        /// Shares needed to recover the secret (M).
+        #[arg(long, short = 'm')]
+        threshold: u8,
+        /// Burn each share link after its first view.
+        #[arg(long)]
+        once: bool,
+        /// Expiry such as 30m, 1h, 2d or 1w (bare numbers are minutes).
+        #[arg(long, short = 'e', value_parser = parse_expire)]
+        expire: Option<u32>,
+        /// Prompt for a password protecting every share link.
+        #[arg(long, short = 'p')]
+        password: bool,
+        /// Seal each share to one custodian's key (slpub_... or @username), in order; repeat once per share.
+        #[arg(long, short = 'r')]
+        recipient: Vec<String>,
+        /// Only allow opening the share links from this network (CIDR or IP; repeatable).
+        #[arg(long = "allow", value_name = "CIDR")]
+        allow: Vec<String>,
+    },
+    /// Read share links (or share texts) and print the recombined secret.
+    Combine {
+        /// Share URLs, bare tokens or `shredlink:share1:` texts.
+        #[arg(required = true)]
+        shares: Vec<String>,
+        /// Write to this file instead of stdout.
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+    },
+    /// Show which recipients of a `send --to` link have opened it (needs the API key that created it).
+    Recipients {
+        /// Token printed by `send --to`.
+        token: String,
+    },
+    /// Ask someone to send you a secret (needs an API key).
+    #[command(subcommand)]
+    Request(RequestCommand),
+    /// Manage your recipient key for links sealed to you.
+    #[command(subcommand)]
+    Key(KeyCommand),
+}
+
+#[derive(Subcommand)]
+enum KeyCommand {
+    /// Create a secret key in the identity file and print its public key.
+    Gen {
+        /// Replace an existing identity file.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print the public key to give to senders or register on your account.
+    Show,
+}
+
+#[derive(Subcommand)]
+enum RequestCommand {
+    /// Create a request and print the link to send to the person who should answer.
+    /// The answer is sealed to the public key of your identity file.
+    New {
+        /// Shown to them, e.g. what to send.
+        #[arg(long)]
+        note: Option<String>,
+        /// How long the request stays open, e.g. 2d (default 7 days).
+        #[arg(long, value_parser = parse_expire)]
+        expire: Option<u32>,
+    },
+    /// List your requests and whether they have been answered.
+    List,
+    /// Print an answer, opened with your identity file; the server deletes it afterwards.
+    Read {
+        /// Request id from `request new` or `request list`.
+        id: String,
+        /// Write to this file instead of stdout.
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Parses `90`, `30m`, `12h`, `2d` or `1w` into minutes.
+fn parse_expire(s: &str) -> std::result::Result<u32, String> {
+    let s = s.trim();
+    let (num, mult) = match s.chars().last() {
+        Some('m') => (&s[..s.len() - 1], 1),
+        Some('h') => (&s[..s.len() - 1], 60),
+        Some('d') => (&s[..s.len() - 1], 60 * 24),
+        Some('w') => (&s[..s.len() - 1], 60 * 24 * 7),
+        _ => (s, 1),
+    };
+    let n: u32 = num
+        .parse()
+        .map_err(|_| format!("invalid expiry {:?}; use e.g. 30m, 1h, 2d", s))?;
+    match n.checked_mul(mult) {
+        Some(0) => Err("expiry must be greater than zero".to_string()),
+        Some(m) => Ok(m),
+        None => Err("expiry is too large".to_string()),
+    }
+}
+
+fn read_input(file: Option<&PathBuf>) -> Result<String> {
+    let mut buf = Vec::new();
+    match file {
+        Some(p) if p.as_os_str() != "-" => {
+            buf = std::fs::read(p).with_context(|| format!("cannot read {}", p.display()))?;
+        }
+        _ => {
+            std::io::stdin().read_to_end(&mut buf).context("cannot read stdin")?;
+        }
+    }
+    String::from_utf8(buf).map_err(|_| anyhow::anyhow!("input is not valid UTF-8 text"))
+}
+
+fn prompt_new_password() -> Result<String> {
+    let first = rpassword::prompt_password("Password: ")?;
+    if first.is_empty() {
+        anyhow::bail!("password must not be empty");
+    }
+    let second = rpassword::prompt_password("Repeat password: ")?;
+    if first != second {
+        anyhow::bail!("passwords do not match");
+    }
+    Ok(first)
+}
+
+/// Accepts a full share URL or a bare token (with optional `#key`) on `default_base`.
+fn parse_share_url(input: &str, default_base: &str) -> Result<ShareUrl> {
+    if input.contains("://") {
+        return Ok(ShareUrl::parse(input)?);
+    }
+    let (token, key) = match input.split_once('#') {
+        Some((t, k)) => (t, Some(k.to_string())),
+        None => (input, None),
+    };
+    Ok(ShareUrl {
+        base: default_base.to_string(),
+        token: token.to_string(),
+        key,
+    })
+}
+
+/// Builds a client for `base`, attaching the API key only for the configured server.
+fn client_for(base: &str, server: &str, api_key: Option<&str>) -> Result<Client> {
+    let mut builder = Client::builder(base);
+    if let Some(key) = api_key.filter(|_| base.trim_end_matches('/') == server) {
+        builder = builder.api_key(key);
+    }
+    Ok(builder.build()?)
+}
+
+/// Reads the content and creates the link; `req` carries the expiry and one-time options.
+async fn send(client: &Client, file: Option<PathBuf>, mut req: CreateRequest, password: bool, no_encrypt: bool) -> Result<()> {
+    let text = read_input(file.as_ref())?;
+    if text.is_empty() {
+        anyhow::bail!("nothing to send: input is empty");
+    }
+    if password || req.one_time_password {
+        req.password = Some(prompt_new_password()?);
+    }
+    req.text = text;
+    let (token, url, recipients, webhook_secret) = if no_encrypt {
+        let res = client.create(&req).await?;
+        (res.token, res.url, res.recipients, res.webhook_secret)
+    } else {
+        let res = client.create_encrypted(req).await?;
+        (res.token, res.url, res.recipients, res.webhook_secret)
+    };
+    if let Some(secret) = webhook_secret {
+        eprintln!("webhook signing secret (shown once): {}", secret);
+    }
+    if recipients.is_empty() {
+        println!("{}", url);
+        return Ok(());
+    }
+    for r in &recipients {
+        println!("{}\t{}", r.label, r.url);
+    }
+    eprintln!("check who has opened it with: shredlink recipients {}", token);
+    Ok(())
+}
+
+async fn recipients(client: &Client, token: &str) -> Result<()> {
+    for r in client.recipients(token).await? {
+        let status = if r.viewed { "opened" } else { "pending" };
+        println!("{}\t{}\t{}", r.label, status, r.viewed_at.as_deref().unwrap_or("-"));
+    }
+    Ok(())
+}
+
+fn write_output(text: &str, output: Option<PathBuf>) -> Result<()> {
+    match output {
+        Some(p) => std::fs::write(&p, text.as_bytes()).with_context(|| format!("cannot write {}", p.display()))?,
+        None => {
+            let mut out = std::io::stdout().lock();
+            out.write_all(text.as_bytes())?;
+            out.flush()?;
+        }
+    }
+    Ok(())
+}
+
+async fn request(client: &Client, identity: Option<&PathBuf>, cmd: RequestCommand) -> Result<()> {
+    match cmd {
+        RequestCommand::New { note, expire } => {
+            let public_key = envelope::public_key_of(&read_identity(identity)?)?;
+            let req = CreateSecretRequest {
+                note,
+                expire_minutes: expire,
+                public_key: Some(public_key),
+            };
+            let created = client.create_secret_request(&req).await?;
+            println!("{}", created.url);
+            eprintln!("id {} (expires {})", created.id, created.expires_at);
+        }
+        RequestCommand::List => {
+            for r in client.secret_requests().await? {
+                let status = match r.status {
+                    SecretRequestStatus::Pending => "pending",
+                    SecretRequestStatus::Submitted => "submitted",
+                    SecretRequestStatus::Expired => "expired",
+                };
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    r.id,
+                    status,
+                    r.expires_at,
+                    r.note.as_deref().unwrap_or("-")
+                );
+            }
+        }
+        RequestCommand::Read { id, output } => {
+            // Reading deletes the answer, so check the key before that.
+            let secret = read_identity(identity)?;
+            let own = envelope::public_key_of(&secret)?;
+            let sealed_to = client
+                .secret_requests()
+                .await?
+                .into_iter()
+                .find(|r| r.id == id)
+                .and_then(|r| r.public_key);
+            if sealed_to.as_deref().is_some_and(|k| k != own) {
+                anyhow::bail!("request {} is sealed to another key than your identity file", id);
+            }
+            let mut text = client.read_secret_request(&id).await?.text;
+            // Answers to requests made before sealing are returned in clear.
+            if envelope::is_sealed(&text) {
+                text = envelope::open_sealed(&text, &secret)?;
+            }
+            write_output(&text, output)?;
+        }
+    }
+    Ok(())
+}
+
+fn read_identity(path: Option<&PathBuf>) -> Result<String> {
+    let path = path.context("no identity file; use --identity")?;
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("cannot read identity {} (create one with `shredlink key gen`)", path.display()))?;
+    // Lines starting with '#' are comments, as in the file `key gen` writes.
+    raw.lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(str::to_string)
+        .with_context(|| format!("no secret key in {}", path.display()))
+}
+
+fn key(cmd: KeyCommand, identity: Option<PathBuf>) -> Result<()> {
+    match cmd {
+        KeyCommand::Gen { force } => {
+            let path = identity.context("no identity file; use --identity")?;
+            if path.exists() && !force {
+                anyhow::bail!("{} already exists; use --force to replace it", path.display());
+            }
+            let (secret, public) = envelope::generate_identity();
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir).with_context(|| format!("cannot create {}", dir.display()))?;
+            }
+            let mut opts = std::fs::OpenOptions::new();
+            opts.write(true).create(true).truncate(true);
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut opts, 0o600);
+            let mut file = opts
+                .open(&path)
+                .with_context(|| format!("cannot write {}", path.display()))?;
+            writeln!(file, "# public key: {}\n{}", public, secret)?;
+            eprintln!("wrote {}", path.display());
+            println!("{}", public);
+        }
+        KeyCommand::Show => {
+            let secret = read_identity(identity.as_ref())?;
+            println!("{}", envelope::public_key_of(&secret)?);
+        }
+    }
+    Ok(())
+}
+
+/// Reads a link, prompting for a password if needed, and opens envelopes and sealed content.
+async fn read_text(server: &str, api_key: Option<&str>, identity: Option<&PathBuf>, input: &str) -> Result<String> {
+    let share = parse_share_url(input, server)?;
+    let client = client_for(&share.base, server, api_key)?;
+    let text = match client.read(&share.token).await {
+        Ok(res) => res.text,
+        Err(ApiError::PasswordRequired) => {
+            let password = rpassword::prompt_password("Password: ")?;
+            client.unlock(&share.token, &password).await?.text
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let secret = if envelope::is_sealed(&text) {
+        Some(read_identity(identity)?)
+    } else {
+        None
+    };
+    Ok(open_text_as(text, share.key.as_deref(), secret.as_deref())?)
+}
+
+async fn get(server: &str, api_key: Option<&str>, identity: Option<&PathBuf>, input: &str, output: Option<PathBuf>) -> Result<()> {
+    let text = read_text(server, api_key, identity, input).await?;
+    write_output(&text, output)
+}
+
+async fn split(client: &Client, file: Option<PathBuf>, mut req: SplitRequest, password: bool) -> Result<()> {
+    req.link.text = read_input(file.as_ref())?;
+    if req.link.text.is_empty() {
+        anyhow::bail!("nothing to split: input is empty");
+    }
+    if password {
+        req.link.password = Some(prompt_new_password()?);
+    }
+    let res = client.split(&req).await?;
+    for link in &res.links {
+        println!("{}", link.url);
+    }
+    eprintln!("any {} of these {} links recover the secret", res.threshold, res.links.len());
+    Ok(())
+}
+
+async fn combine(server: &str, api_key: Option<&str>, identity: Option<&PathBuf>, inputs: &[String], output: Option<PathBuf>) -> Result<()> {
+    let mut shares = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        if shamir::is_share(input) {
+            shares.push(input.clone());
+        } else {
+            let text = read_text(server, api_key, identity, input).await?;
+            if !shamir::is_share(&text) {
+                anyhow::bail!("{} does not hold a share", input);
+            }
+            shares.push(text);
+        }
+    }
+    write_output(&shamir::combine(&shares)?, output)
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let config = CliConfig::load(cli.config.as_deref())?;
+    let server = config.server(cli.server.as_deref());
+    let api_key = config.api_key(cli.api_key.as_deref());
+    let identity = config.identity(cli.identity.as_deref());
+    match cli.command {
+        Command::Send {
+            file,
+            once,
+            expire,
+            expires_at,
+            not_before,
+            idle,
+            notify,
+            notify_email,
+            notify_lang,
+            password,
+            password_once,
+            recipient,
+            to,
+            allow,
+            no_encrypt,
+        } => {
+            let client = client_for(&server, &server, api_key.as_deref())?;
+            let req = CreateRequest {
+                expire_minutes: expire,
+                expires_at,
+                not_before,
+                idle_timeout_minutes: idle,
+                notify_webhook: notify,
+                notify_email,
+                notify_lang,
+                one_time_view: once,
+                one_time_password: password_once,
+                recipient_public_key: recipient,
+                recipient_labels: to,
+                allowed_cidrs: allow,
+                ..Default::default()
+            };
+            send(&client, file, req, password, no_encrypt).await
+        }
+        Command::Get { url, output } => get(&server, api_key.as_deref(), identity.as_ref(), &url, output).await,
+        Command::Split {
+            file,
+            shares,
+            threshold,
+            once,
+            expire,
+            password,
+            recipient,
+            allow,
+        } => {
+            let client = client_for(&server, &server, api_key.as_deref())?;
+            let req = SplitRequest {
+                shares,
+                threshold,
+                recipients: recipient,
+                link: CreateRequest {
+                    expire_minutes: expire,
+                    one_time_view: once,
+                    allowed_cidrs: allow,
+                    ..Default::default()
+                },
+            };
+            split(&client, file, req, password).await
+        }
+        Command::Combine { shares, output } => combine(&server, api_key.as_deref(), identity.as_ref(), &shares, output).await,
+        Command::Recipients { token } => recipients(&client_for(&server, &server, api_key.as_deref())?, &token).await,
+        Command::Request(cmd) => request(&client_for(&server, &server, api_key.as_deref())?, identity.as_ref(), cmd).await,
+        Command::Key(cmd) => key(cmd, identity),
+    }
+}
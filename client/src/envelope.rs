@@ -0,0 +1,149 @@
+//! Client-side encryption compatible with the web viewer (`public/envelope.js`): the
+//! server only stores `shredlink:v1:` + base64(nonce || AES-256-GCM ciphertext), and
+//! the key is carried in the URL fragment, which is never sent in HTTP requests.
+//!
+//! Links created with `recipient_public_key` are sealed by the server to an X25519
+//! key instead: `shredlink:seal1:` + base64(ephemeral public key || nonce ||
+//! AES-256-GCM ciphertext), opened here with the recipient's `slsec_` secret key.
+
+#[cfg(not(feature = "encryption"))]
+use crate::error::{Error, Result};
+
+pub const PREFIX: &str = "shredlink:v1:";
+pub const SEALED_PREFIX: &str = "shredlink:seal1:";
+pub const PUBLIC_KEY_PREFIX: &str = "slpub_";
+pub const SECRET_KEY_PREFIX: &str = "slsec_";
+
+/// True if `text` is a client-side encrypted payload.
+pub fn is_envelope(text: &str) -> bool {
+    text.starts_with(PREFIX)
+}
+
+/// True if `text` is sealed to a recipient key.
+pub fn is_sealed(text: &str) -> bool {
+    text.starts_with(SEALED_PREFIX)
+}
+
+#[cfg(feature = "encryption")]
+mod imp {
+    use super::{PREFIX, PUBLIC_KEY_PREFIX, SEALED_PREFIX, SECRET_KEY_PREFIX};
+    use crate::error::{Error, Result};
+    use aes_gcm::{aead::{Aead, KeyInit}, Aes256Gcm};
+    use base64::engine::general_purpose::{STANDARD as BASE64, URL_SAFE_NO_PAD as BASE64_URL};
+    use base64::Engine;
+    use hkdf::Hkdf;
+    use rand_core::{OsRng, RngCore};
+    use sha2::Sha256;
+    use x25519_dalek::{PublicKey, StaticSecret};
+
+    const NONCE_LEN: usize = 12;
+    const SEAL_INFO: &[u8] = b"shredlink seal v1";
+
+    /// Encrypts `plaintext` with a fresh key. Returns (envelope, base64url key).
+    pub fn seal(plaintext: &str) -> Result<(String, String)> {
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+        let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| Error::Crypto(e.to_string()))?;
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        let ciphertext = cipher
+            .encrypt((&nonce).into(), plaintext.as_bytes())
+            .map_err(|e| Error::Crypto(e.to_string()))?;
+        let mut raw = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        raw.extend_from_slice(&nonce);
+        raw.extend_from_slice(&ciphertext);
+        Ok((format!("{}{}", PREFIX, BASE64.encode(raw)), BASE64_URL.encode(key)))
+    }
+
+    /// Decrypts an envelope with the fragment key from the share URL.
+    pub fn open(envelope: &str, key_b64: &str) -> Result<String> {
+        let body = envelope
+            .strip_prefix(PREFIX)
+            .ok_or_else(|| Error::Crypto("not a client-side encrypted payload".to_string()))?;
+        let key = BASE64_URL
+            .decode(key_b64.trim_end_matches('='))
+            .map_err(|_| Error::Crypto("invalid key in URL fragment".to_string()))?;
+        let cipher = Aes256Gcm::new_from_slice(&key)
+            .map_err(|_| Error::Crypto("invalid key in URL fragment".to_string()))?;
+        let raw = BASE64
+            .decode(body)
+            .map_err(|e| Error::Crypto(format!("invalid payload: {}", e)))?;
+        if raw.len() < NONCE_LEN {
+            return Err(Error::Crypto("payload too short".to_string()));
+        }
+        let (nonce, ciphertext) = raw.split_at(NONCE_LEN);
+        let plaintext = cipher
+            .decrypt(aes_gcm::Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| Error::Crypto("decrypt failed (wrong or truncated key?)".to_string()))?;
+        String::from_utf8(plaintext).map_err(|e| Error::Crypto(format!("utf8: {}", e)))
+    }
+
+    fn parse_secret(secret_key: &str) -> Result<StaticSecret> {
+        let bytes: [u8; 32] = secret_key
+            .trim()
+            .strip_prefix(SECRET_KEY_PREFIX)
+            .and_then(|b| BASE64_URL.decode(b).ok())
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| Error::Crypto(format!("secret key must be {}<base64url of 32 bytes>", SECRET_KEY_PREFIX)))?;
+        Ok(StaticSecret::from(bytes))
+    }
+
+    fn encode_public(secret: &StaticSecret) -> String {
+        format!("{}{}", PUBLIC_KEY_PREFIX, BASE64_URL.encode(PublicKey::from(secret).as_bytes()))
+    }
+
+    /// A new recipient key pair. Returns (`slsec_` secret key, `slpub_` public key).
+    pub fn generate_identity() -> (String, String) {
+        let secret = StaticSecret::random_from_rng(OsRng);
+        let public = encode_public(&secret);
+        (format!("{}{}", SECRET_KEY_PREFIX, BASE64_URL.encode(secret.to_bytes())), public)
+    }
+
+    /// The `slpub_` public key that belongs to an `slsec_` secret key.
+    pub fn public_key_of(secret_key: &str) -> Result<String> {
+        Ok(encode_public(&parse_secret(secret_key)?))
+    }
+
+    /// Opens content sealed to the public half of `secret_key`.
+    pub fn open_sealed(sealed: &str, secret_key: &str) -> Result<String> {
+        let secret = parse_secret(secret_key)?;
+        let raw = sealed
+            .strip_prefix(SEALED_PREFIX)
+            .and_then(|b| BASE64.decode(b).ok())
+            .ok_or_else(|| Error::Crypto("not a sealed payload".to_string()))?;
+        if raw.len() < 32 + NONCE_LEN {
+            return Err(Error::Crypto("payload too short".to_string()));
+        }
+        let (ephemeral, rest) = raw.split_at(32);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let ephemeral: [u8; 32] = ephemeral.try_into().expect("split at 32");
+        let shared = secret.diffie_hellman(&PublicKey::from(ephemeral));
+        let mut salt = [0u8; 64];
+        salt[..32].copy_from_slice(&ephemeral);
+        salt[32..].copy_from_slice(PublicKey::from(&secret).as_bytes());
+        let mut key = [0u8; 32];
+        Hkdf::<Sha256>::new(Some(&salt), shared.as_bytes())
+            .expand(SEAL_INFO, &mut key)
+            .map_err(|e| Error::Crypto(e.to_string()))?;
+        let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| Error::Crypto(e.to_string()))?;
+        let plaintext = cipher
+            .decrypt(aes_gcm::Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| Error::Crypto("decrypt failed (sealed to a different key?)".to_string()))?;
+        String::from_utf8(plaintext).map_err(|e| Error::Crypto(format!("utf8: {}", e)))
+    }
+}
+
+#[cfg(feature = "encryption")]
+pub use imp::{generate_identity, open, open_sealed, public_key_of, seal};
+
+/// Without the `encryption` feature envelopes cannot be opened.
+#[cfg(not(feature = "encryption"))]
+pub fn open(_envelope: &str, _key_b64: &str) -> Result<String> {
+    Err(Error::Crypto("built without the `encryption` feature".to_string()))
+}
+
+/// Without the `encryption` feature sealed content cannot be opened.
+#[cfg(not(feature = "encryption"))]
+pub fn open_sealed(_sealed: &str, _secret_key: &str) -> Result<String> {
+    Err(Error::Crypto("built without the `encryption` feature".to_string()))
+}
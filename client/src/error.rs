@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+/// Errors returned by [`crate::Client`]. API failures are mapped from the
+/// server's `ErrorResponse` and HTTP status.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("http: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("invalid URL: {0}")]
+    InvalidUrl(String),
+    #[error("bad request: {0}")]
+    BadRequest(String),
+    #[error("link not found: {0}")]
+    NotFound(String),
+    #[error("link expired or already used: {0}")]
+    Gone(String),
+    #[error("link is password-protected")]
+    PasswordRequired,
+    #[error("wrong password: {0}")]
+    WrongPassword(String),
+    #[error("payload too large: {0}")]
+    PayloadTooLarge(String),
+    /// The request breaks the server's creation policy (see [`crate::Client::policy`]).
+    #[error("rejected by server policy: {0}")]
+    PolicyViolation(String),
+    /// The link is scheduled (`not_before`) and goes live after `retry_after`.
+    #[error("link not available yet: {message}")]
+    NotYetAvailable { message: String, retry_after: Option<Duration> },
+    #[error("rate limited")]
+    RateLimited { retry_after: Option<Duration> },
+    #[error("server error ({status}): {message}")]
+    Server { status: u16, message: String },
+    #[error("crypto: {0}")]
+    Crypto(String),
+    /// Share texts passed to [`crate::shamir::combine`] can't be recombined.
+    #[error("shares: {0}")]
+    Share(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
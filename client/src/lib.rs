@@ -0,0 +1,352 @@
+//! Async client for the ShredLink HTTP API.
+//!
+//! ```no_run
+//! # async fn demo() -> shredlink_client::Result<()> {
+//! use shredlink_client::{Client, CreateRequest};
+//!
+//! let client = Client::new("https://shredlink.example.com")?;
+//! let link = client
+//!     .create(&CreateRequest { text: "hunter2".into(), one_time_view: true, ..Default::default() })
+//!     .await?;
+//! let text = client.fetch(&link.url, None).await?;
+//! # Ok(()) }
+//! ```
+
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+
+pub mod envelope;
+mod error;
+pub mod pow;
+pub mod shamir;
+
+pub use error::{Error, Result};
+pub use shredlink_types::{
+    CreatePolicy, CreateRequest, CreateResponse, CreateSecretRequest, ErrorResponse, RecipientLink, RecipientStatus, SecretRequestCreated, SecretRequestStatus,
+    SecretRequestSummary, PowChallenge, SplitRequest, SplitResponse, UnlockRequest, UnlockResponse,
+};
+
+const DEFAULT_MAX_RETRIES: u32 = 3;
+/// Fresh proof-of-work challenges solved for one call before giving up.
+const MAX_POW_ATTEMPTS: u32 = 3;
+/// Upper bound on a single wait, whatever the server's `Retry-After` says.
+const MAX_RETRY_WAIT: Duration = Duration::from_secs(60);
+
+/// A share URL split into its parts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareUrl {
+    /// Server base URL, e.g. `https://shredlink.example.com`.
+    pub base: String,
+    pub token: String,
+    /// Client-side encryption key from the `#fragment`, if any.
+    pub key: Option<String>,
+}
+
+impl ShareUrl {
+    /// Parses `https://host[/prefix]/s/<token>[#key]`.
+    pub fn parse(input: &str) -> Result<Self> {
+        let url = url::Url::parse(input).map_err(|e| Error::InvalidUrl(e.to_string()))?;
+        let path = url.path();
+        let idx = path
+            .rfind("/s/")
+            .ok_or_else(|| Error::InvalidUrl("expected /s/<token>".to_string()))?;
+        let token = path[idx + 3..].trim_end_matches('/').to_string();
+        if token.is_empty() {
+            return Err(Error::InvalidUrl("missing token".to_string()));
+        }
+        let mut base = url.origin().ascii_serialization();
+        base.push_str(&path[..idx]);
+        let key = url.fragment().filter(|f| !f.is_empty()).map(str::to_string);
+        Ok(Self { base, token, key })
+    }
+}
+
+/// Result of [`Client::create_encrypted`].
+#[cfg(feature = "encryption")]
+#[derive(Debug, Clone)]
+pub struct SealedLink {
+    pub token: String,
+    /// Share URL including the `#key` fragment needed to decrypt.
+    pub url: String,
+    pub key: String,
+    /// Per-recipient links of a multi-recipient link, their URLs including the `#key` fragment.
+    pub recipients: Vec<RecipientLink>,
+    /// Signing secret for events sent to `notify_webhook`, if one was set.
+    pub webhook_secret: Option<String>,
+}
+
+pub struct ClientBuilder {
+    base: String,
+    max_retries: u32,
+    timeout: Option<Duration>,
+    user_agent: String,
+    api_key: Option<String>,
+}
+
+impl ClientBuilder {
+    /// Retries after HTTP 429 (default 3). `0` disables retrying.
+    pub fn max_retries(mut self, n: u32) -> Self {
+        self.max_retries = n;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn user_agent(mut self, ua: impl Into<String>) -> Self {
+        self.user_agent = ua.into();
+        self
+    }
+
+    /// Sends `Authorization: Bearer <key>` so requests count against the key's
+    /// quota instead of the per-IP limit.
+    pub fn api_key(mut self, key: impl Into<String>) -> Self {
+        self.api_key = Some(key.into());
+        self
+    }
+
+    pub fn build(self) -> Result<Client> {
+        url::Url::parse(&self.base).map_err(|e| Error::InvalidUrl(e.to_string()))?;
+        let mut http = reqwest::Client::builder().user_agent(self.user_agent);
+        if let Some(t) = self.timeout {
+            http = http.timeout(t);
+        }
+        Ok(Client {
+            http: http.build()?,
+            base: self.base.trim_end_matches('/').to_string(),
+            max_retries: self.max_retries,
+            api_key: self.api_key,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base: String,
+    max_retries: u32,
+    api_key: Option<String>,
+}
+
+fn retry_after(res: &Response) -> Option<Duration> {
+    res.headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+async fn error_from(res: Response) -> Error {
+    let status = res.status();
+    let retry_after = retry_after(&res);
+    let message = match res.json::<ErrorResponse>().await {
+        Ok(e) => e.error,
+        Err(_) => status.canonical_reason().unwrap_or("error").to_string(),
+    };
+    match status {
+        StatusCode::BAD_REQUEST => Error::BadRequest(message),
+        StatusCode::UNAUTHORIZED => Error::WrongPassword(message),
+        // Scope errors are 403 too, but only scheduled links carry Retry-After.
+        StatusCode::FORBIDDEN if retry_after.is_some() => Error::NotYetAvailable { message, retry_after },
+        StatusCode::NOT_FOUND => Error::NotFound(message),
+        StatusCode::GONE => Error::Gone(message),
+        StatusCode::PAYLOAD_TOO_LARGE => Error::PayloadTooLarge(message),
+        StatusCode::UNPROCESSABLE_ENTITY => Error::PolicyViolation(message),
+        StatusCode::TOO_MANY_REQUESTS => Error::RateLimited { retry_after },
+        _ => Error::Server {
+            status: status.as_u16(),
+            message,
+        },
+    }
+}
+
+impl Client {
+    pub fn new(base_url: &str) -> Result<Self> {
+        Self::builder(base_url).build()
+    }
+
+    pub fn builder(base_url: &str) -> ClientBuilder {
+        ClientBuilder {
+            base: base_url.to_string(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            timeout: None,
+            user_agent: concat!("shredlink-client/", env!("CARGO_PKG_VERSION")).to_string(),
+            api_key: None,
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base
+    }
+
+    fn post(&self, url: &str) -> RequestBuilder {
+        self.authed(self.http.post(url))
+    }
+
+    fn authed(&self, req: RequestBuilder) -> RequestBuilder {
+        match &self.api_key {
+            Some(key) => req.bearer_auth(key),
+            None => req,
+        }
+    }
+
+    /// Sends the request, retrying on 429 after `Retry-After` (or exponential backoff),
+    /// and on 428 with a solved proof-of-work challenge.
+    async fn send<T: DeserializeOwned>(&self, build: impl Fn() -> RequestBuilder) -> Result<T> {
+        let mut attempt = 0;
+        let mut pow_attempt = 0;
+        let mut proof: Option<String> = None;
+        loop {
+            let req = match &proof {
+                Some(p) => build().header(pow::POW_HEADER, p),
+                None => build(),
+            };
+            let res = req.send().await?;
+            if res.status().is_success() {
+                return Ok(res.json().await?);
+            }
+            if res.status() == StatusCode::PRECONDITION_REQUIRED && pow_attempt < MAX_POW_ATTEMPTS {
+                let challenge: PowChallenge = res.json().await?;
+                proof = Some(pow::solve(&challenge.challenge, challenge.difficulty));
+                pow_attempt += 1;
+                continue;
+            }
+            if res.status() == StatusCode::TOO_MANY_REQUESTS && attempt < self.max_retries {
+                let wait = retry_after(&res)
+                    .unwrap_or_else(|| Duration::from_secs(1 << attempt.min(5)))
+                    .min(MAX_RETRY_WAIT);
+                tokio::time::sleep(wait).await;
+                attempt += 1;
+                continue;
+            }
+            return Err(error_from(res).await);
+        }
+    }
+
+    /// `GET /api/v1/policy`: the creation rules that apply to this client.
+    pub async fn policy(&self) -> Result<CreatePolicy> {
+        let url = format!("{}/api/v1/policy", self.base);
+        self.send(|| self.authed(self.http.get(&url))).await
+    }
+
+    /// `POST /api/v1/create`.
+    pub async fn create(&self, req: &CreateRequest) -> Result<CreateResponse> {
+        let url = format!("{}/api/v1/create", self.base);
+        self.send(|| self.post(&url).json(req)).await
+    }
+
+    /// `POST /api/v1/split`: one link per share; recombine with [`shamir::combine`].
+    pub async fn split(&self, req: &SplitRequest) -> Result<SplitResponse> {
+        let url = format!("{}/api/v1/split", self.base);
+        self.send(|| self.post(&url).json(req)).await
+    }
+
+    /// `GET /api/v1/links/{token}/recipients`: which recipients of a multi-recipient
+    /// link have read it. Needs the API key (or session) that created the link.
+    pub async fn recipients(&self, token: &str) -> Result<Vec<RecipientStatus>> {
+        let url = format!("{}/api/v1/links/{}/recipients", self.base, token);
+        self.send(|| self.authed(self.http.get(&url))).await
+    }
+
+    /// Encrypts `req.text` locally before creating the link; the returned URL
+    /// carries the key in its fragment.
+    #[cfg(feature = "encryption")]
+    pub async fn create_encrypted(&self, mut req: CreateRequest) -> Result<SealedLink> {
+        let (sealed, key) = envelope::seal(&req.text)?;
+        req.text = sealed;
+        let res = self.create(&req).await?;
+        let recipients = res
+            .recipients
+            .into_iter()
+            .map(|r| RecipientLink {
+                url: format!("{}#{}", r.url, key),
+                ..r
+            })
+            .collect();
+        Ok(SealedLink {
+            url: format!("{}#{}", res.url, key),
+            token: res.token,
+            key,
+            recipients,
+            webhook_secret: res.webhook_secret,
+        })
+    }
+
+    /// `POST /api/v1/read/{token}`. Returns [`Error::PasswordRequired`] for protected links.
+    pub async fn read(&self, token: &str) -> Result<UnlockResponse> {
+        let url = format!("{}/api/v1/read/{}", self.base, token);
+        match self.send(|| self.post(&url)).await {
+            Err(Error::WrongPassword(_)) => Err(Error::PasswordRequired),
+            other => other,
+        }
+    }
+
+    /// `POST /api/v1/unlock/{token}`.
+    pub async fn unlock(&self, token: &str, password: &str) -> Result<UnlockResponse> {
+        let url = format!("{}/api/v1/unlock/{}", self.base, token);
+        let body = UnlockRequest {
+            password: password.to_string(),
+        };
+        self.send(|| self.post(&url).json(&body)).await
+    }
+
+    /// `POST /api/v1/requests`: asks someone to send you a secret. Needs an API key.
+    pub async fn create_secret_request(&self, req: &CreateSecretRequest) -> Result<SecretRequestCreated> {
+        let url = format!("{}/api/v1/requests", self.base);
+        self.send(|| self.post(&url).json(req)).await
+    }
+
+    /// `GET /api/v1/requests`: the secret requests owned by this client's API key.
+    pub async fn secret_requests(&self) -> Result<Vec<SecretRequestSummary>> {
+        let url = format!("{}/api/v1/requests", self.base);
+        self.send(|| self.authed(self.http.get(&url))).await
+    }
+
+    /// `POST /api/v1/requests/{id}/read`: returns the answer, which the server then deletes.
+    pub async fn read_secret_request(&self, id: &str) -> Result<UnlockResponse> {
+        let url = format!("{}/api/v1/requests/{}/read", self.base, id);
+        self.send(|| self.post(&url)).await
+    }
+
+    /// Reads a share URL (on this client's server or any other) and opens
+    /// client-side envelopes with the fragment key. Pass `password` for protected links.
+    /// Content sealed to a recipient key is returned sealed; see [`open_text_as`].
+    pub async fn fetch(&self, share_url: &str, password: Option<&str>) -> Result<String> {
+        let share = ShareUrl::parse(share_url)?;
+        // Only send the API key to the server it belongs to.
+        let api_key = self.api_key.clone().filter(|_| share.base == self.base);
+        let client = Client {
+            http: self.http.clone(),
+            base: share.base,
+            max_retries: self.max_retries,
+            api_key,
+        };
+        let text = match password {
+            Some(p) => client.unlock(&share.token, p).await?.text,
+            None => client.read(&share.token).await?.text,
+        };
+        open_text(text, share.key.as_deref())
+    }
+}
+
+/// Opens content sealed to a recipient key with `secret_key` (`slsec_...`), then any
+/// envelope inside it with the fragment key. Other text is handled like [`open_text`].
+pub fn open_text_as(text: String, key: Option<&str>, secret_key: Option<&str>) -> Result<String> {
+    if !envelope::is_sealed(&text) {
+        return open_text(text, key);
+    }
+    let secret_key = secret_key.ok_or_else(|| Error::Crypto("content is sealed to a recipient key; no secret key given".to_string()))?;
+    open_text(envelope::open_sealed(&text, secret_key)?, key)
+}
+
+/// Decrypts `text` if it is an envelope, otherwise returns it unchanged.
+pub fn open_text(text: String, key: Option<&str>) -> Result<String> {
+    if !envelope::is_envelope(&text) {
+        return Ok(text);
+    }
+    let key = key.ok_or_else(|| Error::Crypto("content is encrypted but the URL has no #key fragment".to_string()))?;
+    envelope::open(&text, key)
+}
@@ -0,0 +1,33 @@
+//! Proof-of-work for servers with `POW_ENABLED`: anonymous create, split and
+//! unlock requests are answered with 428 and a challenge, which
+//! [`crate::Client`] solves and retries automatically. Compatible with
+//! `public/pow.js`.
+
+use sha2::{Digest, Sha256};
+
+/// Request header carrying `{challenge}:{solution}`.
+pub const POW_HEADER: &str = "X-ShredLink-PoW";
+
+fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut bits = 0;
+    for &byte in hash {
+        if byte != 0 {
+            return bits + byte.leading_zeros();
+        }
+        bits += 8;
+    }
+    bits
+}
+
+/// Finds a solution for `challenge` and returns the header value. Takes about
+/// 2^`difficulty` hashes, so this blocks for a noticeable time at high difficulty.
+pub fn solve(challenge: &str, difficulty: u8) -> String {
+    let mut counter: u64 = 0;
+    loop {
+        let value = format!("{}:{:x}", challenge, counter);
+        if leading_zero_bits(&Sha256::digest(value.as_bytes())) >= u32::from(difficulty) {
+            return value;
+        }
+        counter += 1;
+    }
+}
@@ -0,0 +1,194 @@
+//! Recombines the Shamir shares created by `POST /api/v1/split` (see
+//! [`crate::Client::split`]). Each share link holds one text of the form
+//! `shredlink:share1:{set}:{threshold}:{x}:{base64 y-bytes}`; any `threshold` of
+//! them from the same set rebuild the secret. Compatible with `public/combine.js`.
+
+use crate::error::{Error, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+pub const SHARE_PREFIX: &str = "shredlink:share1:";
+
+/// True if `text` is a share created by a split.
+pub fn is_share(text: &str) -> bool {
+    text.starts_with(SHARE_PREFIX)
+}
+
+struct Share {
+    set: String,
+    threshold: u8,
+    x: u8,
+    y: Vec<u8>,
+}
+
+fn parse(text: &str) -> Result<Share> {
+    let invalid = || Error::Share("not a share".to_string());
+    let rest = text.trim().strip_prefix(SHARE_PREFIX).ok_or_else(invalid)?;
+    let parts: Vec<&str> = rest.split(':').collect();
+    let [set, threshold, x, y] = parts[..] else {
+        return Err(invalid());
+    };
+    let threshold: u8 = threshold.parse().map_err(|_| invalid())?;
+    let x: u8 = x.parse().map_err(|_| invalid())?;
+    if threshold < 2 || x == 0 {
+        return Err(invalid());
+    }
+    let y = BASE64.decode(y).map_err(|_| invalid())?;
+    Ok(Share {
+        set: set.to_string(),
+        threshold,
+        x,
+        y,
+    })
+}
+
+/// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x + 1.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = 0u8.wrapping_sub(a >> 7);
+        a = (a << 1) ^ (carry & 0x1b);
+        b >>= 1;
+    }
+    product
+}
+
+/// a^254, the multiplicative inverse of a non-zero `a`.
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1u8;
+    let mut base = a;
+    let mut e = 254u8;
+    while e > 0 {
+        if e & 1 == 1 {
+            result = gf_mul(result, base);
+        }
+        base = gf_mul(base, base);
+        e >>= 1;
+    }
+    result
+}
+
+/// Rebuilds the secret from share texts. Fails if they come from different
+/// splits, repeat a share, or are fewer than the threshold.
+pub fn combine(shares: &[String]) -> Result<String> {
+    let shares = shares.iter().map(|s| parse(s)).collect::<Result<Vec<_>>>()?;
+    let first = shares.first().ok_or_else(|| Error::Share("no shares given".to_string()))?;
+    for (i, share) in shares.iter().enumerate() {
+        if share.set != first.set || share.threshold != first.threshold || share.y.len() != first.y.len() {
+            return Err(Error::Share("shares belong to different secrets".to_string()));
+        }
+        if shares[..i].iter().any(|s| s.x == share.x) {
+            return Err(Error::Share("the same share was given twice".to_string()));
+        }
+    }
+    let threshold = first.threshold as usize;
+    if shares.len() < threshold {
+        return Err(Error::Share(format!("need {} shares, got {}", threshold, shares.len())));
+    }
+
+    // Lagrange interpolation at x = 0.
+    let used = &shares[..threshold];
+    let mut secret = vec![0u8; first.y.len()];
+    for (i, si) in used.iter().enumerate() {
+        let basis = used
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .fold(1u8, |acc, (_, sj)| gf_mul(acc, gf_mul(sj.x, gf_inv(sj.x ^ si.x))));
+        for (out, &y) in secret.iter_mut().zip(&si.y) {
+            *out ^= gf_mul(y, basis);
+        }
+    }
+    String::from_utf8(secret).map_err(|_| Error::Share("could not rebuild the secret".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Splits like the server does, with fixed coefficients so failures reproduce.
+    fn split(secret: &str, shares: u8, threshold: u8) -> Vec<String> {
+        let mut ys = vec![Vec::new(); shares as usize];
+        for (n, &byte) in secret.as_bytes().iter().enumerate() {
+            let coefficients: Vec<u8> = (0..threshold)
+                .map(|c| if c == 0 { byte } else { (n as u8).wrapping_mul(31).wrapping_add(c * 17) | 1 })
+                .collect();
+            for (i, y) in ys.iter_mut().enumerate() {
+                let x = i as u8 + 1;
+                y.push(coefficients.iter().rev().fold(0u8, |acc, &c| gf_mul(acc, x) ^ c));
+            }
+        }
+        ys.iter()
+            .enumerate()
+            .map(|(i, y)| format!("{}abcd1234:{}:{}:{}", SHARE_PREFIX, threshold, i + 1, BASE64.encode(y)))
+            .collect()
+    }
+
+    /// Every subset of `shares` with `k` members.
+    fn subsets(shares: &[String], k: usize) -> Vec<Vec<String>> {
+        (0u32..1 << shares.len())
+            .filter(|m| m.count_ones() as usize == k)
+            .map(|m| {
+                shares
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| m & (1 << i) != 0)
+                    .map(|(_, s)| s.clone())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn gf_inv_inverts_every_nonzero_element() {
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1, "a={}", a);
+        }
+    }
+
+    #[test]
+    fn any_threshold_shares_recover_the_secret() {
+        let secret = "hunter2 — گذرواژه";
+        for (n, k) in [(2u8, 2u8), (3, 2), (5, 3), (6, 6)] {
+            let shares = split(secret, n, k);
+            for subset in subsets(&shares, k as usize) {
+                assert_eq!(combine(&subset).unwrap(), secret, "n={} k={}", n, k);
+            }
+            // Extra shares beyond the threshold are fine too.
+            assert_eq!(combine(&shares).unwrap(), secret);
+        }
+    }
+
+    #[test]
+    fn fewer_than_threshold_shares_are_rejected() {
+        let shares = split("secret", 5, 3);
+        for subset in subsets(&shares, 2) {
+            assert!(matches!(combine(&subset), Err(Error::Share(m)) if m == "need 3 shares, got 2"));
+        }
+        assert!(matches!(combine(&[]), Err(Error::Share(_))));
+    }
+
+    #[test]
+    fn duplicate_share_indexes_are_rejected() {
+        let shares = split("secret", 3, 2);
+        let twice = vec![shares[0].clone(), shares[0].clone()];
+        assert!(matches!(combine(&twice), Err(Error::Share(m)) if m == "the same share was given twice"));
+        // Same index with different bytes is still the same share position.
+        let forged = shares[1].replace(":2:2:", ":2:1:");
+        assert!(matches!(combine(&[shares[0].clone(), forged]), Err(Error::Share(_))));
+    }
+
+    #[test]
+    fn shares_from_different_splits_are_rejected() {
+        let a = split("secret", 3, 2);
+        let b = split("secret", 3, 2)[1].replace("abcd1234", "ffff0000");
+        assert!(matches!(combine(&[a[0].clone(), b]), Err(Error::Share(m)) if m == "shares belong to different secrets"));
+    }
+
+    #[test]
+    fn malformed_shares_are_rejected() {
+        for text in ["", "shredlink:share1:abcd:2:0:AA==", "shredlink:share1:abcd:1:1:AA==", "shredlink:share1:abcd:2:1"] {
+            assert!(matches!(combine(&[text.to_string()]), Err(Error::Share(_))), "{:?}", text);
+        }
+    }
+}
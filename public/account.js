@@ -0,0 +1,545 @@
+(function () {
+  'use strict';
+
+  const authSection = document.getElementById('auth-section');
+  const dashboardSection = document.getElementById('dashboard-section');
+  const authForm = document.getElementById('auth-form');
+  const authError = document.getElementById('auth-error');
+  const loginBtn = document.getElementById('login-btn');
+  const registerBtn = document.getElementById('register-btn');
+  const logoutBtn = document.getElementById('logout-btn');
+  const accountName = document.getElementById('account-name');
+  const linksTable = document.getElementById('links-table');
+  const linksBody = document.getElementById('links-body');
+  const noLinks = document.getElementById('no-links');
+  const ssoSection = document.getElementById('sso-section');
+  const ssoError = document.getElementById('sso-error');
+  const requestForm = document.getElementById('request-form');
+  const requestBtn = document.getElementById('request-btn');
+  const requestError = document.getElementById('request-error');
+  const requestCreated = document.getElementById('request-created');
+  const requestUrl = document.getElementById('request-url');
+  const requestAnswer = document.getElementById('request-answer');
+  const requestAnswerText = document.getElementById('request-answer-text');
+  const requestsTable = document.getElementById('requests-table');
+  const requestsBody = document.getElementById('requests-body');
+  const noRequests = document.getElementById('no-requests');
+  const keyRegistered = document.getElementById('key-registered');
+  const keyPublic = document.getElementById('key-public');
+  const keyBrowserMissing = document.getElementById('key-browser-missing');
+  const keyError = document.getElementById('key-error');
+  const keyForm = document.getElementById('key-form');
+  const keyInput = document.getElementById('key-input');
+  const keyGenerateBtn = document.getElementById('key-generate-btn');
+  const keyRemoveBtn = document.getElementById('key-remove-btn');
+  const emailVerified = document.getElementById('email-verified');
+  const emailCurrent = document.getElementById('email-current');
+  const emailError = document.getElementById('email-error');
+  const emailForm = document.getElementById('email-form');
+  const emailSendBtn = document.getElementById('email-send-btn');
+  const emailConfirmForm = document.getElementById('email-confirm-form');
+  const emailConfirmBtn = document.getElementById('email-confirm-btn');
+
+  var links = [];
+  var requests = [];
+  var publicKey = null;
+  var accountEmail = null;
+
+  function tr(key) {
+    return window.i18n ? window.i18n.t(key) : key;
+  }
+
+  function showAuth() {
+    dashboardSection.classList.add('hidden');
+    authSection.classList.remove('hidden');
+  }
+
+  function showDashboard(account) {
+    accountName.textContent = account.username;
+    publicKey = account.public_key || null;
+    renderKey();
+    accountEmail = account.email || null;
+    renderEmail();
+    authSection.classList.add('hidden');
+    dashboardSection.classList.remove('hidden');
+  }
+
+  function formatDate(value) {
+    if (!value) return tr('never');
+    var d = new Date(value);
+    return isNaN(d) ? value : d.toLocaleString(window.i18n && window.i18n.lang() === 'fa' ? 'fa-IR' : undefined);
+  }
+
+  function statusBadge(status) {
+    var span = document.createElement('span');
+    var styles = {
+      active: 'bg-emerald-50 text-emerald-700 dark:bg-emerald-900/30 dark:text-emerald-400',
+      scheduled: 'bg-sky-50 text-sky-700 dark:bg-sky-900/30 dark:text-sky-400',
+      expired: 'bg-slate-100 text-slate-600 dark:bg-slate-700 dark:text-slate-300',
+      consumed: 'bg-amber-50 text-amber-700 dark:bg-amber-900/30 dark:text-amber-400',
+      pending: 'bg-sky-50 text-sky-700 dark:bg-sky-900/30 dark:text-sky-400',
+      submitted: 'bg-emerald-50 text-emerald-700 dark:bg-emerald-900/30 dark:text-emerald-400',
+    };
+    var keys = {
+      active: 'statusActive',
+      scheduled: 'statusScheduled',
+      expired: 'statusExpired',
+      consumed: 'statusConsumed',
+      pending: 'statusPending',
+      submitted: 'statusSubmitted',
+    };
+    span.className = 'inline-block px-2 py-0.5 rounded-lg text-xs font-medium ' + (styles[status] || styles.expired);
+    span.textContent = tr(keys[status] || status);
+    return span;
+  }
+
+  function cell(content) {
+    var td = document.createElement('td');
+    td.className = 'py-2.5 pe-3 align-top';
+    if (content instanceof Node) td.appendChild(content);
+    else td.textContent = content;
+    return td;
+  }
+
+  function render() {
+    linksBody.textContent = '';
+    noLinks.classList.toggle('hidden', links.length > 0);
+    linksTable.classList.toggle('hidden', links.length === 0);
+    links.forEach(function (link) {
+      var row = document.createElement('tr');
+      row.className = 'border-b border-slate-100 dark:border-slate-700/60';
+
+      var linkCell = document.createElement('div');
+      var code = document.createElement('code');
+      code.className = 'font-mono text-xs break-all';
+      code.textContent = link.link_id;
+      linkCell.appendChild(code);
+      var tags = [];
+      if (link.password_protected) tags.push(tr('tagPassword'));
+      if (link.one_time_view || link.one_time_password) tags.push(tr('tagOneTime'));
+      if (link.idle_timeout_minutes) tags.push(tr('tagIdle') + ' ' + link.idle_timeout_minutes + 'm');
+      if (link.allowed_cidrs && link.allowed_cidrs.length) tags.push(tr('tagNetworks') + ' ' + link.allowed_cidrs.join(', '));
+      var recipients = link.recipients || [];
+      if (recipients.length) tags.push(recipients.length + ' ' + tr('tagRecipients'));
+      if (tags.length) {
+        var tagLine = document.createElement('div');
+        tagLine.className = 'text-xs text-slate-500 dark:text-slate-400';
+        tagLine.textContent = tags.join(' · ');
+        linkCell.appendChild(tagLine);
+      }
+      recipients.forEach(function (r) {
+        var line = document.createElement('div');
+        line.className = 'text-xs ' + (r.viewed ? 'text-emerald-700 dark:text-emerald-400' : 'text-slate-500 dark:text-slate-400');
+        line.textContent = r.label + ': ' + (r.viewed ? tr('recipientOpened') + ' ' + formatDate(r.viewed_at) : tr('recipientNotOpened'));
+        linkCell.appendChild(line);
+      });
+      row.appendChild(cell(linkCell));
+      var statusCell = document.createElement('div');
+      statusCell.appendChild(statusBadge(link.status));
+      if (link.status === 'scheduled' && link.not_before) {
+        var from = document.createElement('div');
+        from.className = 'text-xs text-slate-500 dark:text-slate-400';
+        from.textContent = formatDate(link.not_before);
+        statusCell.appendChild(from);
+      }
+      row.appendChild(cell(statusCell));
+      row.appendChild(cell(String(link.views)));
+      row.appendChild(cell(formatDate(link.expires_at)));
+      row.appendChild(cell(formatDate(link.created_at)));
+
+      var revokeBtn = document.createElement('button');
+      revokeBtn.type = 'button';
+      revokeBtn.className = 'text-sm text-red-600 dark:text-red-400 hover:underline';
+      revokeBtn.textContent = tr('revoke');
+      revokeBtn.addEventListener('click', function () { revoke(link.link_id); });
+      row.appendChild(cell(revokeBtn));
+
+      linksBody.appendChild(row);
+    });
+  }
+
+  function actionButton(label, className, onClick) {
+    var btn = document.createElement('button');
+    btn.type = 'button';
+    btn.className = 'text-sm hover:underline ' + className;
+    btn.textContent = label;
+    btn.addEventListener('click', onClick);
+    return btn;
+  }
+
+  function renderRequests() {
+    requestsBody.textContent = '';
+    noRequests.classList.toggle('hidden', requests.length > 0);
+    requestsTable.classList.toggle('hidden', requests.length === 0);
+    requests.forEach(function (req) {
+      var row = document.createElement('tr');
+      row.className = 'border-b border-slate-100 dark:border-slate-700/60';
+
+      var info = document.createElement('div');
+      var note = document.createElement('div');
+      note.dir = 'auto';
+      note.className = 'break-words';
+      note.textContent = req.note || '—';
+      info.appendChild(note);
+      var url = document.createElement('code');
+      url.className = 'font-mono text-xs text-slate-500 dark:text-slate-400 break-all';
+      url.textContent = req.url;
+      info.appendChild(url);
+      row.appendChild(cell(info));
+      row.appendChild(cell(statusBadge(req.status)));
+      row.appendChild(cell(formatDate(req.expires_at)));
+
+      var actions = document.createElement('div');
+      actions.className = 'flex gap-3';
+      if (req.status === 'submitted') {
+        actions.appendChild(actionButton(tr('requestRead'), 'text-indigo-600 dark:text-indigo-400', function () { readRequest(req.id); }));
+      }
+      actions.appendChild(actionButton(tr('revoke'), 'text-red-600 dark:text-red-400', function () { deleteRequest(req.id); }));
+      row.appendChild(cell(actions));
+
+      requestsBody.appendChild(row);
+    });
+  }
+
+  async function loadRequests() {
+    var res = await fetch('/api/v1/requests', { credentials: 'same-origin' });
+    requests = res.ok ? await res.json() : [];
+    renderRequests();
+  }
+
+  function showRequestError(msg) {
+    requestError.textContent = msg;
+    requestError.classList.remove('hidden');
+  }
+
+  async function readRequest(id) {
+    requestError.classList.add('hidden');
+    // Reading deletes the answer, so make sure this browser can open it first.
+    var req = requests.find(function (r) { return r.id === id; });
+    if (req && req.public_key && (await window.shredlinkEnvelope.identityPublicKey()) !== req.public_key) {
+      showRequestError(tr('requestKeyMissing'));
+      return;
+    }
+    if (!window.confirm(tr('requestReadConfirm'))) return;
+    var res = await fetch('/api/v1/requests/' + encodeURIComponent(id) + '/read', {
+      method: 'POST',
+      credentials: 'same-origin',
+    });
+    var data = await res.json().catch(function () { return {}; });
+    if (res.ok) {
+      try {
+        requestAnswerText.textContent = window.shredlinkEnvelope.isSealed(data.text)
+          ? await window.shredlinkEnvelope.open(data.text, '')
+          : data.text;
+      } catch (err) {
+        showRequestError(tr('requestKeyMissing'));
+        return;
+      }
+      requestAnswer.classList.remove('hidden');
+    } else if (res.status === 401) {
+      showAuth();
+      return;
+    }
+    await loadRequests();
+  }
+
+  async function deleteRequest(id) {
+    if (!window.confirm(tr('requestDeleteConfirm'))) return;
+    await fetch('/api/v1/requests/' + encodeURIComponent(id), { method: 'DELETE', credentials: 'same-origin' });
+    await loadRequests();
+  }
+
+  requestForm.addEventListener('submit', async function (e) {
+    e.preventDefault();
+    requestError.classList.add('hidden');
+    requestBtn.disabled = true;
+    try {
+      var note = document.getElementById('request-note').value.trim();
+      var res = await fetch('/api/v1/requests', {
+        method: 'POST',
+        credentials: 'same-origin',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify(note ? { note: note } : {}),
+      });
+      var data = await res.json().catch(function () { return {}; });
+      if (res.ok) {
+        requestForm.reset();
+        requestUrl.value = data.url;
+        requestCreated.classList.remove('hidden');
+        requestUrl.select();
+        await loadRequests();
+      } else if (res.status === 401) {
+        showAuth();
+      } else {
+        showRequestError(data.error || tr('errorGeneric'));
+      }
+    } catch (err) {}
+    requestBtn.disabled = false;
+  });
+
+  function renderKey() {
+    keyRegistered.classList.toggle('hidden', !publicKey);
+    keyRemoveBtn.classList.toggle('hidden', !publicKey);
+    keyPublic.value = publicKey || '';
+    keyBrowserMissing.classList.add('hidden');
+    if (!publicKey) return;
+    window.shredlinkEnvelope.identityPublicKey().then(function (own) {
+      keyBrowserMissing.classList.toggle('hidden', own === publicKey);
+    });
+  }
+
+  function showKeyError(msg) {
+    keyError.textContent = msg;
+    keyError.classList.remove('hidden');
+  }
+
+  async function registerKey(key) {
+    var res = await fetch('/api/v1/account/public-key', {
+      method: 'PUT',
+      credentials: 'same-origin',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ public_key: key }),
+    });
+    var data = await res.json().catch(function () { return {}; });
+    if (res.status === 401) {
+      showAuth();
+      return;
+    }
+    if (!res.ok) {
+      showKeyError(data.error || tr('errorGeneric'));
+      return;
+    }
+    publicKey = data.public_key;
+    renderKey();
+  }
+
+  // A pasted secret key is kept in this browser; only its public half is sent.
+  keyForm.addEventListener('submit', async function (e) {
+    e.preventDefault();
+    keyError.classList.add('hidden');
+    var value = keyInput.value.trim();
+    if (!value) return;
+    try {
+      if (value.indexOf('slsec_') === 0) value = await window.shredlinkEnvelope.setIdentity(value);
+      await registerKey(value);
+      keyForm.reset();
+    } catch (err) {
+      showKeyError(tr('keyInvalid'));
+    }
+  });
+
+  keyGenerateBtn.addEventListener('click', async function () {
+    keyError.classList.add('hidden');
+    var existing = await window.shredlinkEnvelope.identityPublicKey();
+    if (existing && !window.confirm(tr('keyReplaceConfirm'))) return;
+    keyGenerateBtn.disabled = true;
+    try {
+      await registerKey(await window.shredlinkEnvelope.generateIdentity());
+    } catch (err) {
+      showKeyError(tr('keyUnsupported'));
+    }
+    keyGenerateBtn.disabled = false;
+  });
+
+  keyRemoveBtn.addEventListener('click', async function () {
+    if (!window.confirm(tr('keyRemoveConfirm'))) return;
+    var res = await fetch('/api/v1/account/public-key', { method: 'DELETE', credentials: 'same-origin' });
+    if (res.status === 401) {
+      showAuth();
+      return;
+    }
+    if (res.ok) {
+      publicKey = null;
+      renderKey();
+    }
+  });
+
+  async function loadLinks() {
+    var res = await fetch('/api/v1/account/links', { credentials: 'same-origin' });
+    if (res.status === 401) {
+      showAuth();
+      return;
+    }
+    links = res.ok ? await res.json() : [];
+    render();
+  }
+
+  async function revoke(linkId) {
+    if (!window.confirm(tr('revokeConfirm'))) return;
+    var res = await fetch('/api/v1/account/links/' + encodeURIComponent(linkId), {
+      method: 'DELETE',
+      credentials: 'same-origin',
+    });
+    if (res.status === 401) {
+      showAuth();
+      return;
+    }
+    await loadLinks();
+  }
+
+  async function authenticate(action) {
+    authError.classList.add('hidden');
+    loginBtn.disabled = true;
+    registerBtn.disabled = true;
+    try {
+      var res = await fetch('/api/v1/account/' + action, {
+        method: 'POST',
+        credentials: 'same-origin',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({
+          username: document.getElementById('username').value.trim(),
+          password: document.getElementById('account-password').value,
+        }),
+      });
+      var data = await res.json().catch(function () { return {}; });
+      if (!res.ok) {
+        authError.textContent = data.error || tr('errorAuth');
+        authError.classList.remove('hidden');
+      } else {
+        authForm.reset();
+        showDashboard(data);
+        await loadLinks();
+        await loadRequests();
+      }
+    } catch (err) {
+      authError.textContent = tr('errorNetwork');
+      authError.classList.remove('hidden');
+    }
+    loginBtn.disabled = false;
+    registerBtn.disabled = false;
+  }
+
+  authForm.addEventListener('submit', function (e) {
+    e.preventDefault();
+    authenticate('login');
+  });
+
+  registerBtn.addEventListener('click', function () {
+    if (!authForm.reportValidity()) return;
+    authenticate('register');
+  });
+
+  logoutBtn.addEventListener('click', async function () {
+    await fetch('/api/v1/account/logout', { method: 'POST', credentials: 'same-origin' });
+    links = [];
+    requests = [];
+    requestCreated.classList.add('hidden');
+    requestAnswer.classList.add('hidden');
+    requestAnswerText.textContent = '';
+    requestError.classList.add('hidden');
+    publicKey = null;
+    keyError.classList.add('hidden');
+    accountEmail = null;
+    emailForm.reset();
+    emailConfirmForm.reset();
+    emailConfirmForm.classList.add('hidden');
+    emailError.classList.add('hidden');
+    showAuth();
+  });
+
+  function renderEmail() {
+    emailVerified.classList.toggle('hidden', !accountEmail);
+    emailCurrent.textContent = accountEmail || '';
+  }
+
+  function showEmailError(msg) {
+    emailError.textContent = msg;
+    emailError.classList.remove('hidden');
+  }
+
+  emailForm.addEventListener('submit', async function (e) {
+    e.preventDefault();
+    emailError.classList.add('hidden');
+    emailSendBtn.disabled = true;
+    try {
+      var res = await fetch('/api/v1/account/email', {
+        method: 'PUT',
+        credentials: 'same-origin',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({
+          email: document.getElementById('email-input').value.trim(),
+          lang: window.i18n ? window.i18n.lang() : undefined,
+        }),
+      });
+      if (res.status === 401) {
+        showAuth();
+      } else if (res.ok) {
+        emailConfirmForm.classList.remove('hidden');
+        document.getElementById('email-code').focus();
+      } else {
+        var data = await res.json().catch(function () { return {}; });
+        showEmailError(data.error || tr('errorGeneric'));
+      }
+    } catch (err) {
+      showEmailError(tr('errorGeneric'));
+    }
+    emailSendBtn.disabled = false;
+  });
+
+  emailConfirmForm.addEventListener('submit', async function (e) {
+    e.preventDefault();
+    emailError.classList.add('hidden');
+    emailConfirmBtn.disabled = true;
+    try {
+      var res = await fetch('/api/v1/account/email/confirm', {
+        method: 'POST',
+        credentials: 'same-origin',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ code: document.getElementById('email-code').value.trim() }),
+      });
+      var data = await res.json().catch(function () { return {}; });
+      if (res.status === 401) {
+        showAuth();
+      } else if (res.ok) {
+        accountEmail = data.email || null;
+        renderEmail();
+        emailForm.reset();
+        emailConfirmForm.reset();
+        emailConfirmForm.classList.add('hidden');
+      } else {
+        showEmailError(data.error || tr('errorGeneric'));
+      }
+    } catch (err) {
+      showEmailError(tr('errorGeneric'));
+    }
+    emailConfirmBtn.disabled = false;
+  });
+
+  document.addEventListener('shredlink:lang', function () {
+    render();
+    renderRequests();
+  });
+
+  async function loadOptions() {
+    try {
+      var res = await fetch('/api/v1/account/options');
+      if (!res.ok) return;
+      var opts = await res.json();
+      ssoSection.classList.toggle('hidden', !opts.sso_enabled);
+      registerBtn.classList.toggle('hidden', !opts.signup_enabled);
+    } catch (err) {}
+  }
+
+  (async function init() {
+    var params = new URLSearchParams(window.location.search);
+    if (params.get('sso_error')) {
+      ssoError.textContent = params.get('sso_error');
+      ssoError.classList.remove('hidden');
+      window.history.replaceState(null, '', window.location.pathname);
+    }
+    loadOptions();
+    try {
+      var res = await fetch('/api/v1/account', { credentials: 'same-origin' });
+      if (!res.ok) {
+        showAuth();
+        return;
+      }
+      var data = await res.json();
+      showDashboard(data);
+      await loadLinks();
+      await loadRequests();
+    } catch (err) {
+      showAuth();
+    }
+  })();
+})();
@@ -5,6 +5,9 @@
   const createSection = document.getElementById('create-section');
   const resultSection = document.getElementById('result-section');
   const resultUrl = document.getElementById('result-url');
+  const resultLabel = document.getElementById('result-label');
+  const resultSingle = document.getElementById('result-single');
+  const resultRecipients = document.getElementById('result-recipients');
   const copyBtn = document.getElementById('copy-btn');
   const newLinkBtn = document.getElementById('new-link-btn');
   const formError = document.getElementById('form-error');
@@ -23,6 +26,32 @@
     formSuccess.classList.remove('hidden');
   }
 
+  var policy = null;
+
+  // Adapts the form to the server's creation policy so users don't hit avoidable 422s.
+  function applyPolicy() {
+    if (!policy) return;
+    var expire = document.getElementById('expire');
+    if (policy.max_ttl_minutes) {
+      Array.prototype.forEach.call(expire.options, function (opt) {
+        opt.disabled = parseInt(opt.value, 10) > policy.max_ttl_minutes;
+      });
+      if (expire.selectedOptions[0] && expire.selectedOptions[0].disabled) expire.selectedIndex = 0;
+    }
+    var password = document.getElementById('password');
+    password.required = !!policy.require_password;
+    if (policy.min_password_length) password.minLength = policy.min_password_length;
+    var oneTimeView = document.getElementById('one_time_view');
+    if (policy.require_one_time && !oneTimeView.checked && !document.getElementById('one_time_password').checked) {
+      oneTimeView.checked = true;
+    }
+  }
+
+  fetch('/api/v1/policy', { credentials: 'same-origin' })
+    .then(function (res) { return res.ok ? res.json() : null; })
+    .then(function (p) { policy = p; applyPolicy(); })
+    .catch(function () {});
+
   function hideMessages() {
     formError.classList.add('hidden');
     formSuccess.classList.add('hidden');
@@ -44,24 +73,67 @@
     var expireMinutes = parseInt(document.getElementById('expire').value, 10) || 0;
     var oneTimeView = document.getElementById('one_time_view').checked;
     var oneTimePassword = document.getElementById('one_time_password').checked;
+    // datetime-local values are in the browser's time zone; send them as UTC.
+    var notBefore = document.getElementById('not_before').value;
+    var expiresAt = document.getElementById('expires_at').value;
+    var idleMinutes = parseInt(document.getElementById('idle_timeout').value, 10) || 0;
+    var notifyEmail = document.getElementById('notify_email').value.trim();
+    var recipient = document.getElementById('recipient').value.trim();
+    var allowedCidrs = document.getElementById('allowed_cidrs').value.split(/[\s,]+/).filter(Boolean);
+    var recipientLabels = document.getElementById('recipient_labels').value.split('\n')
+      .map(function (l) { return l.trim(); })
+      .filter(Boolean);
 
     var body = {
       text: text,
       password: password || null,
-      expire_minutes: expireMinutes > 0 ? expireMinutes : null,
+      expire_minutes: !expiresAt && expireMinutes > 0 ? expireMinutes : null,
       expire_hours: null,
       one_time_view: oneTimeView,
       one_time_password: oneTimePassword,
     };
+    if (notBefore) body.not_before = new Date(notBefore).toISOString();
+    if (expiresAt) body.expires_at = new Date(expiresAt).toISOString();
+    if (idleMinutes > 0) body.idle_timeout_minutes = idleMinutes;
+    if (notifyEmail) {
+      body.notify_email = notifyEmail;
+      body.notify_lang = window.i18n ? window.i18n.lang() : 'en';
+    }
+    if (recipient) body.recipient_public_key = recipient;
+    if (allowedCidrs.length) body.allowed_cidrs = allowedCidrs;
+    if (recipientLabels.length) {
+      // Every recipient's link is one-time already; the server rejects the flags here.
+      body.recipient_labels = recipientLabels;
+      body.one_time_view = false;
+      body.one_time_password = false;
+    }
 
     try {
-      var res = await fetch('/api/create', {
+      var res = await window.shredlinkPow.fetch('/api/v1/create', {
         method: 'POST',
         headers: { 'Content-Type': 'application/json' },
         body: JSON.stringify(body),
       });
       var data = await res.json().catch(function () { return {}; });
 
+      if (res.status === 401) {
+        // REQUIRE_AUTH_FOR_CREATE is on: point the user at the sign-in page.
+        var key = notifyEmail
+          ? 'errorSignInToNotify'
+          : recipient.charAt(0) === '@'
+            ? 'errorSignInToSeal'
+            : recipientLabels.length
+              ? 'errorSignInToShareMany'
+              : 'errorSignInToCreate';
+        showError(window.i18n ? window.i18n.t(key) : 'Sign in to create links.');
+        var signIn = document.createElement('a');
+        signIn.href = '/account.html';
+        signIn.className = 'ms-2 underline';
+        signIn.textContent = window.i18n ? window.i18n.t('navAccount') : 'My links';
+        formError.appendChild(signIn);
+        submitBtn.disabled = false;
+        return;
+      }
       if (!res.ok) {
         showError(data.error || (window.i18n ? window.i18n.t('errorGeneric') : 'Failed to create link. Try again.'));
         submitBtn.disabled = false;
@@ -69,6 +141,7 @@
       }
 
       resultUrl.value = data.url || '';
+      showRecipients(data.recipients || []);
       createSection.classList.add('hidden');
       resultSection.classList.remove('hidden');
     } catch (err) {
@@ -77,12 +150,41 @@
     submitBtn.disabled = false;
   });
 
-  copyBtn.addEventListener('click', function () {
-    var linkText = resultUrl.value || '';
+  // A multi-recipient link's own URL can't be read; show each person's link instead.
+  function showRecipients(recipients) {
+    resultRecipients.textContent = '';
+    resultRecipients.classList.toggle('hidden', recipients.length === 0);
+    resultSingle.classList.toggle('hidden', recipients.length > 0);
+    resultLabel.setAttribute('data-i18n', recipients.length ? 'yourRecipientLinks' : 'yourLink');
+    resultLabel.textContent = window.i18n ? window.i18n.t(resultLabel.getAttribute('data-i18n')) : resultLabel.textContent;
+    recipients.forEach(function (r) {
+      var row = document.createElement('div');
+      row.className = 'flex flex-col sm:flex-row sm:items-center gap-2';
+      var name = document.createElement('span');
+      name.className = 'sm:w-28 text-sm font-medium text-slate-700 dark:text-slate-300 truncate';
+      name.textContent = r.label;
+      var input = document.createElement('input');
+      input.type = 'text';
+      input.readOnly = true;
+      input.value = r.url;
+      input.className = 'flex-1 px-4 py-2.5 rounded-xl border border-slate-300 dark:border-slate-600 bg-slate-50 dark:bg-slate-700 text-slate-800 dark:text-slate-200 text-sm font-mono';
+      var btn = document.createElement('button');
+      btn.type = 'button';
+      btn.className = copyBtn.className;
+      btn.textContent = window.i18n ? window.i18n.t('copy') : 'Copy';
+      btn.addEventListener('click', function () { copyText(r.url, btn); });
+      row.appendChild(name);
+      row.appendChild(input);
+      row.appendChild(btn);
+      resultRecipients.appendChild(row);
+    });
+  }
+
+  function copyText(linkText, btn) {
     function showCopied() {
-      copyBtn.textContent = window.i18n ? window.i18n.t('copied') : 'Copied!';
+      btn.textContent = window.i18n ? window.i18n.t('copied') : 'Copied!';
       setTimeout(function () {
-        copyBtn.textContent = window.i18n ? window.i18n.t('copy') : 'Copy';
+        btn.textContent = window.i18n ? window.i18n.t('copy') : 'Copy';
       }, 2000);
     }
     function fallbackCopy() {
@@ -104,13 +206,19 @@
       fallbackCopy();
       showCopied();
     }
+  }
+
+  copyBtn.addEventListener('click', function () {
+    copyText(resultUrl.value || '', copyBtn);
   });
 
   newLinkBtn.addEventListener('click', function () {
     resultSection.classList.add('hidden');
     createSection.classList.remove('hidden');
+    showRecipients([]);
     form.reset();
     document.getElementById('expire').selectedIndex = 0;
+    applyPolicy();
     hideMessages();
   });
 })();
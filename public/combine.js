@@ -0,0 +1,251 @@
+(function () {
+  'use strict';
+
+  // Shares from POST /api/v1/split: "shredlink:share1:{set}:{threshold}:{x}:{base64 y}".
+  // Recombined here with Lagrange interpolation over GF(256), as in server/src/shamir.rs.
+  var SHARE_PREFIX = 'shredlink:share1:';
+
+  var msgs = {
+    en: {
+      title: 'Combine shares',
+      desc: 'Paste share links (or share texts) from enough custodians, one per line. The secret is rebuilt in this browser.',
+      warn: 'Opening a share link counts as a view; one-time links are used up.',
+      combine: 'Combine',
+      copy: 'Copy',
+      copied: 'Copied!',
+      back: 'Back home',
+      password: 'Password for share link {n}:',
+      notShare: 'Line {n} is not a share link or share text.',
+      otherServer: 'Line {n} is a link to another server; open it there and paste the share text instead.',
+      readFailed: 'Share link {n}: {error}',
+      mixed: 'These shares belong to different secrets.',
+      duplicate: 'The same share was given twice.',
+      tooFew: 'Need {m} shares, got {k}.',
+      invalid: 'Could not rebuild the secret. Check that the shares are complete.',
+      network: 'Network error'
+    },
+    fa: {
+      title: 'ترکیب سهم‌ها',
+      desc: 'لینک‌های سهم (یا متن سهم‌ها) را از تعداد کافی امانت‌دار، هر کدام در یک خط، وارد کنید. راز در همین مرورگر بازسازی می‌شود.',
+      warn: 'باز کردن لینک سهم یک بازدید حساب می‌شود و لینک‌های یک‌بارمصرف مصرف می‌شوند.',
+      combine: 'ترکیب',
+      copy: 'کپی',
+      copied: 'کپی شد!',
+      back: 'بازگشت به خانه',
+      password: 'رمز عبور لینک سهم {n}:',
+      notShare: 'خط {n} لینک یا متن سهم نیست.',
+      otherServer: 'خط {n} لینکی به سرور دیگری است؛ آن را همان‌جا باز کنید و متن سهم را وارد کنید.',
+      readFailed: 'لینک سهم {n}: {error}',
+      mixed: 'این سهم‌ها متعلق به رازهای متفاوتی هستند.',
+      duplicate: 'یک سهم دو بار وارد شده است.',
+      tooFew: 'به {m} سهم نیاز است، {k} سهم داده شده.',
+      invalid: 'بازسازی راز ممکن نشد. کامل بودن سهم‌ها را بررسی کنید.',
+      network: 'خطای شبکه'
+    }
+  };
+
+  function lang() {
+    return document.documentElement.getAttribute('data-lang') || 'en';
+  }
+
+  function m(key, vars) {
+    var text = (msgs[lang()] || msgs.en)[key] || key;
+    Object.keys(vars || {}).forEach(function (k) {
+      text = text.replace('{' + k + '}', vars[k]);
+    });
+    return text;
+  }
+
+  function applyLang(l) {
+    var t = msgs[l] || msgs.en;
+    document.querySelectorAll('[data-msg]').forEach(function (el) {
+      var key = el.getAttribute('data-msg');
+      if (t[key]) el.textContent = t[key];
+    });
+    document.querySelectorAll('.lang-btn').forEach(function (el) {
+      el.classList.toggle('font-medium', el.id === 'lang-' + (l === 'fa' ? 'fa' : 'en'));
+    });
+  }
+
+  function setLang(l) {
+    localStorage.setItem('shredlink_lang', l);
+    document.documentElement.lang = l;
+    document.documentElement.dir = l === 'fa' ? 'rtl' : 'ltr';
+    document.documentElement.setAttribute('data-lang', l);
+    document.body.classList.toggle('lang-fa', l === 'fa');
+    applyLang(l);
+  }
+
+  function initUi() {
+    var l = lang();
+    document.body.classList.toggle('lang-fa', l === 'fa');
+    applyLang(l);
+    document.getElementById('dark-toggle').addEventListener('click', function () {
+      document.documentElement.classList.toggle('dark');
+      localStorage.setItem('shredlink_dark', document.documentElement.classList.contains('dark') ? '1' : '0');
+    });
+    document.getElementById('lang-en').addEventListener('click', function () { setLang('en'); });
+    document.getElementById('lang-fa').addEventListener('click', function () { setLang('fa'); });
+  }
+
+  initUi();
+
+  function gfMul(a, b) {
+    var p = 0;
+    for (var i = 0; i < 8; i++) {
+      if (b & 1) p ^= a;
+      var carry = a & 0x80;
+      a = (a << 1) & 0xff;
+      if (carry) a ^= 0x1b;
+      b >>= 1;
+    }
+    return p;
+  }
+
+  // a^254 is the inverse of a in GF(256).
+  function gfInv(a) {
+    var result = 1;
+    var base = a;
+    var e = 254;
+    while (e) {
+      if (e & 1) result = gfMul(result, base);
+      base = gfMul(base, base);
+      e >>= 1;
+    }
+    return result;
+  }
+
+  function parseShare(text) {
+    if (text.indexOf(SHARE_PREFIX) !== 0) return null;
+    var parts = text.slice(SHARE_PREFIX.length).trim().split(':');
+    if (parts.length !== 4) return null;
+    var threshold = parseInt(parts[1], 10);
+    var x = parseInt(parts[2], 10);
+    if (!(threshold >= 2) || !(x >= 1 && x <= 255)) return null;
+    var bin;
+    try {
+      bin = atob(parts[3]);
+    } catch (e) {
+      return null;
+    }
+    var y = new Uint8Array(bin.length);
+    for (var i = 0; i < bin.length; i++) y[i] = bin.charCodeAt(i);
+    return { set: parts[0], threshold: threshold, x: x, y: y };
+  }
+
+  // Lagrange interpolation at x = 0 over the first `threshold` shares.
+  function combine(shares) {
+    var first = shares[0];
+    var duplicate = {};
+    for (var i = 0; i < shares.length; i++) {
+      var s = shares[i];
+      if (s.set !== first.set || s.threshold !== first.threshold || s.y.length !== first.y.length) throw new Error(m('mixed'));
+      if (duplicate[s.x]) throw new Error(m('duplicate'));
+      duplicate[s.x] = true;
+    }
+    if (shares.length < first.threshold) throw new Error(m('tooFew', { m: first.threshold, k: shares.length }));
+    var used = shares.slice(0, first.threshold);
+    var out = new Uint8Array(first.y.length);
+    used.forEach(function (si, i) {
+      var basis = 1;
+      used.forEach(function (sj, j) {
+        if (i !== j) basis = gfMul(basis, gfMul(sj.x, gfInv(sj.x ^ si.x)));
+      });
+      for (var b = 0; b < out.length; b++) out[b] ^= gfMul(si.y[b], basis);
+    });
+    try {
+      return new TextDecoder('utf-8', { fatal: true }).decode(out);
+    } catch (e) {
+      throw new Error(m('invalid'));
+    }
+  }
+
+  function readLink(url, n) {
+    var match = url.pathname.match(/\/s\/([^/]+)\/?$/);
+    var token = match ? match[1] : '';
+    var post = function (path, body) {
+      return window.shredlinkPow.fetch(path + encodeURIComponent(token), {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: body ? JSON.stringify(body) : undefined
+      }).then(function (r) {
+        return r.json().catch(function () { return {}; }).then(function (data) {
+          return { status: r.status, data: data };
+        });
+      });
+    };
+    return post('/api/v1/read/').then(function (res) {
+      if (res.status !== 401) return res;
+      var password = window.prompt(m('password', { n: n }));
+      if (password === null) return res;
+      return post('/api/v1/unlock/', { password: password });
+    }).then(function (res) {
+      if (res.status !== 200) throw new Error(m('readFailed', { n: n, error: res.data.error || res.status }));
+      return window.shredlinkEnvelope.open(res.data.text || '', url.hash.slice(1));
+    });
+  }
+
+  function shareText(line, n) {
+    if (line.indexOf(SHARE_PREFIX) === 0) return Promise.resolve(line);
+    var url;
+    try {
+      url = new URL(line);
+    } catch (e) {
+      return Promise.reject(new Error(m('notShare', { n: n })));
+    }
+    if (url.origin !== window.location.origin) return Promise.reject(new Error(m('otherServer', { n: n })));
+    return readLink(url, n);
+  }
+
+  var form = document.getElementById('combine-form');
+  var errEl = document.getElementById('err');
+  var btn = document.getElementById('combine-btn');
+  var result = document.getElementById('result');
+  var secretEl = document.getElementById('secret');
+  var copyBtn = document.getElementById('copy-btn');
+
+  form.addEventListener('submit', function (e) {
+    e.preventDefault();
+    errEl.classList.add('hidden');
+    result.classList.add('hidden');
+    btn.disabled = true;
+    var lines = form.shares.value.split('\n').map(function (l) { return l.trim(); }).filter(Boolean);
+    // One at a time, so password prompts appear in order.
+    var texts = [];
+    var chain = Promise.resolve();
+    lines.forEach(function (line, i) {
+      chain = chain.then(function () {
+        return shareText(line, i + 1).then(function (t) { texts.push(t); });
+      });
+    });
+    chain
+      .then(function () {
+        var shares = texts.map(function (t, i) {
+          var s = parseShare(t);
+          if (!s) throw new Error(m('notShare', { n: i + 1 }));
+          return s;
+        });
+        if (!shares.length) throw new Error(m('tooFew', { m: 2, k: 0 }));
+        secretEl.textContent = combine(shares);
+        form.reset();
+        result.classList.remove('hidden');
+      })
+      .catch(function (err) {
+        errEl.textContent = err && err.message ? err.message : m('network');
+        errEl.classList.remove('hidden');
+      })
+      .then(function () {
+        btn.disabled = false;
+      });
+  });
+
+  copyBtn.addEventListener('click', function () {
+    var done = function () {
+      copyBtn.textContent = m('copied');
+      setTimeout(function () { copyBtn.textContent = m('copy'); }, 2000);
+    };
+    if (navigator.clipboard && navigator.clipboard.writeText) {
+      navigator.clipboard.writeText(secretEl.textContent).then(done, function () {});
+    }
+  });
+})();
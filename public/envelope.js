@@ -0,0 +1,245 @@
+(function () {
+  'use strict';
+
+  // Client-side encrypted content: "shredlink:v1:" + base64(nonce || AES-256-GCM ciphertext).
+  // The key travels only in the URL fragment (base64url), which browsers never send to the server.
+  var PREFIX = 'shredlink:v1:';
+  // Content sealed to a recipient's X25519 key: "shredlink:seal1:" +
+  // base64(ephemeral public key || nonce || ciphertext). The AES key is HKDF-SHA256 of the
+  // shared secret (salt: ephemeral || recipient public key), as in server/src/crypto.rs.
+  var SEAL_PREFIX = 'shredlink:seal1:';
+  var SEAL_INFO = 'shredlink seal v1';
+  // This browser's secret key ("slsec_" + base64url), as written by `shredlink key gen`.
+  var IDENTITY_STORAGE = 'shredlink_identity';
+  // PKCS#8 header of a raw 32-byte X25519 private key.
+  var PKCS8_X25519 = [0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x6e, 0x04, 0x22, 0x04, 0x20];
+
+  function b64ToBytes(s) {
+    s = s.replace(/-/g, '+').replace(/_/g, '/');
+    while (s.length % 4) s += '=';
+    var bin = atob(s);
+    var out = new Uint8Array(bin.length);
+    for (var i = 0; i < bin.length; i++) out[i] = bin.charCodeAt(i);
+    return out;
+  }
+
+  function bytesToB64url(bytes) {
+    var bin = '';
+    for (var i = 0; i < bytes.length; i++) bin += String.fromCharCode(bytes[i]);
+    return btoa(bin).replace(/\+/g, '-').replace(/\//g, '_').replace(/=+$/, '');
+  }
+
+  function bytesToB64(bytes) {
+    var bin = '';
+    for (var i = 0; i < bytes.length; i++) bin += String.fromCharCode(bytes[i]);
+    return btoa(bin);
+  }
+
+  function isSealed(text) {
+    return typeof text === 'string' && text.indexOf(SEAL_PREFIX) === 0;
+  }
+
+  function isEnvelope(text) {
+    return typeof text === 'string' && (text.indexOf(PREFIX) === 0 || isSealed(text));
+  }
+
+  function identity() {
+    return localStorage.getItem(IDENTITY_STORAGE) || '';
+  }
+
+  function importSecret(secret) {
+    if (secret.indexOf('slsec_') !== 0) return Promise.reject(new Error('invalid secret key'));
+    var raw = b64ToBytes(secret.slice(6));
+    if (raw.length !== 32) return Promise.reject(new Error('invalid secret key'));
+    var pkcs8 = new Uint8Array(PKCS8_X25519.length + 32);
+    pkcs8.set(PKCS8_X25519);
+    pkcs8.set(raw, PKCS8_X25519.length);
+    return window.crypto.subtle.importKey('pkcs8', pkcs8, { name: 'X25519' }, true, ['deriveBits']);
+  }
+
+  // Resolves to the "slpub_" public key of a CryptoKey secret key.
+  function publicKeyOf(key) {
+    return window.crypto.subtle.exportKey('jwk', key).then(function (jwk) {
+      return 'slpub_' + jwk.x;
+    });
+  }
+
+  // Stores `secret` as this browser's key and resolves to its public key.
+  function setIdentity(secret) {
+    return importSecret(secret.trim()).then(publicKeyOf).then(function (pub) {
+      localStorage.setItem(IDENTITY_STORAGE, secret.trim());
+      return pub;
+    });
+  }
+
+  function clearIdentity() {
+    localStorage.removeItem(IDENTITY_STORAGE);
+  }
+
+  // Creates and stores a new key; resolves to its public key.
+  function generateIdentity() {
+    return window.crypto.subtle
+      .generateKey({ name: 'X25519' }, true, ['deriveBits'])
+      .then(function (pair) {
+        return window.crypto.subtle.exportKey('pkcs8', pair.privateKey);
+      })
+      .then(function (pkcs8) {
+        return setIdentity('slsec_' + bytesToB64url(new Uint8Array(pkcs8).slice(-32)));
+      });
+  }
+
+  // Resolves to this browser's public key, or '' if it has none.
+  function identityPublicKey() {
+    var secret = identity();
+    if (!secret || !window.crypto || !window.crypto.subtle) return Promise.resolve('');
+    return importSecret(secret).then(publicKeyOf, function () { return ''; });
+  }
+
+  function openSealed(text) {
+    var secret = identity();
+    if (!secret || !window.crypto || !window.crypto.subtle) {
+      return Promise.reject(new Error('missing identity'));
+    }
+    var raw;
+    try {
+      raw = b64ToBytes(text.slice(SEAL_PREFIX.length));
+    } catch (e) {
+      return Promise.reject(e);
+    }
+    if (raw.length < 44) return Promise.reject(new Error('payload too short'));
+    var ephemeral = raw.slice(0, 32);
+    var subtle = window.crypto.subtle;
+    var secretKey;
+    return importSecret(secret)
+      .then(function (k) {
+        secretKey = k;
+        return Promise.all([publicKeyOf(k), subtle.importKey('raw', ephemeral, { name: 'X25519' }, false, [])]);
+      })
+      .then(function (res) {
+        var salt = new Uint8Array(64);
+        salt.set(ephemeral);
+        salt.set(b64ToBytes(res[0].slice(6)), 32);
+        return subtle
+          .deriveBits({ name: 'X25519', public: res[1] }, secretKey, 256)
+          .then(function (shared) {
+            return subtle.importKey('raw', shared, 'HKDF', false, ['deriveKey']);
+          })
+          .then(function (ikm) {
+            return subtle.deriveKey(
+              { name: 'HKDF', hash: 'SHA-256', salt: salt, info: new TextEncoder().encode(SEAL_INFO) },
+              ikm,
+              { name: 'AES-GCM', length: 256 },
+              false,
+              ['decrypt']
+            );
+          });
+      })
+      .then(function (k) {
+        return subtle.decrypt({ name: 'AES-GCM', iv: raw.slice(32, 44) }, k, raw.slice(44));
+      })
+      .then(function (buf) {
+        return new TextDecoder().decode(buf);
+      });
+  }
+
+  // Resolves to `text` sealed to the "slpub_" key `publicKey`, in the same format
+  // as crypto::seal_to on the server.
+  function seal(text, publicKey) {
+    if (!window.crypto || !window.crypto.subtle) return Promise.reject(new Error('WebCrypto unavailable'));
+    if (typeof publicKey !== 'string' || publicKey.indexOf('slpub_') !== 0) {
+      return Promise.reject(new Error('invalid public key'));
+    }
+    var recipient;
+    try {
+      recipient = b64ToBytes(publicKey.slice(6));
+    } catch (e) {
+      return Promise.reject(e);
+    }
+    if (recipient.length !== 32) return Promise.reject(new Error('invalid public key'));
+    var subtle = window.crypto.subtle;
+    var iv = window.crypto.getRandomValues(new Uint8Array(12));
+    var ephemeral;
+    return Promise.all([
+      subtle.generateKey({ name: 'X25519' }, true, ['deriveBits']),
+      subtle.importKey('raw', recipient, { name: 'X25519' }, false, [])
+    ])
+      .then(function (res) {
+        var pair = res[0];
+        return Promise.all([
+          subtle.exportKey('raw', pair.publicKey),
+          subtle.deriveBits({ name: 'X25519', public: res[1] }, pair.privateKey, 256)
+        ]);
+      })
+      .then(function (res) {
+        ephemeral = new Uint8Array(res[0]);
+        var salt = new Uint8Array(64);
+        salt.set(ephemeral);
+        salt.set(recipient, 32);
+        return subtle.importKey('raw', res[1], 'HKDF', false, ['deriveKey']).then(function (ikm) {
+          return subtle.deriveKey(
+            { name: 'HKDF', hash: 'SHA-256', salt: salt, info: new TextEncoder().encode(SEAL_INFO) },
+            ikm,
+            { name: 'AES-GCM', length: 256 },
+            false,
+            ['encrypt']
+          );
+        });
+      })
+      .then(function (k) {
+        return subtle.encrypt({ name: 'AES-GCM', iv: iv }, k, new TextEncoder().encode(text));
+      })
+      .then(function (ct) {
+        var out = new Uint8Array(32 + 12 + ct.byteLength);
+        out.set(ephemeral);
+        out.set(iv, 32);
+        out.set(new Uint8Array(ct), 44);
+        return SEAL_PREFIX + bytesToB64(out);
+      });
+  }
+
+  function fragmentKey() {
+    var h = window.location.hash || '';
+    return h.length > 1 ? h.slice(1) : '';
+  }
+
+  // Resolves to the plaintext; text that is not an envelope is returned unchanged.
+  // Sealed content may itself contain an envelope, which is opened next. `fragment`
+  // defaults to this page's URL fragment.
+  function open(text, fragment) {
+    if (isSealed(text)) {
+      return openSealed(text).then(function (inner) { return open(inner, fragment); });
+    }
+    if (!isEnvelope(text)) return Promise.resolve(text);
+    var key = fragment === undefined ? fragmentKey() : fragment;
+    if (!key || !window.crypto || !window.crypto.subtle) {
+      return Promise.reject(new Error('missing key'));
+    }
+    var raw;
+    var keyBytes;
+    try {
+      raw = b64ToBytes(text.slice(PREFIX.length));
+      keyBytes = b64ToBytes(key);
+    } catch (e) {
+      return Promise.reject(e);
+    }
+    return window.crypto.subtle
+      .importKey('raw', keyBytes, 'AES-GCM', false, ['decrypt'])
+      .then(function (k) {
+        return window.crypto.subtle.decrypt({ name: 'AES-GCM', iv: raw.slice(0, 12) }, k, raw.slice(12));
+      })
+      .then(function (buf) {
+        return new TextDecoder().decode(buf);
+      });
+  }
+
+  window.shredlinkEnvelope = {
+    isEnvelope: isEnvelope,
+    isSealed: isSealed,
+    open: open,
+    seal: seal,
+    identityPublicKey: identityPublicKey,
+    generateIdentity: generateIdentity,
+    setIdentity: setIdentity,
+    clearIdentity: clearIdentity
+  };
+})();
@@ -23,6 +23,7 @@
       oneTimePassword: 'One-time password (expires after correct password once)',
       createBtn: 'Create link',
       yourLink: 'Your secure link:',
+      yourRecipientLinks: 'One link per person; each opens once:',
       copy: 'Copy',
       copied: 'Copied!',
       createAnother: 'Create another link',
@@ -31,6 +32,89 @@
       errorNetwork: 'Network error. Check your connection and try again.',
       errorGeneric: 'Failed to create link. Try again.',
       copyManual: 'Select and copy manually',
+      navAccount: 'My links',
+      navCombine: 'Combine shares',
+      accountTitle: 'My links',
+      accountIntro: 'Sign in to see the links you created. Content is never shown here, only metadata.',
+      usernameLabel: 'Username',
+      accountPasswordLabel: 'Password',
+      signIn: 'Sign in',
+      signUp: 'Create account',
+      signOut: 'Sign out',
+      signedInAs: 'Signed in as',
+      noLinks: 'You have not created any links while signed in.',
+      colLink: 'Link',
+      colStatus: 'Status',
+      colViews: 'Views',
+      colExpires: 'Expires',
+      colCreated: 'Created',
+      statusActive: 'Active',
+      statusExpired: 'Expired',
+      statusConsumed: 'Used',
+      never: 'Never',
+      revoke: 'Revoke',
+      revokeConfirm: 'Delete this link? It cannot be opened afterwards.',
+      requestsTitle: 'Secret requests',
+      requestsIntro: 'Ask someone to send you a secret. Send them the link; their answer is sealed to your recipient key and you can read it here once.',
+      requestKeyMissing: 'The answer is sealed to a key this browser does not hold. Read it with the CLI or import the key under Recipient key.',
+      requestNotePlaceholder: 'What should they send? (optional)',
+      requestCreate: 'Create request link',
+      requestCreatedHint: 'Send this link to the person who should answer:',
+      requestAnswerHint: 'This answer has been deleted from the server. Copy it now.',
+      noRequests: 'No open requests.',
+      colRequest: 'Request',
+      statusPending: 'Waiting',
+      statusSubmitted: 'Answered',
+      requestRead: 'Read',
+      requestReadConfirm: 'Read the answer? It is deleted from the server as soon as it is shown.',
+      requestDeleteConfirm: 'Delete this request? Its link stops working.',
+      tagPassword: 'password',
+      tagOneTime: 'one-time',
+      errorAuth: 'Sign-in failed.',
+      signInSso: 'Sign in with SSO',
+      orDivider: 'or',
+      errorSignInToCreate: 'Sign in to create links.',
+      notBeforeLabel: 'Available from (optional)',
+      expiresAtLabel: 'Expire at (optional)',
+      expiresAtHint: 'Overrides "Expire after".',
+      notifyEmailLabel: 'Email me when opened or expired unread (optional)',
+      notifyEmailHint: 'Must be the email you confirmed on your account page.',
+      errorSignInToNotify: 'Sign in to get email notifications.',
+      statusScheduled: 'Scheduled',
+      idleLabel: 'Expire after inactivity',
+      idleNone: 'Never',
+      tagIdle: 'idle',
+      recipientLabel: 'Seal to a recipient (optional)',
+      recipientHint: 'Their public key (slpub_…) or @username. Only their key can open the link.',
+      errorSignInToSeal: 'Sign in to seal links to a username.',
+      recipientsLabel: 'Send to several people (optional)',
+      recipientsHint: 'One name per line. Each person gets their own one-time link, and My links shows who has opened it. Requires signing in.',
+      errorSignInToShareMany: 'Sign in to send a link to several people.',
+      tagRecipients: 'recipients',
+      tagNetworks: 'only from',
+      allowedCidrsLabel: 'Only open from these networks (optional)',
+      allowedCidrsHint: 'CIDRs or IP addresses, separated by commas. Elsewhere the link looks like it does not exist.',
+      recipientOpened: 'opened',
+      recipientNotOpened: 'not opened',
+      keyTitle: 'Recipient key',
+      keyIntro: 'Others can seal links to @your-username so only your key can open them, even if the link leaks.',
+      keyRegistered: 'Registered public key:',
+      keyBrowserMissing: 'This browser does not hold the matching secret key; open sealed links with the CLI or import the key below.',
+      keyInputPlaceholder: 'slpub_… or slsec_…',
+      keyRegister: 'Register key',
+      keyGenerate: 'Create key in this browser',
+      keyRemove: 'Remove key',
+      keyInvalid: 'That is not a valid key.',
+      keyUnsupported: 'This browser cannot create X25519 keys. Use shredlink key gen and register its public key.',
+      keyReplaceConfirm: 'Replace the key in this browser? Links sealed to the old key can no longer be opened here.',
+      keyRemoveConfirm: 'Remove your registered key? Links already sealed to it still need it.',
+      emailTitle: 'Notification email',
+      emailIntro: 'Links can only email you when opened at an address you have confirmed here.',
+      emailVerified: 'Confirmed address:',
+      emailPlaceholder: 'you@example.com',
+      emailSend: 'Send code',
+      emailCodePlaceholder: 'Code from the email',
+      emailConfirm: 'Confirm',
     },
     fa: {
       title: 'ShredLink',
@@ -50,6 +134,7 @@
       oneTimePassword: 'یک‌بار رمز (بعد از یک بار وارد کردن صحیح رمز، لینک منقضی می‌شود)',
       createBtn: 'ساخت لینک',
       yourLink: 'لینک امن شما:',
+      yourRecipientLinks: 'برای هر نفر یک لینک که فقط یک بار باز می‌شود:',
       copy: 'کپی',
       copied: 'کپی شد!',
       createAnother: 'ساخت لینک دیگر',
@@ -58,6 +143,89 @@
       errorNetwork: 'خطای شبکه. اتصال را بررسی کنید.',
       errorGeneric: 'ساخت لینک ناموفق بود. دوباره تلاش کنید.',
       copyManual: 'انتخاب و کپی دستی',
+      navAccount: 'لینک‌های من',
+      navCombine: 'ترکیب سهم‌ها',
+      accountTitle: 'لینک‌های من',
+      accountIntro: 'برای دیدن لینک‌هایی که ساخته‌اید وارد شوید. محتوا هرگز اینجا نمایش داده نمی‌شود، فقط اطلاعات لینک.',
+      usernameLabel: 'نام کاربری',
+      accountPasswordLabel: 'رمز عبور',
+      signIn: 'ورود',
+      signUp: 'ساخت حساب',
+      signOut: 'خروج',
+      signedInAs: 'واردشده با',
+      noLinks: 'هنوز در حالت ورود لینکی نساخته‌اید.',
+      colLink: 'لینک',
+      colStatus: 'وضعیت',
+      colViews: 'بازدید',
+      colExpires: 'انقضا',
+      colCreated: 'ساخته‌شده',
+      statusActive: 'فعال',
+      statusExpired: 'منقضی',
+      statusConsumed: 'استفاده‌شده',
+      never: 'هرگز',
+      revoke: 'حذف',
+      revokeConfirm: 'این لینک حذف شود؟ پس از آن قابل باز شدن نیست.',
+      requestsTitle: 'درخواست‌های راز',
+      requestsIntro: 'از کسی بخواهید رازی برایتان بفرستد. لینک را برایش بفرستید؛ پاسخ برای کلید گیرنده شما مهر می‌شود و فقط یک بار اینجا می‌توانید بخوانید.',
+      requestKeyMissing: 'پاسخ برای کلیدی مهر شده که این مرورگر ندارد. آن را با CLI بخوانید یا کلید را در بخش کلید گیرنده وارد کنید.',
+      requestNotePlaceholder: 'چه چیزی باید بفرستد؟ (اختیاری)',
+      requestCreate: 'ساخت لینک درخواست',
+      requestCreatedHint: 'این لینک را برای کسی که باید پاسخ دهد بفرستید:',
+      requestAnswerHint: 'این پاسخ از سرور حذف شد. همین حالا آن را کپی کنید.',
+      noRequests: 'درخواست بازی وجود ندارد.',
+      colRequest: 'درخواست',
+      statusPending: 'در انتظار',
+      statusSubmitted: 'پاسخ داده شده',
+      requestRead: 'خواندن',
+      requestReadConfirm: 'پاسخ خوانده شود؟ به محض نمایش از سرور حذف می‌شود.',
+      requestDeleteConfirm: 'این درخواست حذف شود؟ لینک آن دیگر کار نمی‌کند.',
+      tagPassword: 'رمزدار',
+      tagOneTime: 'یک‌بار',
+      errorAuth: 'ورود ناموفق بود.',
+      signInSso: 'ورود با SSO',
+      orDivider: 'یا',
+      errorSignInToCreate: 'برای ساخت لینک وارد شوید.',
+      notBeforeLabel: 'در دسترس از (اختیاری)',
+      expiresAtLabel: 'انقضا در (اختیاری)',
+      expiresAtHint: 'جایگزین «انقضا پس از» می‌شود.',
+      notifyEmailLabel: 'وقتی باز شد یا بدون باز شدن منقضی شد به من ایمیل بزن (اختیاری)',
+      notifyEmailHint: 'باید همان ایمیلی باشد که در صفحه حساب تأیید کرده‌اید.',
+      errorSignInToNotify: 'برای دریافت اعلان ایمیلی وارد شوید.',
+      statusScheduled: 'زمان‌بندی‌شده',
+      idleLabel: 'انقضا پس از عدم استفاده',
+      idleNone: 'هرگز',
+      tagIdle: 'عدم استفاده',
+      recipientLabel: 'مهر کردن برای یک گیرنده (اختیاری)',
+      recipientHint: 'کلید عمومی او (slpub_…) یا @نام‌کاربری. فقط کلید او لینک را باز می‌کند.',
+      errorSignInToSeal: 'برای مهر کردن لینک با نام کاربری وارد شوید.',
+      recipientsLabel: 'ارسال برای چند نفر (اختیاری)',
+      recipientsHint: 'هر نام در یک خط. هر نفر لینک یک‌بارمصرف خودش را می‌گیرد و در «لینک‌های من» می‌بینید چه کسی آن را باز کرده است. نیاز به ورود دارد.',
+      errorSignInToShareMany: 'برای ارسال لینک به چند نفر وارد شوید.',
+      tagRecipients: 'گیرنده',
+      tagNetworks: 'فقط از',
+      allowedCidrsLabel: 'فقط از این شبکه‌ها باز شود (اختیاری)',
+      allowedCidrsHint: 'CIDR یا آدرس IP، جدا شده با ویرگول. در جای دیگر لینک طوری رفتار می‌کند که انگار وجود ندارد.',
+      recipientOpened: 'باز شده',
+      recipientNotOpened: 'باز نشده',
+      keyTitle: 'کلید گیرنده',
+      keyIntro: 'دیگران می‌توانند لینک‌ها را برای ‎@نام‌کاربری شما مهر کنند تا فقط کلید شما آن‌ها را باز کند، حتی اگر لینک فاش شود.',
+      keyRegistered: 'کلید عمومی ثبت‌شده:',
+      keyBrowserMissing: 'این مرورگر کلید خصوصی متناظر را ندارد؛ لینک‌های مهرشده را با CLI باز کنید یا کلید را در زیر وارد کنید.',
+      keyInputPlaceholder: 'slpub_… یا slsec_…',
+      keyRegister: 'ثبت کلید',
+      keyGenerate: 'ساخت کلید در این مرورگر',
+      keyRemove: 'حذف کلید',
+      keyInvalid: 'این کلید معتبر نیست.',
+      keyUnsupported: 'این مرورگر نمی‌تواند کلید X25519 بسازد. از shredlink key gen استفاده کنید و کلید عمومی آن را ثبت کنید.',
+      keyReplaceConfirm: 'کلید این مرورگر جایگزین شود؟ لینک‌هایی که برای کلید قبلی مهر شده‌اند دیگر اینجا باز نمی‌شوند.',
+      keyRemoveConfirm: 'کلید ثبت‌شده حذف شود؟ لینک‌هایی که قبلاً برای آن مهر شده‌اند همچنان به آن نیاز دارند.',
+      emailTitle: 'ایمیل اعلان',
+      emailIntro: 'لینک‌ها فقط به نشانی‌ای که اینجا تأیید کرده‌اید از باز شدنشان ایمیل می‌فرستند.',
+      emailVerified: 'نشانی تأییدشده:',
+      emailPlaceholder: 'you@example.com',
+      emailSend: 'ارسال کد',
+      emailCodePlaceholder: 'کد داخل ایمیل',
+      emailConfirm: 'تأیید',
     },
   };
 
@@ -77,7 +245,7 @@
       var key = el.getAttribute('data-i18n-placeholder');
       if (t[currentLang][key]) el.placeholder = t[currentLang][key];
     });
-    document.querySelectorAll('select#expire option').forEach(function (opt) {
+    document.querySelectorAll('select#expire option, select#idle_timeout option').forEach(function (opt) {
       var key = opt.getAttribute('data-i18n');
       if (key && t[currentLang][key]) opt.textContent = t[currentLang][key];
     });
@@ -106,6 +274,7 @@
       currentLang = lang;
       localStorage.setItem(STORAGE_LANG, currentLang);
       applyLang();
+      document.dispatchEvent(new Event('shredlink:lang'));
     },
     applyLang: applyLang,
     initDark: initDark,
@@ -0,0 +1,54 @@
+(function () {
+  'use strict';
+
+  // Proof-of-work for servers with POW_ENABLED. Anonymous create/split/unlock requests are
+  // answered with 428 and { challenge, difficulty }; find a solution such that
+  // SHA-256("{challenge}:{solution}") starts with `difficulty` zero bits and repeat the
+  // request with "X-ShredLink-PoW: {challenge}:{solution}". Same scheme as server/src/pow.rs.
+  var HEADER = 'X-ShredLink-PoW';
+  var MAX_ATTEMPTS = 3;
+  // Hashes started at once; WebCrypto digests are async, so batching keeps it fast.
+  var BATCH = 512;
+
+  function leadingZeroBits(bytes) {
+    var bits = 0;
+    for (var i = 0; i < bytes.length; i++) {
+      if (bytes[i] === 0) {
+        bits += 8;
+        continue;
+      }
+      return bits + Math.clz32(bytes[i]) - 24;
+    }
+    return bits;
+  }
+
+  async function solve(challenge, difficulty) {
+    var enc = new TextEncoder();
+    for (var start = 0; ; start += BATCH) {
+      var values = [];
+      for (var i = start; i < start + BATCH; i++) values.push(challenge + ':' + i.toString(16));
+      var hashes = await Promise.all(values.map(function (v) {
+        return crypto.subtle.digest('SHA-256', enc.encode(v));
+      }));
+      for (var j = 0; j < hashes.length; j++) {
+        if (leadingZeroBits(new Uint8Array(hashes[j])) >= difficulty) return values[j];
+      }
+    }
+  }
+
+  // fetch() that answers 428 challenges by solving them and retrying.
+  async function powFetch(url, options) {
+    options = options || {};
+    var res = await fetch(url, options);
+    for (var attempt = 0; res.status === 428 && attempt < MAX_ATTEMPTS; attempt++) {
+      var data = await res.clone().json().catch(function () { return {}; });
+      if (!data.challenge) break;
+      var headers = new Headers(options.headers || {});
+      headers.set(HEADER, await solve(data.challenge, data.difficulty));
+      res = await fetch(url, Object.assign({}, options, { headers: headers }));
+    }
+    return res;
+  }
+
+  window.shredlinkPow = { fetch: powFetch, solve: solve };
+})();
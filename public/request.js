@@ -0,0 +1,165 @@
+(function () {
+  'use strict';
+
+  var params = new URLSearchParams(window.location.search);
+  var token = params.get('token') || '';
+
+  var msgs = {
+    en: {
+      title: 'Someone asked you for a secret',
+      desc: 'What you send is encrypted and can be read once, only by the person who asked.',
+      back: 'Back home',
+      send: 'Send securely',
+      done: 'Sent. This link no longer works.',
+      placeholder: 'Paste the secret here',
+      closed: 'This request has expired or has already been answered.',
+      notFound: 'This link does not exist or has been removed.',
+      tooLarge: 'The text is too long.',
+      sealFailed: 'This browser cannot encrypt the secret. Try a current version of Firefox, Chrome or Safari.',
+      noKey: 'This request cannot be answered: it has no key to encrypt to.',
+      network: 'Network error'
+    },
+    fa: {
+      title: 'کسی از شما یک راز خواسته است',
+      desc: 'آنچه می‌فرستید رمزنگاری می‌شود و فقط یک بار، و فقط توسط درخواست‌کننده، قابل خواندن است.',
+      back: 'بازگشت به خانه',
+      send: 'ارسال امن',
+      done: 'ارسال شد. این لینک دیگر کار نمی‌کند.',
+      placeholder: 'راز را اینجا وارد کنید',
+      closed: 'این درخواست منقضی شده یا قبلاً پاسخ داده شده است.',
+      notFound: 'این لینک وجود ندارد یا حذف شده است.',
+      tooLarge: 'متن بیش از حد طولانی است.',
+      sealFailed: 'این مرورگر نمی‌تواند راز را رمزنگاری کند. نسخهٔ جدید Firefox، Chrome یا Safari را امتحان کنید.',
+      noKey: 'به این درخواست نمی‌توان پاسخ داد: کلیدی برای رمزنگاری ندارد.',
+      network: 'خطای شبکه'
+    }
+  };
+
+  function lang() {
+    return document.documentElement.getAttribute('data-lang') || 'en';
+  }
+
+  function m() {
+    return msgs[lang()] || msgs.en;
+  }
+
+  function applyLang(l) {
+    var t = msgs[l] || msgs.en;
+    document.querySelectorAll('[data-msg]').forEach(function (el) {
+      var key = el.getAttribute('data-msg');
+      if (t[key]) el.textContent = t[key];
+    });
+    var input = document.getElementById('secret-input');
+    if (input) input.placeholder = t.placeholder;
+    document.querySelectorAll('.lang-btn').forEach(function (el) {
+      el.classList.toggle('font-medium', el.id === 'lang-' + (l === 'fa' ? 'fa' : 'en'));
+    });
+  }
+
+  function setLang(l) {
+    localStorage.setItem('shredlink_lang', l);
+    document.documentElement.lang = l;
+    document.documentElement.dir = l === 'fa' ? 'rtl' : 'ltr';
+    document.documentElement.setAttribute('data-lang', l);
+    document.body.classList.toggle('lang-fa', l === 'fa');
+    applyLang(l);
+  }
+
+  function initUi() {
+    var l = lang();
+    document.body.classList.toggle('lang-fa', l === 'fa');
+    applyLang(l);
+    document.getElementById('dark-toggle').addEventListener('click', function () {
+      document.documentElement.classList.toggle('dark');
+      localStorage.setItem('shredlink_dark', document.documentElement.classList.contains('dark') ? '1' : '0');
+    });
+    document.getElementById('lang-en').addEventListener('click', function () { setLang('en'); });
+    document.getElementById('lang-fa').addEventListener('click', function () { setLang('fa'); });
+  }
+
+  initUi();
+
+  var form = document.getElementById('submit-form');
+  var errEl = document.getElementById('err');
+  var submitBtn = document.getElementById('submit-btn');
+  var url = '/api/v1/requests/submit/' + encodeURIComponent(token);
+  // The requester's key; the secret is sealed to it here, so only ciphertext is sent.
+  var publicKey = '';
+
+  function showError(key) {
+    errEl.textContent = m()[key] || key;
+    errEl.classList.remove('hidden');
+  }
+
+  function statusError(status) {
+    if (status === 404) return 'notFound';
+    if (status === 410) return 'closed';
+    if (status === 413) return 'tooLarge';
+    return 'network';
+  }
+
+  fetch(url)
+    .then(function (r) {
+      if (!r.ok) {
+        showError(statusError(r.status));
+        return;
+      }
+      return r.json().then(function (info) {
+        if (!info.public_key) {
+          showError('noKey');
+          return;
+        }
+        publicKey = info.public_key;
+        if (info.note) {
+          var note = document.getElementById('note');
+          note.textContent = info.note;
+          note.classList.remove('hidden');
+        }
+        form.classList.remove('hidden');
+      });
+    })
+    .catch(function () { showError('network'); });
+
+  form.addEventListener('submit', function (e) {
+    e.preventDefault();
+    errEl.classList.add('hidden');
+    submitBtn.disabled = true;
+    window.shredlinkEnvelope
+      .seal(form.text.value, publicKey)
+      .then(
+        function (sealed) {
+          return fetch(url, {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ text: sealed })
+          });
+        },
+        function () {
+          submitBtn.disabled = false;
+          showError('sealFailed');
+        }
+      )
+      .then(function (r) {
+        if (!r) return;
+        if (r.ok) {
+          form.reset();
+          form.classList.add('hidden');
+          document.getElementById('done').classList.remove('hidden');
+          return;
+        }
+        return r.json().catch(function () { return {}; }).then(function (data) {
+          submitBtn.disabled = false;
+          if (r.status === 400 || r.status === 429) {
+            errEl.textContent = data.error || m().network;
+            errEl.classList.remove('hidden');
+          } else {
+            showError(statusError(r.status));
+          }
+        });
+      })
+      .catch(function () {
+        submitBtn.disabled = false;
+        showError('network');
+      });
+  });
+})();
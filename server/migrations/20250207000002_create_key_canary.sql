@@ -0,0 +1,7 @@
+-- Single-row table holding a value encrypted with ENCRYPTION_KEY.
+-- Readiness checks decrypt it to catch a key that no longer matches stored data.
+CREATE TABLE IF NOT EXISTS key_canary (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    encrypted_value TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
@@ -0,0 +1,19 @@
+-- API keys for authenticated creation. Only a SHA-256 hash of the key is stored;
+-- key_prefix is the non-secret leading part used to identify keys in listings.
+CREATE TABLE IF NOT EXISTS api_keys (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    key_prefix TEXT NOT NULL,
+    key_hash TEXT NOT NULL UNIQUE,
+    scopes TEXT NOT NULL,
+    rate_limit_per_minute INTEGER NOT NULL,
+    create_count INTEGER NOT NULL DEFAULT 0,
+    read_count INTEGER NOT NULL DEFAULT 0,
+    last_used_at TEXT,
+    revoked_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+-- Links created with an API key are attributed to it (NULL for anonymous links).
+ALTER TABLE links ADD COLUMN api_key_id INTEGER REFERENCES api_keys(id);
+CREATE INDEX IF NOT EXISTS idx_links_api_key_id ON links(api_key_id);
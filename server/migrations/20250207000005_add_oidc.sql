@@ -0,0 +1,16 @@
+-- Accounts signed in through OpenID Connect are keyed by issuer + subject.
+-- They have no local password (password_hash is empty and never verifies).
+ALTER TABLE users ADD COLUMN oidc_issuer TEXT;
+ALTER TABLE users ADD COLUMN oidc_subject TEXT;
+-- Comma-separated groups from the IdP's groups claim, refreshed on every sign-in.
+ALTER TABLE users ADD COLUMN groups TEXT NOT NULL DEFAULT '';
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_oidc ON users(oidc_issuer, oidc_subject);
+
+-- In-flight authorization requests (state -> PKCE verifier, nonce), valid for a few minutes.
+CREATE TABLE IF NOT EXISTS oidc_logins (
+    state TEXT PRIMARY KEY,
+    pkce_verifier TEXT NOT NULL,
+    nonce TEXT NOT NULL,
+    return_to TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
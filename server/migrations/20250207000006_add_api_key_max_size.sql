@@ -0,0 +1,2 @@
+-- Optional per-key text size limit; NULL uses MAX_TEXT_SIZE_BYTES.
+ALTER TABLE api_keys ADD COLUMN max_text_size_bytes INTEGER;
@@ -0,0 +1,2 @@
+-- Optional go-live time; the link cannot be read before it.
+ALTER TABLE links ADD COLUMN not_before TEXT;
@@ -0,0 +1,4 @@
+-- Optional inactivity expiry: the link expires idle_timeout_minutes after the
+-- last read (or after going live, if it was never read).
+ALTER TABLE links ADD COLUMN idle_timeout_minutes INTEGER;
+ALTER TABLE links ADD COLUMN last_accessed_at TEXT;
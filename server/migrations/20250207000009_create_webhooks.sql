@@ -0,0 +1,21 @@
+-- Read receipts: links get a public id (safe to share, unlike the token) and an
+-- optional webhook URL. Events wait in webhook_deliveries until delivered.
+ALTER TABLE links ADD COLUMN public_id TEXT;
+UPDATE links SET public_id = lower(hex(randomblob(8))) WHERE public_id IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_links_public_id ON links(public_id);
+ALTER TABLE links ADD COLUMN webhook_url TEXT;
+
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    url TEXT NOT NULL,
+    event TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TEXT NOT NULL,
+    last_error TEXT,
+    -- Set when the delivery gave up after WEBHOOK_MAX_ATTEMPTS.
+    failed_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due ON webhook_deliveries(failed_at, next_attempt_at);
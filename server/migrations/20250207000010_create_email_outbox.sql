@@ -0,0 +1,20 @@
+-- Email notifications: the address to notify and the template language per link,
+-- and rendered messages waiting to be sent.
+ALTER TABLE links ADD COLUMN notify_email TEXT;
+ALTER TABLE links ADD COLUMN notify_lang TEXT;
+
+CREATE TABLE IF NOT EXISTS email_outbox (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    recipient TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    text_body TEXT NOT NULL,
+    html_body TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TEXT NOT NULL,
+    last_error TEXT,
+    -- Set when sending gave up after SMTP_MAX_ATTEMPTS.
+    failed_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_email_outbox_due ON email_outbox(failed_at, next_attempt_at);
@@ -0,0 +1,20 @@
+-- Secret requests: a signed-in user or API key asks someone else to send them a
+-- secret. The submitter opens /r/{token} and fills in encrypted_text once; only
+-- the owner can read it, after which the row is deleted.
+CREATE TABLE IF NOT EXISTS secret_requests (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    public_id TEXT NOT NULL UNIQUE,
+    token TEXT NOT NULL UNIQUE,
+    -- Shown to the submitter, e.g. what to send and why.
+    note TEXT,
+    user_id INTEGER REFERENCES users(id) ON DELETE CASCADE,
+    api_key_id INTEGER REFERENCES api_keys(id),
+    encrypted_text TEXT,
+    expires_at TEXT NOT NULL,
+    submitted_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_secret_requests_user_id ON secret_requests(user_id);
+CREATE INDEX IF NOT EXISTS idx_secret_requests_api_key_id ON secret_requests(api_key_id);
+CREATE INDEX IF NOT EXISTS idx_secret_requests_expires_at ON secret_requests(expires_at);
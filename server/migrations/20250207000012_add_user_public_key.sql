@@ -0,0 +1,3 @@
+-- Optional X25519 recipient key (`slpub_...`) so others can seal links to a user
+-- by username. Only the public half is ever stored.
+ALTER TABLE users ADD COLUMN public_key TEXT;
@@ -0,0 +1,15 @@
+-- Multi-recipient links: each named recipient gets a one-time token for the same
+-- links row. recipient_count > 0 marks such a link; its own token is only the
+-- creator's handle, and it counts as consumed once view_count reaches recipient_count.
+ALTER TABLE links ADD COLUMN recipient_count INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS link_recipients (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    link_id INTEGER NOT NULL REFERENCES links(id) ON DELETE CASCADE,
+    token TEXT NOT NULL UNIQUE,
+    label TEXT NOT NULL,
+    viewed_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_link_recipients_link_id ON link_recipients(link_id);
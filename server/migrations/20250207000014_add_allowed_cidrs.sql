@@ -0,0 +1,3 @@
+-- Optional network restriction: comma-separated CIDRs the link may be opened
+-- from (matched against the client IP). NULL means anywhere.
+ALTER TABLE links ADD COLUMN allowed_cidrs TEXT;
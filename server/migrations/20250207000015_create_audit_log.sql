@@ -0,0 +1,30 @@
+-- Tamper-evident audit log of link events. Rows carry the link's public id and
+-- hashed client details, never tokens or content. Each row's hash covers the
+-- previous row's hash, so edits and gaps show up in `audit verify`.
+CREATE TABLE IF NOT EXISTS audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    created_at TEXT NOT NULL,
+    event TEXT NOT NULL,
+    link_id TEXT,
+    actor TEXT,
+    detail TEXT,
+    ip_hash TEXT,
+    user_agent_hash TEXT,
+    -- Set in the same transaction as the insert, once the row's id is known.
+    prev_hash TEXT,
+    hash TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_link_id ON audit_log(link_id);
+
+-- Append-only: rows can be sealed once but never changed or removed afterwards.
+CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+WHEN OLD.hash IS NOT NULL
+BEGIN
+    SELECT RAISE(ABORT, 'audit_log is append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'audit_log is append-only');
+END;
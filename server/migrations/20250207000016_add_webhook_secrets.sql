@@ -0,0 +1,6 @@
+-- Per-link webhook signing secrets: a link's own notify_webhook is signed with a
+-- secret returned once at creation, so receivers never need WEBHOOK_SECRET.
+-- Deliveries copy it because the link may be gone before they are sent; NULL
+-- means WEBHOOK_SECRET (the server default URL, and links created before this).
+ALTER TABLE links ADD COLUMN webhook_secret TEXT;
+ALTER TABLE webhook_deliveries ADD COLUMN secret TEXT;
@@ -0,0 +1,8 @@
+-- notify_email may only name the creator's own verified address. Local accounts
+-- confirm one with an emailed code, SSO accounts take the IdP's verified email
+-- claim, and API keys get one from the operator.
+ALTER TABLE users ADD COLUMN email TEXT;
+ALTER TABLE users ADD COLUMN pending_email TEXT;
+ALTER TABLE users ADD COLUMN email_code_hash TEXT;
+ALTER TABLE users ADD COLUMN email_code_expires_at TEXT;
+ALTER TABLE api_keys ADD COLUMN email TEXT;
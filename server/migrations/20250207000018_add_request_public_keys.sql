@@ -0,0 +1,4 @@
+-- Answers to secret requests are sealed to the requester's recipient key, so the
+-- server alone cannot read them. NULL for requests created before this, whose
+-- answers are only encrypted with ENCRYPTION_KEY.
+ALTER TABLE secret_requests ADD COLUMN public_key TEXT;
@@ -0,0 +1,262 @@
+//! Optional user accounts: username/password login with Argon2 and cookie sessions.
+//! Accounts only ever see link metadata; content stays encrypted and is never listed.
+
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::{HttpRequest, HttpResponse};
+use anyhow::{Context, Result};
+use chrono::{Duration, Utc};
+use nanoid::nanoid;
+use sqlx::FromRow;
+
+use crate::config::Config;
+use crate::crypto::sha256_hex;
+use crate::db::DbPool;
+use crate::oidc::Identity;
+use shredlink_types::v1::ErrorResponse;
+
+pub const SESSION_COOKIE: &str = "shredlink_session";
+const SESSION_ID_LEN: usize = 43;
+const EMAIL_CODE_LEN: usize = 12;
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct User {
+    pub id: i64,
+    pub username: String,
+    pub password_hash: String,
+    /// Comma-separated IdP groups; empty for local accounts.
+    pub groups: String,
+    /// Registered recipient key (`slpub_...`), if any.
+    pub public_key: Option<String>,
+    /// Confirmed address that `notify_email` may name.
+    pub email: Option<String>,
+}
+
+impl User {
+    pub fn group_list(&self) -> Vec<String> {
+        self.groups
+            .split(',')
+            .filter(|g| !g.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+/// 3–32 characters from `[A-Za-z0-9._-]`.
+pub fn valid_username(name: &str) -> bool {
+    (3..=32).contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+/// Inserts a user. Returns `None` if the username is taken.
+pub async fn create_user(pool: &DbPool, username: &str, password_hash: &str) -> Result<Option<i64>> {
+    let r = sqlx::query("INSERT OR IGNORE INTO users (username, password_hash) VALUES (?, ?)")
+        .bind(username)
+        .bind(password_hash)
+        .execute(pool)
+        .await?;
+    Ok((r.rows_affected() > 0).then(|| r.last_insert_rowid()))
+}
+
+/// Turns an IdP username into a valid local one (`valid_username`).
+fn local_username(preferred: &str, subject: &str) -> String {
+    let cleaned: String = preferred
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') { c } else { '-' })
+        .take(32)
+        .collect();
+    if valid_username(&cleaned) {
+        cleaned
+    } else {
+        format!("user-{}", &sha256_hex(subject)[..8])
+    }
+}
+
+/// Finds or creates the account for an OIDC identity and refreshes its groups and,
+/// if the IdP vouches for one, its verified email.
+/// A username already used by another account gets a suffix derived from the subject.
+pub async fn upsert_oidc_user(pool: &DbPool, identity: &Identity) -> Result<User> {
+    let groups = identity.groups.join(",");
+    let existing: Option<(i64,)> = sqlx::query_as("SELECT id FROM users WHERE oidc_issuer = ? AND oidc_subject = ?")
+        .bind(&identity.issuer)
+        .bind(&identity.subject)
+        .fetch_optional(pool)
+        .await?;
+    let id = match existing {
+        Some((id,)) => {
+            sqlx::query("UPDATE users SET groups = ?, email = COALESCE(?, email) WHERE id = ?")
+                .bind(&groups)
+                .bind(&identity.email)
+                .bind(id)
+                .execute(pool)
+                .await?;
+            id
+        }
+        None => {
+            let base = local_username(&identity.username, &identity.subject);
+            let suffixed = format!(
+                "{}-{}",
+                base.chars().take(23).collect::<String>(),
+                &sha256_hex(&identity.subject)[..8]
+            );
+            let mut inserted = None;
+            for username in [base, suffixed] {
+                let r = sqlx::query(
+                    "INSERT OR IGNORE INTO users (username, password_hash, oidc_issuer, oidc_subject, groups, email) \
+                     VALUES (?, '', ?, ?, ?, ?)",
+                )
+                .bind(&username)
+                .bind(&identity.issuer)
+                .bind(&identity.subject)
+                .bind(&groups)
+                .bind(&identity.email)
+                .execute(pool)
+                .await?;
+                if r.rows_affected() > 0 {
+                    inserted = Some(r.last_insert_rowid());
+                    break;
+                }
+            }
+            inserted.context("no free username for this account")?
+        }
+    };
+    Ok(
+        sqlx::query_as::<_, User>("SELECT id, username, password_hash, groups, public_key, email FROM users WHERE id = ?")
+            .bind(id)
+            .fetch_one(pool)
+            .await?,
+    )
+}
+
+pub async fn find_user(pool: &DbPool, username: &str) -> Result<Option<User>> {
+    Ok(
+        sqlx::query_as::<_, User>("SELECT id, username, password_hash, groups, public_key, email FROM users WHERE username = ?")
+            .bind(username)
+            .fetch_optional(pool)
+            .await?,
+    )
+}
+
+/// Starts confirming `email` for the user and returns the code to mail to it. The
+/// current verified address stays in place until the code is confirmed.
+pub async fn start_email_verification(pool: &DbPool, user_id: i64, email: &str) -> Result<String> {
+    let code = nanoid!(EMAIL_CODE_LEN, &nanoid::alphabet::SAFE[2..]);
+    let expires_at = Utc::now() + Duration::minutes(crate::email::VERIFICATION_TTL_MINUTES);
+    sqlx::query("UPDATE users SET pending_email = ?, email_code_hash = ?, email_code_expires_at = ? WHERE id = ?")
+        .bind(email)
+        .bind(sha256_hex(&code))
+        .bind(expires_at.to_rfc3339())
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(code)
+}
+
+/// Makes the pending address the user's verified email if `code` matches and has
+/// not expired. Returns the now verified address.
+pub async fn confirm_email(pool: &DbPool, user_id: i64, code: &str) -> Result<Option<String>> {
+    Ok(sqlx::query_scalar::<_, Option<String>>(
+        "UPDATE users SET email = pending_email, pending_email = NULL, email_code_hash = NULL, email_code_expires_at = NULL \
+         WHERE id = ? AND pending_email IS NOT NULL AND email_code_hash = ? AND datetime(email_code_expires_at) > datetime(?) \
+         RETURNING email",
+    )
+    .bind(user_id)
+    .bind(sha256_hex(code.trim()))
+    .bind(Utc::now().to_rfc3339())
+    .fetch_optional(pool)
+    .await?
+    .flatten())
+}
+
+/// Registers (or with `None` removes) the user's recipient key.
+pub async fn set_public_key(pool: &DbPool, user_id: i64, public_key: Option<&str>) -> Result<()> {
+    sqlx::query("UPDATE users SET public_key = ? WHERE id = ?")
+        .bind(public_key)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Starts a session and returns the cookie carrying its id.
+pub async fn create_session(pool: &DbPool, config: &Config, user_id: i64) -> Result<Cookie<'static>> {
+    let id = nanoid!(SESSION_ID_LEN);
+    let expires_at = Utc::now() + Duration::hours(config.session_ttl_hours as i64);
+    sqlx::query("INSERT INTO sessions (id_hash, user_id, expires_at) VALUES (?, ?, ?)")
+        .bind(sha256_hex(&id))
+        .bind(user_id)
+        .bind(expires_at.to_rfc3339())
+        .execute(pool)
+        .await?;
+    Ok(session_cookie(config, id, actix_web::cookie::time::Duration::hours(config.session_ttl_hours as i64)))
+}
+
+/// Cookie that clears the session in the browser.
+pub fn removal_cookie(config: &Config) -> Cookie<'static> {
+    session_cookie(config, String::new(), actix_web::cookie::time::Duration::ZERO)
+}
+
+fn session_cookie(config: &Config, value: String, max_age: actix_web::cookie::time::Duration) -> Cookie<'static> {
+    Cookie::build(SESSION_COOKIE, value)
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .secure(config.base_url.starts_with("https://"))
+        .max_age(max_age)
+        .finish()
+}
+
+/// The signed-in user, if the request carries a live session cookie.
+pub async fn session_user(pool: &DbPool, req: &HttpRequest) -> Result<Option<User>> {
+    let Some(cookie) = req.cookie(SESSION_COOKIE) else {
+        return Ok(None);
+    };
+    if cookie.value().is_empty() {
+        return Ok(None);
+    }
+    Ok(sqlx::query_as::<_, User>(
+        "SELECT users.id, users.username, users.password_hash, users.groups, users.public_key, users.email FROM sessions \
+         JOIN users ON users.id = sessions.user_id \
+         WHERE sessions.id_hash = ? AND datetime(sessions.expires_at) > datetime(?)",
+    )
+    .bind(sha256_hex(cookie.value()))
+    .bind(Utc::now().to_rfc3339())
+    .fetch_optional(pool)
+    .await?)
+}
+
+/// Like `session_user`, but answers 401 when nobody is signed in.
+pub async fn require_user(pool: &DbPool, req: &HttpRequest) -> Result<User, HttpResponse> {
+    match session_user(pool, req).await {
+        Ok(Some(user)) => Ok(user),
+        Ok(None) => Err(HttpResponse::Unauthorized().json(ErrorResponse {
+            error: "Sign in required".to_string(),
+        })),
+        Err(e) => {
+            tracing::warn!("session lookup: {}", e);
+            Err(HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Server error".to_string(),
+            }))
+        }
+    }
+}
+
+pub async fn delete_session(pool: &DbPool, req: &HttpRequest) -> Result<()> {
+    if let Some(cookie) = req.cookie(SESSION_COOKIE) {
+        sqlx::query("DELETE FROM sessions WHERE id_hash = ?")
+            .bind(sha256_hex(cookie.value()))
+            .execute(pool)
+            .await?;
+    }
+    Ok(())
+}
+
+pub async fn delete_expired_sessions(pool: &DbPool) -> Result<u64> {
+    let r = sqlx::query("DELETE FROM sessions WHERE datetime(expires_at) <= datetime(?)")
+        .bind(Utc::now().to_rfc3339())
+        .execute(pool)
+        .await?;
+    Ok(r.rows_affected())
+}
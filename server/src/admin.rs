@@ -0,0 +1,275 @@
+//! Operator subcommands that act on the database directly, using the same `Config` as `serve`.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+
+use crate::api_keys;
+use crate::audit;
+use crate::config::Config;
+use crate::crypto::{decrypt_text, encrypt_text, generate_key};
+use crate::db::{self, DbPool, IDLE_EXPIRED};
+use crate::middleware::delete_expired_or_invalid;
+
+async fn open_pool(config: &Config) -> Result<DbPool> {
+    let pool = db::create_pool(config).await?;
+    db::run_migrations(&pool).await?;
+    Ok(pool)
+}
+
+/// Accepts either a bare token or a share URL ending in `/s/{token}`.
+fn token_from_arg(arg: &str) -> &str {
+    let arg = arg.trim().trim_end_matches('/');
+    match arg.rfind("/s/") {
+        Some(i) => &arg[i + 3..],
+        None => arg,
+    }
+}
+
+pub async fn migrate(config: &Config) -> Result<()> {
+    let pool = db::create_pool(config).await?;
+    let pending = db::pending_migrations(&pool).await.unwrap_or_default();
+    db::run_migrations(&pool).await?;
+    if pending.is_empty() {
+        println!("database is up to date");
+    } else {
+        println!("applied {} migration(s): {:?}", pending.len(), pending);
+    }
+    db::close_pool(&pool).await;
+    Ok(())
+}
+
+pub async fn purge_expired(config: &Config) -> Result<()> {
+    let pool = open_pool(config).await?;
+    let deleted = delete_expired_or_invalid(&pool, config).await?;
+    println!("deleted {} expired/invalid link(s)", deleted);
+    db::close_pool(&pool).await;
+    Ok(())
+}
+
+pub async fn stats(config: &Config) -> Result<()> {
+    let pool = open_pool(config).await?;
+    let sql = format!(
+        "SELECT COUNT(*), \
+         COALESCE(SUM(CASE WHEN (expires_at IS NULL OR datetime(expires_at) >= datetime(?1)) \
+           AND NOT (one_time_view = 1 AND view_count > 0) \
+           AND NOT (one_time_password = 1 AND password_used = 1) \
+           AND NOT (recipient_count > 0 AND view_count >= recipient_count) \
+           AND NOT {} THEN 1 ELSE 0 END), 0), \
+         COALESCE(SUM(CASE WHEN password_hash IS NOT NULL AND password_hash != '' THEN 1 ELSE 0 END), 0), \
+         COALESCE(SUM(CASE WHEN one_time_view = 1 OR one_time_password = 1 THEN 1 ELSE 0 END), 0) \
+         FROM links",
+        IDLE_EXPIRED
+    );
+    let (total, live, protected, one_time): (i64, i64, i64, i64) = sqlx::query_as(&sql)
+        .bind(Utc::now().to_rfc3339())
+        .fetch_one(&pool)
+        .await?;
+    let (oldest, newest): (Option<String>, Option<String>) =
+        sqlx::query_as("SELECT MIN(created_at), MAX(created_at) FROM links")
+            .fetch_one(&pool)
+            .await?;
+    println!("links total:              {}", total);
+    println!("links live:               {}", live);
+    println!("links expired/consumed:   {}", total - live);
+    println!("password-protected:       {}", protected);
+    println!("one-time:                 {}", one_time);
+    println!("oldest created_at:        {}", oldest.as_deref().unwrap_or("-"));
+    println!("newest created_at:        {}", newest.as_deref().unwrap_or("-"));
+    db::close_pool(&pool).await;
+    Ok(())
+}
+
+pub async fn revoke(config: &Config, arg: &str) -> Result<()> {
+    let token = token_from_arg(arg);
+    let pool = open_pool(config).await?;
+    let mut tx = pool.begin().await?;
+    let removed = sqlx::query_scalar::<_, Option<String>>("DELETE FROM links WHERE token = ? RETURNING public_id")
+        .bind(token)
+        .fetch_optional(&mut *tx)
+        .await?;
+    if let Some(link_id) = &removed {
+        let record = audit::Record::new(audit::Event::Revoked, link_id.as_deref()).actor("admin");
+        audit::append(&mut tx, config, &record).await?;
+    }
+    tx.commit().await?;
+    db::close_pool(&pool).await;
+    if removed.is_none() {
+        anyhow::bail!("no link with that token");
+    }
+    println!("link revoked");
+    Ok(())
+}
+
+/// Re-encrypts every link, secret request answer and the key canary in one transaction. All rows must
+/// decrypt with the current key first; nothing is written if any row fails.
+pub async fn rotate_key(config: &Config, new_key: Option<String>) -> Result<()> {
+    let generated = new_key.is_none();
+    let new_key = new_key.unwrap_or_else(generate_key);
+    // Validate the new key before touching data.
+    encrypt_text("", &new_key).context("invalid new key")?;
+
+    let pool = open_pool(config).await?;
+    db::check_key_canary(&pool, config.encryption_key_base64.expose())
+        .await
+        .context("ENCRYPTION_KEY does not match stored data; refusing to rotate")?;
+
+    let mut tx = pool.begin().await?;
+    let rows: Vec<(i64, String)> = sqlx::query_as("SELECT id, encrypted_text FROM links")
+        .fetch_all(&mut *tx)
+        .await?;
+    for (id, encrypted) in &rows {
+        let plaintext = decrypt_text(encrypted, config.encryption_key_base64.expose())
+            .with_context(|| format!("link id {} does not decrypt with ENCRYPTION_KEY", id))?;
+        let reencrypted = encrypt_text(&plaintext, &new_key)?;
+        sqlx::query("UPDATE links SET encrypted_text = ? WHERE id = ?")
+            .bind(&reencrypted)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    let answers: Vec<(i64, String)> =
+        sqlx::query_as("SELECT id, encrypted_text FROM secret_requests WHERE encrypted_text IS NOT NULL")
+            .fetch_all(&mut *tx)
+            .await?;
+    for (id, encrypted) in &answers {
+        let plaintext = decrypt_text(encrypted, config.encryption_key_base64.expose())
+            .with_context(|| format!("secret request id {} does not decrypt with ENCRYPTION_KEY", id))?;
+        sqlx::query("UPDATE secret_requests SET encrypted_text = ? WHERE id = ?")
+            .bind(encrypt_text(&plaintext, &new_key)?)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    sqlx::query("UPDATE key_canary SET encrypted_value = ? WHERE id = 1")
+        .bind(encrypt_text(db::KEY_CANARY_PLAINTEXT, &new_key)?)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    db::close_pool(&pool).await;
+
+    println!("re-encrypted {} link(s) and {} secret request answer(s)", rows.len(), answers.len());
+    if generated {
+        println!("new key: {}", new_key);
+    }
+    println!("set ENCRYPTION_KEY to the new key and restart the server");
+    Ok(())
+}
+
+pub async fn api_key_create(
+    config: &Config,
+    name: &str,
+    rate_limit: u32,
+    scopes: &str,
+    max_size: Option<u32>,
+    email: Option<&str>,
+) -> Result<()> {
+    if rate_limit == 0 {
+        anyhow::bail!("--rate-limit must be at least 1");
+    }
+    if max_size == Some(0) {
+        anyhow::bail!("--max-size must be at least 1");
+    }
+    if email.is_some_and(|e| !crate::email::check_address(e)) {
+        anyhow::bail!("--email is not a valid email address");
+    }
+    let scopes = api_keys::parse_scopes(scopes)?;
+    let pool = open_pool(config).await?;
+    let (id, key) = api_keys::insert(&pool, name, &scopes, rate_limit, max_size, email).await?;
+    db::close_pool(&pool).await;
+    println!("created API key {} ({}) with scopes {}", id, name, scopes);
+    println!("key: {}", key);
+    println!("store it now; it cannot be shown again");
+    Ok(())
+}
+
+pub async fn api_key_list(config: &Config) -> Result<()> {
+    let pool = open_pool(config).await?;
+    let keys = api_keys::list(&pool).await?;
+    if keys.is_empty() {
+        println!("no API keys");
+    }
+    for k in &keys {
+        let links = api_keys::count_links(&pool, k.id).await?;
+        let status = match &k.revoked_at {
+            Some(at) => format!("revoked {}", at),
+            None => "active".to_string(),
+        };
+        println!(
+            "{:>4}  {:<20} {}…  {}  {}/min  max {}  email {}  creates={} reads={} links={}  created {}  last used {}  {}",
+            k.id,
+            k.name,
+            k.key_prefix,
+            k.scopes,
+            k.rate_limit_per_minute,
+            k.max_text_size_bytes
+                .map_or_else(|| "default".to_string(), |n| format!("{}B", n)),
+            k.email.as_deref().unwrap_or("-"),
+            k.create_count,
+            k.read_count,
+            links,
+            k.created_at,
+            k.last_used_at.as_deref().unwrap_or("never"),
+            status,
+        );
+    }
+    db::close_pool(&pool).await;
+    Ok(())
+}
+
+pub async fn api_key_revoke(config: &Config, id: i64) -> Result<()> {
+    let pool = open_pool(config).await?;
+    let revoked = api_keys::revoke(&pool, id).await?;
+    db::close_pool(&pool).await;
+    if !revoked {
+        anyhow::bail!("no active API key with id {}", id);
+    }
+    println!("API key {} revoked", id);
+    Ok(())
+}
+
+pub async fn api_key_revoke_links(config: &Config, id: i64) -> Result<()> {
+    let pool = open_pool(config).await?;
+    let mut tx = pool.begin().await?;
+    let deleted = api_keys::delete_links(&mut tx, id).await?;
+    for link_id in &deleted {
+        let record = audit::Record::new(audit::Event::Revoked, link_id.as_deref())
+            .actor("admin")
+            .detail(format!("api_key:{}", id));
+        audit::append(&mut tx, config, &record).await?;
+    }
+    tx.commit().await?;
+    db::close_pool(&pool).await;
+    println!("deleted {} link(s) created with API key {}", deleted.len(), id);
+    Ok(())
+}
+
+/// Checks the audit log's hash chain; fails naming the first broken entry.
+pub async fn audit_verify(config: &Config) -> Result<()> {
+    let pool = open_pool(config).await?;
+    let verified = audit::verify(&pool, config).await;
+    db::close_pool(&pool).await;
+    let verified = verified.context("audit log verification failed")?;
+    println!("audit log OK: {} entries", verified.entries);
+    println!("head hash: {}", verified.head);
+    Ok(())
+}
+
+/// Writes audit entries after id `after` to stdout as JSON lines.
+pub async fn audit_export(config: &Config, after: i64) -> Result<()> {
+    use std::io::Write;
+
+    let pool = open_pool(config).await?;
+    let mut out = std::io::BufWriter::new(std::io::stdout().lock());
+    let mut last = after;
+    loop {
+        let entries = audit::page(&pool, last).await?;
+        let Some(tail) = entries.last() else { break };
+        last = tail.id;
+        for entry in &entries {
+            writeln!(out, "{}", serde_json::to_string(entry)?)?;
+        }
+    }
+    out.flush()?;
+    db::close_pool(&pool).await;
+    Ok(())
+}
@@ -0,0 +1,657 @@
+//! `/api/v1/account`: sign-up, sign-in, the recipient key and the dashboard of the signed-in user's links.
+//! Mounted under `/api/v1` only; there are no unversioned aliases for these routes.
+
+use actix_web::http::header::LOCATION;
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use super::recipients;
+use crate::accounts::{self, MIN_PASSWORD_LEN};
+use crate::audit;
+use crate::config::Config;
+use crate::crypto::{self, hash_password, verify_password};
+use crate::email;
+use crate::db::{DbPool, SELECT_LINKS_BY_USER};
+use crate::metrics::Metrics;
+use crate::middleware::{self, LinkState};
+use crate::models::LinkRow;
+use crate::oidc::{self, OidcClient};
+use shredlink_types::v1::{AccountOptions, AccountRequest, AccountResponse, EmailConfirmRequest, EmailRequest, ErrorResponse, LinkStatus, LinkSummary, PublicKeyRequest};
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/account")
+            .route("", web::get().to(me))
+            .route("/options", web::get().to(options))
+            .route("/oidc/login", web::get().to(oidc_login))
+            .route("/oidc/callback", web::get().to(oidc_callback))
+            .route("/register", web::post().to(register))
+            .route("/login", web::post().to(login))
+            .route("/logout", web::post().to(logout))
+            .route("/public-key", web::put().to(set_public_key))
+            .route("/public-key", web::delete().to(delete_public_key))
+            .route("/email", web::put().to(set_email))
+            .route("/email/confirm", web::post().to(confirm_email))
+            .route("/links", web::get().to(list_links))
+            .route("/links/{link_id}", web::delete().to(revoke_link)),
+    );
+}
+
+fn server_error() -> HttpResponse {
+    HttpResponse::InternalServerError().json(ErrorResponse {
+        error: "Server error".to_string(),
+    })
+}
+
+fn check_login_rate(limiter: &middleware::LoginRateLimiter, metrics: &Metrics, req: &HttpRequest) -> Result<(), HttpResponse> {
+    if let Some(ip) = middleware::peer_ip(req) {
+        if let Err(wait) = limiter.check(&ip) {
+            metrics.rate_limited.inc();
+            let retry_after = wait.as_millis().div_ceil(1000).max(1);
+            return Err(HttpResponse::TooManyRequests()
+                .insert_header(("Retry-After", retry_after.to_string()))
+                .json(ErrorResponse {
+                    error: "Too many attempts. Try again later.".to_string(),
+                }));
+        }
+    }
+    Ok(())
+}
+
+/// Responds with the account and a fresh session cookie.
+async fn signed_in(pool: &DbPool, config: &Config, user_id: i64, account: AccountResponse) -> HttpResponse {
+    match accounts::create_session(pool, config, user_id).await {
+        Ok(cookie) => HttpResponse::Ok().cookie(cookie).json(account),
+        Err(e) => {
+            tracing::warn!("create session: {}", e);
+            server_error()
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/account/register",
+    tag = "account",
+    request_body = AccountRequest,
+    responses(
+        (status = 200, description = "Account created and signed in; sets the session cookie", body = AccountResponse),
+        (status = 400, description = "Invalid username or password too short", body = ErrorResponse),
+        (status = 403, description = "Sign-up is disabled (SIGNUP_ENABLED=false)", body = ErrorResponse),
+        (status = 409, description = "Username taken", body = ErrorResponse),
+        (status = 429, description = "Too many attempts from this IP", body = ErrorResponse),
+    )
+)]
+pub async fn register(
+    pool: web::Data<Arc<DbPool>>,
+    config: web::Data<Config>,
+    limiter: web::Data<middleware::LoginRateLimiter>,
+    metrics: web::Data<Metrics>,
+    http_req: HttpRequest,
+    req: web::Json<AccountRequest>,
+) -> HttpResponse {
+    if !config.signup_enabled {
+        return HttpResponse::Forbidden().json(ErrorResponse {
+            error: "Sign-up is disabled on this server.".to_string(),
+        });
+    }
+    if let Err(resp) = check_login_rate(&limiter, &metrics, &http_req) {
+        return resp;
+    }
+    let username = req.username.trim();
+    if !accounts::valid_username(username) {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Username must be 3-32 letters, digits, '.', '_' or '-'.".to_string(),
+        });
+    }
+    if req.password.chars().count() < MIN_PASSWORD_LEN {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("Password must be at least {} characters.", MIN_PASSWORD_LEN),
+        });
+    }
+
+    let start = std::time::Instant::now();
+    let hashed = hash_password(&req.password);
+    metrics.observe_argon2("hash", start.elapsed());
+    let password_hash = match hashed {
+        Ok(h) => h,
+        Err(e) => {
+            tracing::warn!("hash_password: {}", e);
+            return server_error();
+        }
+    };
+    match accounts::create_user((pool.get_ref()).as_ref(), username, &password_hash).await {
+        Ok(Some(id)) => {
+            let account = AccountResponse {
+                username: username.to_string(),
+                groups: Vec::new(),
+                public_key: None,
+                email: None,
+            };
+            signed_in((pool.get_ref()).as_ref(), &config, id, account).await
+        }
+        Ok(None) => HttpResponse::Conflict().json(ErrorResponse {
+            error: "That username is taken.".to_string(),
+        }),
+        Err(e) => {
+            tracing::warn!("create user: {}", e);
+            server_error()
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/account/login",
+    tag = "account",
+    request_body = AccountRequest,
+    responses(
+        (status = 200, description = "Signed in; sets the session cookie", body = AccountResponse),
+        (status = 401, description = "Wrong username or password", body = ErrorResponse),
+        (status = 429, description = "Too many attempts from this IP", body = ErrorResponse),
+    )
+)]
+pub async fn login(
+    pool: web::Data<Arc<DbPool>>,
+    config: web::Data<Config>,
+    limiter: web::Data<middleware::LoginRateLimiter>,
+    metrics: web::Data<Metrics>,
+    http_req: HttpRequest,
+    req: web::Json<AccountRequest>,
+) -> HttpResponse {
+    if let Err(resp) = check_login_rate(&limiter, &metrics, &http_req) {
+        return resp;
+    }
+    let user = match accounts::find_user((pool.get_ref()).as_ref(), req.username.trim()).await {
+        Ok(u) => u,
+        Err(e) => {
+            tracing::warn!("find user: {}", e);
+            return server_error();
+        }
+    };
+
+    let start = std::time::Instant::now();
+    let verified = match &user {
+        Some(u) => verify_password(&req.password, &u.password_hash).unwrap_or(false),
+        None => {
+            // Spend the same Argon2 time for unknown users so timing does not reveal them.
+            let _ = hash_password(&req.password);
+            false
+        }
+    };
+    metrics.observe_argon2("verify", start.elapsed());
+
+    match user {
+        Some(u) if verified => {
+            let account = AccountResponse {
+                groups: u.group_list(),
+                username: u.username,
+                public_key: u.public_key,
+                email: u.email,
+            };
+            signed_in((pool.get_ref()).as_ref(), &config, u.id, account).await
+        }
+        _ => HttpResponse::Unauthorized().json(ErrorResponse {
+            error: "Wrong username or password".to_string(),
+        }),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/account/options",
+    tag = "account",
+    responses((status = 200, description = "Enabled sign-in methods and policies", body = AccountOptions))
+)]
+pub async fn options(config: web::Data<Config>) -> HttpResponse {
+    HttpResponse::Ok().json(AccountOptions {
+        signup_enabled: config.signup_enabled,
+        sso_enabled: config.oidc.is_some(),
+        require_auth_for_create: config.require_auth_for_create,
+    })
+}
+
+const DEFAULT_RETURN_TO: &str = "/account.html";
+
+/// Only same-site paths are accepted as post-login targets, so the flow can't be
+/// used as an open redirect.
+fn safe_return_to(path: Option<&str>) -> String {
+    match path {
+        Some(p) if p.starts_with('/') && !p.starts_with("//") && !p.contains('\\') => p.to_string(),
+        _ => DEFAULT_RETURN_TO.to_string(),
+    }
+}
+
+fn redirect(location: &str) -> HttpResponse {
+    HttpResponse::Found().insert_header((LOCATION, location)).finish()
+}
+
+fn sso_error_redirect(message: &str) -> HttpResponse {
+    let mut url = url::Url::parse("http://localhost/account.html").expect("static URL");
+    url.query_pairs_mut().append_pair("sso_error", message);
+    redirect(&format!("{}?{}", url.path(), url.query().unwrap_or_default()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OidcLoginQuery {
+    return_to: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/account/oidc/login",
+    tag = "account",
+    params(("return_to" = Option<String>, Query, description = "Same-site path to return to after signing in")),
+    responses(
+        (status = 302, description = "Redirect to the identity provider; sets the short-lived login state cookie"),
+        (status = 404, description = "Single sign-on is not configured", body = ErrorResponse),
+        (status = 502, description = "Identity provider discovery failed", body = ErrorResponse),
+    )
+)]
+pub async fn oidc_login(
+    pool: web::Data<Arc<DbPool>>,
+    oidc: Option<web::Data<OidcClient>>,
+    query: web::Query<OidcLoginQuery>,
+) -> HttpResponse {
+    let Some(oidc) = oidc else {
+        return HttpResponse::NotFound().json(ErrorResponse {
+            error: "Single sign-on is not configured".to_string(),
+        });
+    };
+    let return_to = safe_return_to(query.return_to.as_deref());
+    match oidc.authorization_url((pool.get_ref()).as_ref(), &return_to).await {
+        Ok((url, cookie)) => HttpResponse::Found().insert_header((LOCATION, url)).cookie(cookie).finish(),
+        Err(e) => {
+            tracing::warn!("oidc login: {:#}", e);
+            HttpResponse::BadGateway().json(ErrorResponse {
+                error: "Identity provider unavailable".to_string(),
+            })
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OidcCallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/account/oidc/callback",
+    tag = "account",
+    params(
+        ("code" = Option<String>, Query, description = "Authorization code"),
+        ("state" = Option<String>, Query, description = "State from the login redirect"),
+    ),
+    responses(
+        (status = 302, description = "Signed in (sets the session cookie) and redirected back; \
+            on failure, including a state that does not match the login state cookie, redirected to `/account.html?sso_error=...`"),
+        (status = 404, description = "Single sign-on is not configured", body = ErrorResponse),
+    )
+)]
+pub async fn oidc_callback(
+    pool: web::Data<Arc<DbPool>>,
+    config: web::Data<Config>,
+    oidc: Option<web::Data<OidcClient>>,
+    http_req: HttpRequest,
+    query: web::Query<OidcCallbackQuery>,
+) -> HttpResponse {
+    let Some(oidc) = oidc else {
+        return HttpResponse::NotFound().json(ErrorResponse {
+            error: "Single sign-on is not configured".to_string(),
+        });
+    };
+    if let Some(error) = &query.error {
+        tracing::info!("oidc callback error: {} {:?}", error, query.error_description);
+        return sso_error_redirect("The identity provider did not sign you in.");
+    }
+    let (Some(code), Some(state)) = (&query.code, &query.state) else {
+        return sso_error_redirect("Sign-in response is incomplete.");
+    };
+    let pool = (pool.get_ref()).as_ref();
+    let cookie_state = http_req.cookie(oidc::STATE_COOKIE);
+    let completed = oidc.complete(pool, state, cookie_state.as_ref().map(|c| c.value()), code).await;
+    let (identity, return_to) = match completed {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::warn!("oidc callback: {:#}", e);
+            return sso_error_redirect("Sign-in failed. Try again.");
+        }
+    };
+    if !oidc.is_allowed(&identity) {
+        tracing::info!("oidc: subject {} is not in an allowed group", identity.subject);
+        return sso_error_redirect("Your account is not allowed to sign in here.");
+    }
+    let user = match accounts::upsert_oidc_user(pool, &identity).await {
+        Ok(u) => u,
+        Err(e) => {
+            tracing::warn!("oidc user: {:#}", e);
+            return sso_error_redirect("Sign-in failed. Try again.");
+        }
+    };
+    match accounts::create_session(pool, &config, user.id).await {
+        Ok(cookie) => HttpResponse::Found()
+            .insert_header((LOCATION, return_to))
+            .cookie(cookie)
+            .cookie(oidc.removal_cookie())
+            .finish(),
+        Err(e) => {
+            tracing::warn!("create session: {}", e);
+            sso_error_redirect("Sign-in failed. Try again.")
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/account/logout",
+    tag = "account",
+    responses((status = 204, description = "Session ended; clears the session cookie"))
+)]
+pub async fn logout(pool: web::Data<Arc<DbPool>>, config: web::Data<Config>, http_req: HttpRequest) -> HttpResponse {
+    if let Err(e) = accounts::delete_session((pool.get_ref()).as_ref(), &http_req).await {
+        tracing::warn!("delete session: {}", e);
+    }
+    HttpResponse::NoContent().cookie(accounts::removal_cookie(&config)).finish()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/account",
+    tag = "account",
+    responses(
+        (status = 200, description = "The signed-in account", body = AccountResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+pub async fn me(pool: web::Data<Arc<DbPool>>, http_req: HttpRequest) -> HttpResponse {
+    match accounts::require_user((pool.get_ref()).as_ref(), &http_req).await {
+        Ok(user) => HttpResponse::Ok().json(AccountResponse {
+            groups: user.group_list(),
+            username: user.username,
+            public_key: user.public_key,
+            email: user.email,
+        }),
+        Err(resp) => resp,
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/account/public-key",
+    tag = "account",
+    request_body = PublicKeyRequest,
+    responses(
+        (status = 200, description = "Key registered; links can now be sealed to `@username`", body = AccountResponse),
+        (status = 400, description = "Not a usable `slpub_` key", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+pub async fn set_public_key(
+    pool: web::Data<Arc<DbPool>>,
+    http_req: HttpRequest,
+    req: web::Json<PublicKeyRequest>,
+) -> HttpResponse {
+    let user = match accounts::require_user((pool.get_ref()).as_ref(), &http_req).await {
+        Ok(u) => u,
+        Err(resp) => return resp,
+    };
+    if let Err(e) = crypto::parse_public_key(&req.public_key) {
+        return HttpResponse::BadRequest().json(ErrorResponse { error: e.to_string() });
+    }
+    let public_key = req.public_key.trim().to_string();
+    if let Err(e) = accounts::set_public_key((pool.get_ref()).as_ref(), user.id, Some(&public_key)).await {
+        tracing::warn!("set public key: {}", e);
+        return server_error();
+    }
+    HttpResponse::Ok().json(AccountResponse {
+        groups: user.group_list(),
+        username: user.username,
+        public_key: Some(public_key),
+        email: user.email,
+    })
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/account/public-key",
+    tag = "account",
+    responses(
+        (status = 204, description = "Key removed; links already sealed to it still need it"),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+pub async fn delete_public_key(pool: web::Data<Arc<DbPool>>, http_req: HttpRequest) -> HttpResponse {
+    let user = match accounts::require_user((pool.get_ref()).as_ref(), &http_req).await {
+        Ok(u) => u,
+        Err(resp) => return resp,
+    };
+    match accounts::set_public_key((pool.get_ref()).as_ref(), user.id, None).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e) => {
+            tracing::warn!("delete public key: {}", e);
+            server_error()
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/account/email",
+    tag = "account",
+    request_body = EmailRequest,
+    responses(
+        (status = 202, description = "Confirmation code sent to the address; confirm it with POST /api/v1/account/email/confirm"),
+        (status = 400, description = "Invalid address, or email is not enabled on this server", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 429, description = "Too many attempts from this IP", body = ErrorResponse),
+    )
+)]
+pub async fn set_email(
+    pool: web::Data<Arc<DbPool>>,
+    config: web::Data<Config>,
+    limiter: web::Data<middleware::LoginRateLimiter>,
+    metrics: web::Data<Metrics>,
+    http_req: HttpRequest,
+    req: web::Json<EmailRequest>,
+) -> HttpResponse {
+    if config.email.is_none() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Email notifications are not enabled on this server.".to_string(),
+        });
+    }
+    if let Err(resp) = check_login_rate(&limiter, &metrics, &http_req) {
+        return resp;
+    }
+    let pool = (pool.get_ref()).as_ref();
+    let user = match accounts::require_user(pool, &http_req).await {
+        Ok(u) => u,
+        Err(resp) => return resp,
+    };
+    let addr = req.email.trim();
+    if !email::check_address(addr) {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Not a valid email address.".to_string(),
+        });
+    }
+    let lang = req.lang.as_deref().filter(|l| email::LANGS.contains(l)).unwrap_or("en");
+    let sent = async {
+        let code = accounts::start_email_verification(pool, user.id, addr).await?;
+        email::send_verification(pool, &config, addr, &code, lang).await?;
+        Ok::<_, anyhow::Error>(())
+    };
+    match sent.await {
+        Ok(()) => HttpResponse::Accepted().finish(),
+        Err(e) => {
+            tracing::warn!("email verification: {}", e);
+            server_error()
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/account/email/confirm",
+    tag = "account",
+    request_body = EmailConfirmRequest,
+    responses(
+        (status = 200, description = "Address verified; links may now name it as notify_email", body = AccountResponse),
+        (status = 400, description = "Wrong or expired code", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 429, description = "Too many attempts from this IP", body = ErrorResponse),
+    )
+)]
+pub async fn confirm_email(
+    pool: web::Data<Arc<DbPool>>,
+    limiter: web::Data<middleware::LoginRateLimiter>,
+    metrics: web::Data<Metrics>,
+    http_req: HttpRequest,
+    req: web::Json<EmailConfirmRequest>,
+) -> HttpResponse {
+    if let Err(resp) = check_login_rate(&limiter, &metrics, &http_req) {
+        return resp;
+    }
+    let pool = (pool.get_ref()).as_ref();
+    let user = match accounts::require_user(pool, &http_req).await {
+        Ok(u) => u,
+        Err(resp) => return resp,
+    };
+    match accounts::confirm_email(pool, user.id, &req.code).await {
+        Ok(Some(email)) => HttpResponse::Ok().json(AccountResponse {
+            groups: user.group_list(),
+            username: user.username,
+            public_key: user.public_key,
+            email: Some(email),
+        }),
+        Ok(None) => HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Wrong or expired code.".to_string(),
+        }),
+        Err(e) => {
+            tracing::warn!("confirm email: {}", e);
+            server_error()
+        }
+    }
+}
+
+fn link_status(row: &LinkRow) -> LinkStatus {
+    match middleware::link_state(row) {
+        LinkState::Available => LinkStatus::Active,
+        LinkState::NotYetAvailable(_) => LinkStatus::Scheduled,
+        LinkState::Expired => LinkStatus::Expired,
+        LinkState::Consumed => LinkStatus::Consumed,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/account/links",
+    tag = "account",
+    responses(
+        (status = 200, description = "Metadata of links created while signed in, newest first", body = [LinkSummary]),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+pub async fn list_links(pool: web::Data<Arc<DbPool>>, http_req: HttpRequest) -> HttpResponse {
+    let user = match accounts::require_user((pool.get_ref()).as_ref(), &http_req).await {
+        Ok(u) => u,
+        Err(resp) => return resp,
+    };
+    let rows = match sqlx::query_as::<_, LinkRow>(SELECT_LINKS_BY_USER)
+        .bind(user.id)
+        .fetch_all((pool.get_ref()).as_ref())
+        .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::warn!("list links: {}", e);
+            return server_error();
+        }
+    };
+    let mut links = Vec::with_capacity(rows.len());
+    for row in &rows {
+        let recipients = if row.recipient_count > 0 {
+            match recipients::statuses((pool.get_ref()).as_ref(), row.id).await {
+                Ok(list) => list,
+                Err(e) => {
+                    tracing::warn!("list recipients: {}", e);
+                    return server_error();
+                }
+            }
+        } else {
+            Vec::new()
+        };
+        links.push(LinkSummary {
+            link_id: row.public_id.clone().unwrap_or_default(),
+            status: link_status(row),
+            views: row.view_count,
+            password_protected: row.password_hash.as_deref().is_some_and(|h| !h.is_empty()),
+            one_time_view: row.one_time_view != 0,
+            one_time_password: row.one_time_password != 0,
+            expires_at: row.expires_at.map(|t| t.to_rfc3339()),
+            not_before: row.not_before.map(|t| t.to_rfc3339()),
+            idle_timeout_minutes: row.idle_timeout_minutes.map(|m| m as u32),
+            last_accessed_at: row.last_accessed_at.map(|t| t.to_rfc3339()),
+            allowed_cidrs: row
+                .allowed_cidrs
+                .as_deref()
+                .map(|c| c.split(',').map(str::to_string).collect())
+                .unwrap_or_default(),
+            recipients,
+            created_at: row.created_at.to_rfc3339(),
+        });
+    }
+    HttpResponse::Ok().json(links)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/account/links/{link_id}",
+    tag = "account",
+    params(("link_id" = String, Path, description = "Public id of the link (`link_id` in the dashboard)")),
+    responses(
+        (status = 204, description = "Link deleted"),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "No such link owned by this account", body = ErrorResponse),
+    )
+)]
+pub async fn revoke_link(
+    pool: web::Data<Arc<DbPool>>,
+    config: web::Data<Config>,
+    http_req: HttpRequest,
+    link_id: web::Path<String>,
+) -> HttpResponse {
+    let user = match accounts::require_user((pool.get_ref()).as_ref(), &http_req).await {
+        Ok(u) => u,
+        Err(resp) => return resp,
+    };
+    let revoked = async {
+        let mut tx = pool.begin().await?;
+        let removed = sqlx::query_scalar::<_, Option<String>>("DELETE FROM links WHERE public_id = ? AND user_id = ? RETURNING public_id")
+            .bind(link_id.into_inner())
+            .bind(user.id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if let Some(link_id) = &removed {
+            let record = audit::Record::new(audit::Event::Revoked, link_id.as_deref())
+                .actor(format!("user:{}", user.id))
+                .client(&config, &http_req);
+            audit::append(&mut tx, &config, &record).await?;
+        }
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(removed)
+    };
+    match revoked.await {
+        Ok(Some(_)) => HttpResponse::NoContent().finish(),
+        Ok(None) => HttpResponse::NotFound().json(ErrorResponse {
+            error: "Link not found".to_string(),
+        }),
+        Err(e) => {
+            tracing::warn!("revoke link: {}", e);
+            server_error()
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! Versioned JSON API. Each version module owns its handlers and models and
+//! registers its routes through `configure`; `/api/v{n}` scopes are mounted in `main`.
+
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::{web, Error};
+use std::future::Future;
+
+pub mod account;
+pub mod pow;
+pub mod recipients;
+pub mod requests;
+pub mod split;
+pub mod v1;
+
+/// Mounts v1 (and the v1-only account, secret request, split, recipient status
+/// and proof-of-work challenge routes) under `/api/v1`.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api/v1")
+            .configure(v1::configure)
+            .configure(account::configure)
+            .configure(requests::configure)
+            .configure(split::configure)
+            .configure(recipients::configure)
+            .configure(pow::configure),
+    );
+}
+
+/// Unversioned aliases of v1 (`/api/create`, ...), kept for existing scripts.
+/// Responses carry `Deprecation: true` and a `Link` to the v1 successor.
+pub fn configure_legacy(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("").wrap_fn(mark_deprecated).configure(v1::configure));
+}
+
+fn mark_deprecated<S>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = Result<ServiceResponse, Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse, Error = Error>,
+{
+    let successor = req.path().replacen("/api/", "/api/v1/", 1);
+    let fut = srv.call(req);
+    async move {
+        let mut res = fut.await?;
+        let headers = res.headers_mut();
+        headers.insert(HeaderName::from_static("deprecation"), HeaderValue::from_static("true"));
+        if let Ok(link) = HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", successor)) {
+            headers.insert(actix_web::http::header::LINK, link);
+        }
+        Ok(res)
+    }
+}
@@ -0,0 +1,46 @@
+//! `GET /api/v1/pow`: issues a proof-of-work challenge ahead of an anonymous
+//! create or unlock (see `crate::pow`). A 428 answer to those requests carries a
+//! fresh challenge too, so clients may also just retry.
+//! Mounted under `/api/v1` only; there is no unversioned alias for this route.
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde::Deserialize;
+
+use crate::middleware;
+use crate::pow::{Action, Pow};
+use shredlink_types::v1::{ErrorResponse, PowChallenge};
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/pow", web::get().to(get_challenge));
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PowQuery {
+    action: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/pow",
+    tag = "links",
+    params(("action" = Option<String>, Query, description = "`create` (default; also covers split) or `unlock`")),
+    responses(
+        (status = 200, description = "Challenge bound to the caller's address", body = PowChallenge),
+        (status = 400, description = "Unknown action", body = ErrorResponse),
+        (status = 404, description = "Proof-of-work is not enabled on this server", body = ErrorResponse),
+    )
+)]
+pub async fn get_challenge(pow: web::Data<Pow>, http_req: HttpRequest, query: web::Query<PowQuery>) -> HttpResponse {
+    let Some(action) = Action::parse(query.action.as_deref().unwrap_or("create")) else {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "action must be create or unlock".to_string(),
+        });
+    };
+    let ip = middleware::peer_ip(&http_req).map(|ip| ip.to_canonical());
+    match pow.issue(action, ip) {
+        Some(challenge) => HttpResponse::Ok().json(challenge),
+        None => HttpResponse::NotFound().json(ErrorResponse {
+            error: "Proof-of-work is not enabled".to_string(),
+        }),
+    }
+}
@@ -0,0 +1,220 @@
+//! Multi-recipient links: a create request with `recipient_labels` stores the
+//! content once and issues one one-time token per named recipient. The link's own
+//! token is only the creator's handle for `GET /api/v1/links/{token}/recipients`;
+//! reading it answers 404. Cleanup deletes the link (and, by cascade, its tokens)
+//! once every recipient has read it or it expires.
+//! Mounted under `/api/v1` only; there is no unversioned alias for this route.
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use nanoid::nanoid;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use super::v1::{authorize_key, TOKEN_LEN};
+use crate::accounts;
+use crate::api_keys::Usage;
+use crate::config::Config;
+use crate::db::{DbPool, SELECT_LINK_BY_ID, SELECT_LINK_BY_TOKEN};
+use crate::metrics::Metrics;
+use crate::middleware::{self, LinkState};
+use crate::models::{LinkRecipientRow, LinkRow};
+use shredlink_types::v1::{ErrorResponse, RecipientLink, RecipientStatus};
+
+pub const MAX_RECIPIENTS: usize = 50;
+const MAX_LABEL_CHARS: usize = 100;
+const SELECT_RECIPIENT: &str = "SELECT id, link_id, label, viewed_at FROM link_recipients";
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/links/{token}/recipients", web::get().to(list_recipients));
+}
+
+fn not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(ErrorResponse {
+        error: "Link not found".to_string(),
+    })
+}
+
+fn share_url(config: &Config, token: &str) -> String {
+    format!("{}/s/{}", config.base_url.trim_end_matches('/'), token)
+}
+
+/// Trims `labels` and checks there are at most `MAX_RECIPIENTS` distinct,
+/// non-empty ones of reasonable length.
+pub fn check_labels(labels: &[String]) -> Result<Vec<String>, String> {
+    if labels.len() > MAX_RECIPIENTS {
+        return Err(format!("at most {} recipient_labels are allowed", MAX_RECIPIENTS));
+    }
+    let mut seen = HashSet::new();
+    let mut out = Vec::with_capacity(labels.len());
+    for label in labels {
+        let label = label.trim();
+        if label.is_empty() {
+            return Err("recipient_labels must not be empty".to_string());
+        }
+        if label.chars().count() > MAX_LABEL_CHARS {
+            return Err(format!("recipient_labels must be at most {} characters each", MAX_LABEL_CHARS));
+        }
+        if !seen.insert(label.to_lowercase()) {
+            return Err(format!("recipient_labels lists {:?} twice", label));
+        }
+        out.push(label.to_string());
+    }
+    Ok(out)
+}
+
+/// Issues a token per label for the link with row id `link_id`.
+pub async fn insert(
+    conn: &mut sqlx::SqliteConnection,
+    config: &Config,
+    link_id: i64,
+    labels: &[String],
+) -> sqlx::Result<Vec<RecipientLink>> {
+    let mut links = Vec::with_capacity(labels.len());
+    for label in labels {
+        let token = nanoid!(TOKEN_LEN);
+        sqlx::query("INSERT INTO link_recipients (link_id, token, label) VALUES (?, ?, ?)")
+            .bind(link_id)
+            .bind(&token)
+            .bind(label)
+            .execute(&mut *conn)
+            .await?;
+        links.push(RecipientLink {
+            label: label.clone(),
+            url: share_url(config, &token),
+            token,
+        });
+    }
+    Ok(links)
+}
+
+/// Resolves a token from a share URL: an ordinary link's token, or a recipient's
+/// token together with that recipient. The creator's handle of a multi-recipient
+/// link resolves to nothing, like an unknown token.
+pub async fn find_link(pool: &DbPool, token: &str) -> sqlx::Result<Option<(LinkRow, Option<LinkRecipientRow>)>> {
+    if let Some(row) = sqlx::query_as::<_, LinkRow>(SELECT_LINK_BY_TOKEN)
+        .bind(token)
+        .fetch_optional(pool)
+        .await?
+    {
+        return Ok((row.recipient_count == 0).then_some((row, None)));
+    }
+    let Some(recipient) = sqlx::query_as::<_, LinkRecipientRow>(&format!("{} WHERE token = ?", SELECT_RECIPIENT))
+        .bind(token)
+        .fetch_optional(pool)
+        .await?
+    else {
+        return Ok(None);
+    };
+    let row = sqlx::query_as::<_, LinkRow>(SELECT_LINK_BY_ID)
+        .bind(recipient.link_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|row| (row, Some(recipient))))
+}
+
+/// Like `middleware::link_state`, but a recipient's own token is consumed by
+/// their first read.
+pub fn link_state(row: &LinkRow, recipient: Option<&LinkRecipientRow>) -> LinkState {
+    if recipient.is_some_and(|r| r.viewed_at.is_some()) {
+        return LinkState::Consumed;
+    }
+    middleware::link_state(row)
+}
+
+/// Marks the recipient's token as read. Returns false if a concurrent read got
+/// there first.
+pub async fn claim(pool: &DbPool, recipient: &LinkRecipientRow) -> sqlx::Result<bool> {
+    let r = sqlx::query("UPDATE link_recipients SET viewed_at = ? WHERE id = ? AND viewed_at IS NULL")
+        .bind(Utc::now().to_rfc3339())
+        .bind(recipient.id)
+        .execute(pool)
+        .await?;
+    Ok(r.rows_affected() > 0)
+}
+
+/// Per-recipient status of the link with row id `link_id`, in creation order.
+pub async fn statuses(pool: &DbPool, link_id: i64) -> sqlx::Result<Vec<RecipientStatus>> {
+    let rows = sqlx::query_as::<_, LinkRecipientRow>(&format!("{} WHERE link_id = ? ORDER BY id", SELECT_RECIPIENT))
+        .bind(link_id)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| RecipientStatus {
+            label: r.label,
+            viewed: r.viewed_at.is_some(),
+            viewed_at: r.viewed_at.map(|t| t.to_rfc3339()),
+        })
+        .collect())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/links/{token}/recipients",
+    tag = "links",
+    params(("token" = String, Path, description = "Token of the multi-recipient link (from the create response)")),
+    responses(
+        (status = 200, description = "Which recipients have read the link", body = [RecipientStatus]),
+        (status = 401, description = "Not signed in and no API key", body = ErrorResponse),
+        (status = 403, description = "API key lacks the read scope", body = ErrorResponse),
+        (status = 404, description = "No multi-recipient link with this token was created by the caller", body = ErrorResponse),
+        (status = 429, description = "API key quota exceeded", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn list_recipients(
+    pool: web::Data<Arc<DbPool>>,
+    key_limiter: web::Data<middleware::ApiKeyRateLimiter>,
+    metrics: web::Data<Metrics>,
+    http_req: HttpRequest,
+    token: web::Path<String>,
+) -> HttpResponse {
+    let pool = (pool.get_ref()).as_ref();
+    let key = match authorize_key(pool, &key_limiter, &metrics, &http_req, Usage::Read).await {
+        Ok(k) => k,
+        Err(resp) => return resp,
+    };
+    let user = match accounts::session_user(pool, &http_req).await {
+        Ok(u) => u,
+        Err(e) => {
+            tracing::warn!("session lookup: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Server error".to_string(),
+            });
+        }
+    };
+    if key.is_none() && user.is_none() {
+        return HttpResponse::Unauthorized().json(ErrorResponse {
+            error: "Sign in or use the API key that created the link.".to_string(),
+        });
+    }
+    let link_id = match sqlx::query_scalar::<_, i64>(
+        "SELECT id FROM links WHERE token = ? AND recipient_count > 0 AND (user_id = ? OR api_key_id = ?)",
+    )
+    .bind(token.into_inner())
+    .bind(user.map(|u| u.id))
+    .bind(key.map(|k| k.id))
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(Some(id)) => id,
+        Ok(None) => return not_found(),
+        Err(e) => {
+            tracing::warn!("fetch link: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Server error".to_string(),
+            });
+        }
+    };
+    match statuses(pool, link_id).await {
+        Ok(list) => HttpResponse::Ok().json(list),
+        Err(e) => {
+            tracing::warn!("list recipients: {}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Server error".to_string(),
+            })
+        }
+    }
+}
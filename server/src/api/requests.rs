@@ -0,0 +1,494 @@
+//! `/api/v1/requests`: secret requests ("send me a secret"). A signed-in user or
+//! API key creates a request and sends its `/r/{token}` link to someone, who
+//! submits a secret once through the form. The answer is sealed to the owner's
+//! recipient key, then encrypted at rest like links; only the owner can read it,
+//! and it is deleted on that read.
+//! Mounted under `/api/v1` only; there are no unversioned aliases for these routes.
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{Duration, Utc};
+use nanoid::nanoid;
+use std::sync::Arc;
+
+use super::v1::{authorize_key, too_many_requests, TOKEN_LEN};
+use crate::accounts;
+use crate::api_keys::Usage;
+use crate::config::Config;
+use crate::crypto::{self, decrypt_text, encrypt_text, random_hex};
+use crate::db::DbPool;
+use crate::metrics::Metrics;
+use crate::middleware;
+use crate::models::SecretRequestRow;
+use shredlink_types::v1::{
+    CreateSecretRequest, ErrorResponse, SecretRequestCreated, SecretRequestInfo, SecretRequestStatus, SecretRequestSummary,
+    SubmitSecretRequest, UnlockResponse,
+};
+
+const DEFAULT_TTL_MINUTES: u32 = 7 * 24 * 60;
+const MAX_TTL_MINUTES: u32 = 30 * 24 * 60;
+const MAX_NOTE_CHARS: usize = 500;
+const SELECT_REQUEST: &str =
+    "SELECT id, public_id, token, note, public_key, encrypted_text, expires_at, submitted_at, created_at FROM secret_requests";
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/requests")
+            .route("", web::post().to(create_request))
+            .route("", web::get().to(list_requests))
+            .route("/submit/{token}", web::get().to(request_info))
+            .route("/submit/{token}", web::post().to(submit_request))
+            .route("/{id}/read", web::post().to(read_request))
+            .route("/{id}", web::delete().to(delete_request)),
+    );
+}
+
+fn server_error() -> HttpResponse {
+    HttpResponse::InternalServerError().json(ErrorResponse {
+        error: "Server error".to_string(),
+    })
+}
+
+fn gone() -> HttpResponse {
+    HttpResponse::Gone().json(ErrorResponse {
+        error: "This request has expired or has already been answered.".to_string(),
+    })
+}
+
+/// Who a request belongs to: the signed-in user and/or the API key.
+struct Owner {
+    user_id: Option<i64>,
+    api_key_id: Option<i64>,
+    /// The signed-in user's registered recipient key.
+    public_key: Option<String>,
+}
+
+/// Resolves the caller to an owner, answering 401 for anonymous callers.
+async fn owner(
+    pool: &DbPool,
+    key_limiter: &middleware::ApiKeyRateLimiter,
+    metrics: &Metrics,
+    http_req: &HttpRequest,
+    usage: Usage,
+) -> Result<Owner, HttpResponse> {
+    let key = authorize_key(pool, key_limiter, metrics, http_req, usage).await?;
+    let user = match accounts::session_user(pool, http_req).await {
+        Ok(u) => u,
+        Err(e) => {
+            tracing::warn!("session lookup: {}", e);
+            return Err(server_error());
+        }
+    };
+    if key.is_none() && user.is_none() {
+        return Err(HttpResponse::Unauthorized().json(ErrorResponse {
+            error: "Sign in or use an API key to manage secret requests.".to_string(),
+        }));
+    }
+    Ok(Owner {
+        user_id: user.as_ref().map(|u| u.id),
+        api_key_id: key.map(|k| k.id),
+        public_key: user.and_then(|u| u.public_key),
+    })
+}
+
+fn status(row: &SecretRequestRow) -> SecretRequestStatus {
+    if row.expires_at <= Utc::now() {
+        SecretRequestStatus::Expired
+    } else if row.submitted_at.is_some() {
+        SecretRequestStatus::Submitted
+    } else {
+        SecretRequestStatus::Pending
+    }
+}
+
+fn request_url(config: &Config, token: &str) -> String {
+    format!("{}/r/{}", config.base_url.trim_end_matches('/'), token)
+}
+
+/// Looks up an open (unanswered, unexpired) request by its submit token.
+pub async fn find_open(pool: &DbPool, token: &str) -> Result<Option<SecretRequestRow>, HttpResponse> {
+    let row = sqlx::query_as::<_, SecretRequestRow>(&format!("{} WHERE token = ?", SELECT_REQUEST))
+        .bind(token)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::warn!("fetch request: {}", e);
+            server_error()
+        })?;
+    Ok(row.filter(|r| status(r) == SecretRequestStatus::Pending))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/requests",
+    tag = "requests",
+    request_body = CreateSecretRequest,
+    responses(
+        (status = 200, description = "Request created; send `url` to the person who should answer", body = SecretRequestCreated),
+        (status = 400, description = "Note too long, expiry out of range, or no usable public_key", body = ErrorResponse),
+        (status = 401, description = "Not signed in and no API key", body = ErrorResponse),
+        (status = 403, description = "API key lacks the create scope", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn create_request(
+    pool: web::Data<Arc<DbPool>>,
+    config: web::Data<Config>,
+    key_limiter: web::Data<middleware::ApiKeyRateLimiter>,
+    metrics: web::Data<Metrics>,
+    http_req: HttpRequest,
+    req: web::Json<CreateSecretRequest>,
+) -> HttpResponse {
+    let pool = (pool.get_ref()).as_ref();
+    let owner = match owner(pool, &key_limiter, &metrics, &http_req, Usage::Create).await {
+        Ok(o) => o,
+        Err(resp) => return resp,
+    };
+    let note = req.note.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    if note.is_some_and(|n| n.chars().count() > MAX_NOTE_CHARS) {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("note must be at most {} characters", MAX_NOTE_CHARS),
+        });
+    }
+    let ttl = req.expire_minutes.unwrap_or(DEFAULT_TTL_MINUTES);
+    if ttl == 0 || ttl > MAX_TTL_MINUTES {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("expire_minutes must be between 1 and {}", MAX_TTL_MINUTES),
+        });
+    }
+
+    let Some(public_key) = req.public_key.as_deref().or(owner.public_key.as_deref()) else {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "public_key is required unless your account has a registered recipient key.".to_string(),
+        });
+    };
+    if let Err(e) = crypto::parse_public_key(public_key) {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("public_key: {}", e),
+        });
+    }
+
+    let id = random_hex(8);
+    let token = nanoid!(TOKEN_LEN);
+    let expires_at = Utc::now() + Duration::minutes(ttl as i64);
+    if let Err(e) = sqlx::query(
+        "INSERT INTO secret_requests (public_id, token, note, public_key, user_id, api_key_id, expires_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(&token)
+    .bind(note)
+    .bind(public_key.trim())
+    .bind(owner.user_id)
+    .bind(owner.api_key_id)
+    .bind(expires_at.to_rfc3339())
+    .execute(pool)
+    .await
+    {
+        tracing::warn!("insert request: {}", e);
+        return server_error();
+    }
+    HttpResponse::Ok().json(SecretRequestCreated {
+        url: request_url(&config, &token),
+        id,
+        expires_at: expires_at.to_rfc3339(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/requests",
+    tag = "requests",
+    responses(
+        (status = 200, description = "The caller's secret requests, newest first", body = [SecretRequestSummary]),
+        (status = 401, description = "Not signed in and no API key", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn list_requests(
+    pool: web::Data<Arc<DbPool>>,
+    config: web::Data<Config>,
+    key_limiter: web::Data<middleware::ApiKeyRateLimiter>,
+    metrics: web::Data<Metrics>,
+    http_req: HttpRequest,
+) -> HttpResponse {
+    let pool = (pool.get_ref()).as_ref();
+    let owner = match owner(pool, &key_limiter, &metrics, &http_req, Usage::Read).await {
+        Ok(o) => o,
+        Err(resp) => return resp,
+    };
+    let rows = match sqlx::query_as::<_, SecretRequestRow>(&format!(
+        "{} WHERE user_id = ? OR api_key_id = ? ORDER BY created_at DESC, id DESC",
+        SELECT_REQUEST
+    ))
+    .bind(owner.user_id)
+    .bind(owner.api_key_id)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::warn!("list requests: {}", e);
+            return server_error();
+        }
+    };
+    let requests: Vec<SecretRequestSummary> = rows
+        .iter()
+        .map(|row| SecretRequestSummary {
+            id: row.public_id.clone(),
+            url: request_url(&config, &row.token),
+            note: row.note.clone(),
+            public_key: row.public_key.clone(),
+            status: status(row),
+            expires_at: row.expires_at.to_rfc3339(),
+            submitted_at: row.submitted_at.map(|t| t.to_rfc3339()),
+            created_at: row.created_at.to_rfc3339(),
+        })
+        .collect();
+    HttpResponse::Ok().json(requests)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/requests/{id}/read",
+    tag = "requests",
+    params(("id" = String, Path, description = "Request id")),
+    responses(
+        (status = 200, description = "The submitted secret, sealed to the request's public key (`shredlink:seal1:`); the request is deleted", body = UnlockResponse),
+        (status = 401, description = "Not signed in and no API key", body = ErrorResponse),
+        (status = 404, description = "No such request owned by the caller", body = ErrorResponse),
+        (status = 409, description = "Nothing has been submitted yet", body = ErrorResponse),
+        (status = 410, description = "Request expired", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn read_request(
+    pool: web::Data<Arc<DbPool>>,
+    config: web::Data<Config>,
+    key_limiter: web::Data<middleware::ApiKeyRateLimiter>,
+    metrics: web::Data<Metrics>,
+    http_req: HttpRequest,
+    id: web::Path<String>,
+) -> HttpResponse {
+    let pool = (pool.get_ref()).as_ref();
+    let owner = match owner(pool, &key_limiter, &metrics, &http_req, Usage::Read).await {
+        Ok(o) => o,
+        Err(resp) => return resp,
+    };
+    let row = match sqlx::query_as::<_, SecretRequestRow>(&format!(
+        "{} WHERE public_id = ? AND (user_id = ? OR api_key_id = ?)",
+        SELECT_REQUEST
+    ))
+    .bind(id.as_str())
+    .bind(owner.user_id)
+    .bind(owner.api_key_id)
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(Some(r)) => r,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ErrorResponse {
+                error: "Request not found".to_string(),
+            })
+        }
+        Err(e) => {
+            tracing::warn!("fetch request: {}", e);
+            return server_error();
+        }
+    };
+    let encrypted = match (status(&row), &row.encrypted_text) {
+        (SecretRequestStatus::Expired, _) => return gone(),
+        (_, Some(encrypted)) => encrypted,
+        (_, None) => {
+            return HttpResponse::Conflict().json(ErrorResponse {
+                error: "Nothing has been submitted yet.".to_string(),
+            })
+        }
+    };
+    let text = match decrypt_text(encrypted, config.encryption_key_base64.expose()) {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::warn!("decrypt request: {}", e);
+            return server_error();
+        }
+    };
+    // Burn on read; if a concurrent read got there first, it owns the answer.
+    match sqlx::query("DELETE FROM secret_requests WHERE id = ?").bind(row.id).execute(pool).await {
+        Ok(r) if r.rows_affected() == 1 => HttpResponse::Ok().json(UnlockResponse { text }),
+        Ok(_) => gone(),
+        Err(e) => {
+            tracing::warn!("delete request: {}", e);
+            server_error()
+        }
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/requests/{id}",
+    tag = "requests",
+    params(("id" = String, Path, description = "Request id")),
+    responses(
+        (status = 204, description = "Request and any answer deleted"),
+        (status = 401, description = "Not signed in and no API key", body = ErrorResponse),
+        (status = 404, description = "No such request owned by the caller", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn delete_request(
+    pool: web::Data<Arc<DbPool>>,
+    key_limiter: web::Data<middleware::ApiKeyRateLimiter>,
+    metrics: web::Data<Metrics>,
+    http_req: HttpRequest,
+    id: web::Path<String>,
+) -> HttpResponse {
+    let pool = (pool.get_ref()).as_ref();
+    let owner = match owner(pool, &key_limiter, &metrics, &http_req, Usage::Create).await {
+        Ok(o) => o,
+        Err(resp) => return resp,
+    };
+    match sqlx::query("DELETE FROM secret_requests WHERE public_id = ? AND (user_id = ? OR api_key_id = ?)")
+        .bind(id.as_str())
+        .bind(owner.user_id)
+        .bind(owner.api_key_id)
+        .execute(pool)
+        .await
+    {
+        Ok(r) if r.rows_affected() > 0 => HttpResponse::NoContent().finish(),
+        Ok(_) => HttpResponse::NotFound().json(ErrorResponse {
+            error: "Request not found".to_string(),
+        }),
+        Err(e) => {
+            tracing::warn!("delete request: {}", e);
+            server_error()
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/requests/submit/{token}",
+    tag = "requests",
+    params(("token" = String, Path, description = "Token from the request link")),
+    responses(
+        (status = 200, description = "The request as shown to the person answering", body = SecretRequestInfo),
+        (status = 404, description = "Request does not exist", body = ErrorResponse),
+        (status = 410, description = "Request expired or already answered", body = ErrorResponse),
+    )
+)]
+pub async fn request_info(pool: web::Data<Arc<DbPool>>, config: web::Data<Config>, token: web::Path<String>) -> HttpResponse {
+    match find_open((pool.get_ref()).as_ref(), &token).await {
+        Ok(Some(row)) => HttpResponse::Ok().json(SecretRequestInfo {
+            note: row.note,
+            expires_at: row.expires_at.to_rfc3339(),
+            max_text_size_bytes: config.max_text_size_bytes,
+            public_key: row.public_key,
+        }),
+        Ok(None) => not_open((pool.get_ref()).as_ref(), &token).await,
+        Err(resp) => resp,
+    }
+}
+
+/// 404 for unknown tokens, 410 for requests that exist but are closed.
+async fn not_open(pool: &DbPool, token: &str) -> HttpResponse {
+    match sqlx::query_scalar::<_, i64>("SELECT id FROM secret_requests WHERE token = ?")
+        .bind(token)
+        .fetch_optional(pool)
+        .await
+    {
+        Ok(Some(_)) => gone(),
+        Ok(None) => HttpResponse::NotFound().json(ErrorResponse {
+            error: "Request not found".to_string(),
+        }),
+        Err(e) => {
+            tracing::warn!("fetch request: {}", e);
+            server_error()
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/requests/submit/{token}",
+    tag = "requests",
+    params(("token" = String, Path, description = "Token from the request link")),
+    request_body = SubmitSecretRequest,
+    responses(
+        (status = 204, description = "Secret stored for the requester; the link is now closed"),
+        (status = 400, description = "Missing text, or text not sealed to the request's public key (`shredlink:seal1:`)", body = ErrorResponse),
+        (status = 404, description = "Request does not exist", body = ErrorResponse),
+        (status = 410, description = "Request expired or already answered", body = ErrorResponse),
+        (status = 413, description = "Text exceeds MAX_TEXT_SIZE_BYTES", body = ErrorResponse),
+        (status = 429, description = "Rate limited per client IP", body = ErrorResponse,
+            headers(("Retry-After" = u64, description = "Seconds until the next request is allowed"))),
+    )
+)]
+pub async fn submit_request(
+    pool: web::Data<Arc<DbPool>>,
+    config: web::Data<Config>,
+    rate_limiter: web::Data<middleware::CreateRateLimiter>,
+    metrics: web::Data<Metrics>,
+    http_req: HttpRequest,
+    token: web::Path<String>,
+    req: web::Json<SubmitSecretRequest>,
+) -> HttpResponse {
+    let pool = (pool.get_ref()).as_ref();
+    // Answering is anonymous, so it shares the per-IP create quota.
+    if let Some(ip) = middleware::peer_ip(&http_req) {
+        if let Err(wait) = rate_limiter.check(&ip) {
+            return too_many_requests(&metrics, wait);
+        }
+    }
+    if req.text.is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "text is required".to_string(),
+        });
+    }
+    if req.text.len() > config.max_text_size_bytes {
+        return HttpResponse::PayloadTooLarge().json(ErrorResponse {
+            error: format!("text exceeds max size ({} bytes)", config.max_text_size_bytes),
+        });
+    }
+    // The answer is sealed in the sender's browser, so the plaintext never reaches
+    // the server.
+    if !crypto::is_sealed(&req.text) {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("text must be sealed to the request's public_key ({}...)", crypto::SEALED_PREFIX),
+        });
+    }
+    let row = match find_open(pool, &token).await {
+        Ok(Some(row)) => row,
+        Ok(None) => return not_open(pool, &token).await,
+        Err(resp) => return resp,
+    };
+    // Sealed answers are still encrypted at rest with ENCRYPTION_KEY, like sealed links.
+    let encrypted = match encrypt_text(&req.text, config.encryption_key_base64.expose()) {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::warn!("encrypt: {}", e);
+            return server_error();
+        }
+    };
+    // Only the first answer is kept.
+    match sqlx::query("UPDATE secret_requests SET encrypted_text = ?, submitted_at = ? WHERE id = ? AND submitted_at IS NULL")
+        .bind(&encrypted)
+        .bind(Utc::now().to_rfc3339())
+        .bind(row.id)
+        .execute(pool)
+        .await
+    {
+        Ok(r) if r.rows_affected() == 1 => HttpResponse::NoContent().finish(),
+        Ok(_) => gone(),
+        Err(e) => {
+            tracing::warn!("submit request: {}", e);
+            server_error()
+        }
+    }
+}
+
+/// Deletes expired requests together with any unread answers.
+pub async fn delete_expired(pool: &DbPool) -> anyhow::Result<u64> {
+    let r = sqlx::query("DELETE FROM secret_requests WHERE datetime(expires_at) <= datetime(?)")
+        .bind(Utc::now().to_rfc3339())
+        .execute(pool)
+        .await?;
+    Ok(r.rows_affected())
+}
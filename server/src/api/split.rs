@@ -0,0 +1,120 @@
+//! `/api/v1/split`: M-of-N secret splitting. The secret is cut into Shamir shares
+//! and each share becomes an ordinary link, created exactly as `POST /api/v1/create`
+//! would. `/combine.html` reads the share links and recombines them in the browser.
+//! Mounted under `/api/v1` only; there is no unversioned alias for this route.
+
+use actix_web::middleware::from_fn;
+use actix_web::{web, HttpRequest, HttpResponse};
+use std::sync::Arc;
+
+use super::v1::{authorize_create, prepare_link, store_link};
+use crate::config::Config;
+use crate::db::DbPool;
+use crate::metrics::Metrics;
+use crate::middleware;
+use crate::pow;
+use crate::shamir::{self, MAX_SHARES, MIN_SHARES};
+use shredlink_types::v1::{CreateResponse, ErrorResponse, PowChallenge, SplitRequest, SplitResponse};
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/split", web::post().to(split_secret).wrap(from_fn(pow::require_for_create)));
+}
+
+fn bad_request(error: String) -> HttpResponse {
+    HttpResponse::BadRequest().json(ErrorResponse { error })
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/split",
+    tag = "links",
+    request_body = SplitRequest,
+    responses(
+        (status = 200, description = "One link per share", body = SplitResponse),
+        (status = 400, description = "Invalid shares/threshold/recipients, or any reason POST /api/v1/create returns 400", body = ErrorResponse),
+        (status = 401, description = "As for POST /api/v1/create", body = ErrorResponse),
+        (status = 403, description = "API key lacks a scope the request needs", body = ErrorResponse),
+        (status = 413, description = "A share exceeds the caller's size limit", body = ErrorResponse),
+        (status = 422, description = "Share links would violate the creation policy", body = ErrorResponse),
+        (status = 428, description = "As for POST /api/v1/create; use a create challenge", body = PowChallenge),
+        (status = 429, description = "Rate limited; the whole split counts as one create", body = ErrorResponse,
+            headers(("Retry-After" = u64, description = "Seconds until the next request is allowed"))),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security((), ("api_key" = []))
+)]
+pub async fn split_secret(
+    pool: web::Data<Arc<DbPool>>,
+    config: web::Data<Config>,
+    rate_limiter: web::Data<middleware::CreateRateLimiter>,
+    key_limiter: web::Data<middleware::ApiKeyRateLimiter>,
+    metrics: web::Data<Metrics>,
+    http_req: HttpRequest,
+    req: web::Json<SplitRequest>,
+) -> HttpResponse {
+    let creator = match authorize_create(pool.get_ref(), &config, &rate_limiter, &key_limiter, &metrics, &http_req).await {
+        Ok(c) => c,
+        Err(resp) => return resp,
+    };
+    let req = req.into_inner();
+    if !(MIN_SHARES..=MAX_SHARES).contains(&req.shares) {
+        return bad_request(format!("shares must be between {} and {}", MIN_SHARES, MAX_SHARES));
+    }
+    if req.threshold < MIN_SHARES || req.threshold > req.shares {
+        return bad_request(format!("threshold must be between {} and shares", MIN_SHARES));
+    }
+    if !req.recipients.is_empty() {
+        if req.recipients.len() != req.shares as usize {
+            return bad_request("recipients must list one key per share".to_string());
+        }
+        if req.link.recipient_public_key.is_some() {
+            return bad_request("Use either recipients or recipient_public_key, not both.".to_string());
+        }
+    }
+    if !req.link.recipient_labels.is_empty() {
+        return bad_request("recipient_labels cannot be used when splitting".to_string());
+    }
+    if req.link.text.is_empty() {
+        return bad_request("text is required".to_string());
+    }
+
+    let shares = shamir::split(req.link.text.as_bytes(), req.shares, req.threshold);
+    let mut link = req.link;
+    // A later share can still fail on its own recipient key, so check and encrypt
+    // every share before storing any of them.
+    let mut prepared = Vec::with_capacity(shares.len());
+    for (i, share) in shares.into_iter().enumerate() {
+        link.text = share;
+        if let Some(recipient) = req.recipients.get(i) {
+            link.recipient_public_key = Some(recipient.clone());
+        }
+        match prepare_link(pool.get_ref(), &config, &metrics, &creator, &link).await {
+            Ok(p) => prepared.push(p),
+            Err(resp) => return resp,
+        }
+    }
+    let stored = async {
+        let mut tx = pool.begin().await?;
+        let mut links: Vec<CreateResponse> = Vec::with_capacity(prepared.len());
+        for p in prepared {
+            let record = creator.audit_record(&config, &http_req, &p.link_id);
+            links.push(store_link(&mut tx, &config, p, &record).await?);
+        }
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(links)
+    };
+    let links = match stored.await {
+        Ok(l) => l,
+        Err(e) => {
+            tracing::warn!("split insert: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "server error".to_string(),
+            });
+        }
+    };
+    metrics.links_created.inc_by(links.len() as u64);
+    HttpResponse::Ok().json(SplitResponse {
+        threshold: req.threshold,
+        links,
+    })
+}
@@ -0,0 +1,902 @@
+//! `/api/v1`: the current, frozen API surface. Changes that alter request or response
+//! semantics belong in a new version module rather than here.
+
+use actix_web::middleware::from_fn;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Duration, Utc};
+use nanoid::nanoid;
+
+use std::sync::Arc;
+
+use crate::accounts;
+use crate::audit;
+use super::recipients;
+use crate::api_keys::{self, ApiKeyRow, Usage};
+use crate::config::Config;
+use crate::crypto::{self, decrypt_text, encrypt_text, hash_password, random_hex, verify_password};
+use crate::db::DbPool;
+use crate::metrics::Metrics;
+use crate::middleware::{self, LinkState};
+use crate::models::{LinkRecipientRow, LinkRow};
+use crate::policy::PolicyViolation;
+use crate::pow;
+use crate::email;
+use crate::webhooks::{self, Event};
+use shredlink_types::v1::{CreatePolicy, CreateRequest, CreateResponse, ErrorResponse, PowChallenge, UnlockRequest, UnlockResponse};
+
+pub const TOKEN_LEN: usize = 16;
+
+/// When a new link becomes readable and when it expires.
+struct Schedule {
+    not_before: Option<DateTime<Utc>>,
+    /// Fixed expiry from `expires_at`.
+    expires_at: Option<DateTime<Utc>>,
+    /// Requested lifetime in minutes from go-live, for the policy check.
+    ttl_minutes: Option<i64>,
+}
+
+fn parse_time(field: &str, value: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|_| format!("{} must be an RFC 3339 timestamp", field))
+}
+
+fn schedule(req: &CreateRequest, now: DateTime<Utc>) -> Result<Schedule, String> {
+    // A go-live time in the past just means "readable now".
+    let not_before = match req.not_before.as_deref() {
+        Some(v) => Some(parse_time("not_before", v)?).filter(|t| *t > now),
+        None => None,
+    };
+    let live_from = not_before.unwrap_or(now);
+    let relative = req.expire_minutes.unwrap_or(0) as i64 + req.expire_hours.unwrap_or(0) as i64 * 60;
+    let expires_at = match req.expires_at.as_deref() {
+        Some(_) if relative > 0 => {
+            return Err("Use either expires_at or expire_minutes/expire_hours, not both.".to_string());
+        }
+        Some(v) => {
+            let at = parse_time("expires_at", v)?;
+            if at <= live_from {
+                return Err(match not_before {
+                    Some(_) => "expires_at must be after not_before".to_string(),
+                    None => "expires_at must be in the future".to_string(),
+                });
+            }
+            Some(at)
+        }
+        None => None,
+    };
+    let ttl_minutes = match expires_at {
+        Some(at) => Some(((at - live_from).num_seconds() + 59) / 60),
+        None if relative > 0 => Some(relative),
+        None => None,
+    };
+    Ok(Schedule {
+        not_before,
+        expires_at,
+        ttl_minutes,
+    })
+}
+
+pub fn too_many_requests(metrics: &Metrics, wait: std::time::Duration) -> HttpResponse {
+    metrics.rate_limited.inc();
+    let retry_after = wait.as_millis().div_ceil(1000).max(1);
+    HttpResponse::TooManyRequests()
+        .insert_header(("Retry-After", retry_after.to_string()))
+        .json(ErrorResponse {
+            error: "Too many requests. Try again later.".to_string(),
+        })
+}
+
+/// `410` for expired or consumed links (or a recipient token already read); `403`
+/// with `Retry-After` for links scheduled to go live later.
+fn check_available(row: &LinkRow, recipient: Option<&LinkRecipientRow>) -> Result<(), HttpResponse> {
+    match recipients::link_state(row, recipient) {
+        LinkState::Available => Ok(()),
+        LinkState::NotYetAvailable(at) => {
+            let wait = (at - Utc::now()).num_seconds().max(1);
+            Err(HttpResponse::Forbidden()
+                .insert_header(("Retry-After", wait.to_string()))
+                .json(ErrorResponse {
+                    error: format!("This link is not available until {}.", at.to_rfc3339()),
+                }))
+        }
+        LinkState::Expired | LinkState::Consumed => Err(HttpResponse::Gone().json(ErrorResponse {
+            error: "This link has expired or has already been used.".to_string(),
+        })),
+    }
+}
+
+/// Uses up a recipient's token before its content is returned; `410` if another
+/// read already did.
+async fn claim_recipient(pool: &DbPool, recipient: Option<&LinkRecipientRow>) -> Result<(), HttpResponse> {
+    let Some(recipient) = recipient else {
+        return Ok(());
+    };
+    match recipients::claim(pool, recipient).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(HttpResponse::Gone().json(ErrorResponse {
+            error: "This link has expired or has already been used.".to_string(),
+        })),
+        Err(e) => {
+            tracing::warn!("claim recipient: {}", e);
+            Err(HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Server error".to_string(),
+            }))
+        }
+    }
+}
+
+/// Counts a view in one statement (also using up the password of a one-time-password
+/// link) and appends `record` in the same transaction. Returns the new view count,
+/// so concurrent reads each see their own, or `None` if cleanup removed the link.
+pub async fn count_view(pool: &DbPool, config: &Config, row: &LinkRow, use_password: bool, record: &audit::Record) -> sqlx::Result<Option<i64>> {
+    let sql = if use_password {
+        "UPDATE links SET password_used = 1, view_count = view_count + 1, last_accessed_at = ? WHERE id = ? RETURNING view_count"
+    } else {
+        "UPDATE links SET view_count = view_count + 1, last_accessed_at = ? WHERE id = ? RETURNING view_count"
+    };
+    let mut tx = pool.begin().await?;
+    let views = sqlx::query_scalar::<_, i64>(sql)
+        .bind(Utc::now().to_rfc3339())
+        .bind(row.id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    audit::append(&mut tx, config, record).await?;
+    tx.commit().await?;
+    Ok(views)
+}
+
+/// Authenticates an optional bearer key for `usage`: checks its scope and per-key
+/// quota, and counts the request against it.
+pub async fn authorize_key(
+    pool: &DbPool,
+    key_limiter: &middleware::ApiKeyRateLimiter,
+    metrics: &Metrics,
+    http_req: &HttpRequest,
+    usage: Usage,
+) -> Result<Option<ApiKeyRow>, HttpResponse> {
+    let Some(key) = api_keys::authenticate(pool, http_req).await? else {
+        return Ok(None);
+    };
+    let scope = match usage {
+        Usage::Create => "create",
+        Usage::Read => "read",
+    };
+    api_keys::require_scope(Some(&key), scope)?;
+    let limit = u32::try_from(key.rate_limit_per_minute).unwrap_or(u32::MAX);
+    if let Err(wait) = key_limiter.check(key.id, limit) {
+        return Err(too_many_requests(metrics, wait));
+    }
+    api_keys::record_use(pool, key.id, usage).await;
+    Ok(Some(key))
+}
+
+/// Size limit for the caller: the API key's own limit, the signed-in user limit,
+/// or `MAX_TEXT_SIZE_BYTES`.
+fn max_text_size(config: &Config, key: Option<&ApiKeyRow>, signed_in: bool) -> usize {
+    if let Some(limit) = key.and_then(|k| k.max_text_size_bytes) {
+        return usize::try_from(limit).unwrap_or(config.max_text_size_bytes);
+    }
+    match config.policy.user_max_text_size_bytes {
+        Some(limit) if signed_in => limit,
+        _ => config.max_text_size_bytes,
+    }
+}
+
+/// Resolves `recipient_public_key`: an `slpub_` key, or `@username` of an account
+/// that registered one. Usernames are only looked up for accountable callers.
+async fn recipient_key(pool: &DbPool, value: &str, authenticated: bool) -> Result<x25519_dalek::PublicKey, HttpResponse> {
+    let value = value.trim();
+    let encoded = match value.strip_prefix('@') {
+        Some(_) if !authenticated => {
+            return Err(HttpResponse::Unauthorized().json(ErrorResponse {
+                error: "Sign in or use an API key to seal links to a username.".to_string(),
+            }));
+        }
+        Some(username) => match accounts::find_user(pool, username).await {
+            Ok(Some(accounts::User { public_key: Some(k), .. })) => k,
+            Ok(_) => {
+                return Err(HttpResponse::BadRequest().json(ErrorResponse {
+                    error: format!("No public key is registered for {}.", value),
+                }));
+            }
+            Err(e) => {
+                tracing::warn!("find user: {}", e);
+                return Err(HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: "server error".to_string(),
+                }));
+            }
+        },
+        None => value.to_string(),
+    };
+    crypto::parse_public_key(&encoded).map_err(|e| {
+        HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("recipient_public_key: {}", e),
+        })
+    })
+}
+
+/// Registers the v1 routes relative to the enclosing scope.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/policy", web::get().to(get_policy))
+        .route("/create", web::post().to(create_link).wrap(from_fn(pow::require_for_create)))
+        .route("/read/{token}", web::post().to(read_link))
+        .route("/unlock/{token}", web::post().to(unlock_link).wrap(from_fn(pow::require_for_unlock)));
+}
+
+/// Creation rules, with the size limit that applies to this caller (API key,
+/// signed-in user or anonymous).
+#[utoipa::path(
+    get,
+    path = "/api/v1/policy",
+    tag = "links",
+    responses(
+        (status = 200, description = "Rules enforced on POST /api/v1/create", body = CreatePolicy),
+        (status = 401, description = "Invalid or revoked API key", body = ErrorResponse),
+    ),
+    security((), ("api_key" = []))
+)]
+pub async fn get_policy(pool: web::Data<Arc<DbPool>>, config: web::Data<Config>, http_req: HttpRequest) -> HttpResponse {
+    let key = match api_keys::authenticate(pool.get_ref(), &http_req).await {
+        Ok(k) => k,
+        Err(resp) => return resp,
+    };
+    let signed_in = matches!(accounts::session_user(pool.get_ref(), &http_req).await, Ok(Some(_)));
+    let limit = max_text_size(&config, key.as_ref(), signed_in);
+    HttpResponse::Ok().json(config.policy.describe(limit))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/create",
+    tag = "links",
+    request_body = CreateRequest,
+    responses(
+        (status = 200, description = "Link created", body = CreateResponse),
+        (status = 400, description = "Missing text, invalid JSON, invalid expires_at/not_before/allowed_cidrs, an unusable notify_webhook/notify_email, or an invalid or unregistered recipient_public_key", body = ErrorResponse),
+        (status = 401, description = "Invalid or revoked API key, anonymous request while REQUIRE_AUTH_FOR_CREATE is on, or anonymous notify_email or `@username` recipient", body = ErrorResponse),
+        (status = 403, description = "API key lacks a scope the request needs, or notify_email is not the caller's verified email", body = ErrorResponse),
+        (status = 413, description = "Text exceeds the caller's size limit", body = ErrorResponse),
+        (status = 422, description = "Request violates the creation policy (see GET /api/v1/policy)", body = ErrorResponse),
+        (status = 428, description = "Anonymous create without a valid proof of work while POW_ENABLED is on; retry with X-ShredLink-PoW", body = PowChallenge),
+        (status = 429, description = "Rate limited per client IP, or per API key when one is given", body = ErrorResponse,
+            headers(("Retry-After" = u64, description = "Seconds until the next request is allowed"))),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security((), ("api_key" = []))
+)]
+pub async fn create_link(
+    pool: web::Data<Arc<DbPool>>,
+    config: web::Data<Config>,
+    rate_limiter: web::Data<middleware::CreateRateLimiter>,
+    key_limiter: web::Data<middleware::ApiKeyRateLimiter>,
+    metrics: web::Data<Metrics>,
+    http_req: HttpRequest,
+    req: web::Json<CreateRequest>,
+) -> HttpResponse {
+    let creator = match authorize_create(pool.get_ref(), &config, &rate_limiter, &key_limiter, &metrics, &http_req).await {
+        Ok(c) => c,
+        Err(resp) => return resp,
+    };
+    let link = match prepare_link(pool.get_ref(), &config, &metrics, &creator, &req).await {
+        Ok(l) => l,
+        Err(resp) => return resp,
+    };
+    let record = creator.audit_record(&config, &http_req, &link.link_id);
+    let stored = async {
+        let mut tx = pool.begin().await?;
+        let created = store_link(&mut tx, &config, link, &record).await?;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(created)
+    };
+    match stored.await {
+        Ok(created) => {
+            metrics.links_created.inc();
+            HttpResponse::Ok().json(created)
+        }
+        Err(e) => {
+            tracing::warn!("insert: {}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "server error".to_string(),
+            })
+        }
+    }
+}
+
+/// Who is creating links: an API key, a signed-in user, both or neither.
+pub struct Creator {
+    pub key: Option<ApiKeyRow>,
+    pub user: Option<accounts::User>,
+}
+
+impl Creator {
+    /// The audit log's `created` event for a link this creator is making.
+    pub fn audit_record(&self, config: &Config, http_req: &HttpRequest, link_id: &str) -> audit::Record {
+        audit::Record::new(audit::Event::Created, Some(link_id))
+            .actor(audit::actor(self.key.as_ref(), self.user.as_ref()))
+            .client(config, http_req)
+    }
+}
+
+/// Authenticates the caller of a create request and applies the per-key or
+/// per-IP rate limit.
+pub async fn authorize_create(
+    pool: &DbPool,
+    config: &Config,
+    rate_limiter: &middleware::CreateRateLimiter,
+    key_limiter: &middleware::ApiKeyRateLimiter,
+    metrics: &Metrics,
+    http_req: &HttpRequest,
+) -> Result<Creator, HttpResponse> {
+    // Authenticated requests are limited per key instead of per IP.
+    let key = authorize_key(pool, key_limiter, metrics, http_req, Usage::Create).await?;
+    let user = match accounts::session_user(pool, http_req).await {
+        Ok(u) => u,
+        Err(e) => {
+            tracing::warn!("session lookup: {}", e);
+            return Err(HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "server error".to_string(),
+            }));
+        }
+    };
+    if config.require_auth_for_create && key.is_none() && user.is_none() {
+        return Err(HttpResponse::Unauthorized().json(ErrorResponse {
+            error: "Sign in or use an API key to create links.".to_string(),
+        }));
+    }
+    if key.is_none() {
+        if let Some(ip) = middleware::peer_ip(http_req) {
+            if let Err(wait) = rate_limiter.check(&ip) {
+                return Err(too_many_requests(metrics, wait));
+            }
+        }
+    }
+    Ok(Creator { key, user })
+}
+
+/// Checks `req` against the caller's scopes and limits and the creation policy,
+/// and encrypts it for `store_link`.
+pub async fn prepare_link(
+    pool: &DbPool,
+    config: &Config,
+    metrics: &Metrics,
+    creator: &Creator,
+    req: &CreateRequest,
+) -> Result<NewLink, HttpResponse> {
+    let (key, user) = (&creator.key, &creator.user);
+    if req.password.as_deref().is_some_and(|p| !p.is_empty()) {
+        api_keys::require_scope(key.as_ref(), "password")?;
+    }
+    if req.one_time_view || req.one_time_password || !req.recipient_labels.is_empty() {
+        api_keys::require_scope(key.as_ref(), "one_time")?;
+    }
+    if req.text.is_empty() {
+        return Err(HttpResponse::BadRequest().json(ErrorResponse {
+            error: "text is required".to_string(),
+        }));
+    }
+    if let Some(url) = req.notify_webhook.as_deref() {
+        if config.webhooks.is_none() {
+            return Err(HttpResponse::BadRequest().json(ErrorResponse {
+                error: "Webhooks are not enabled on this server.".to_string(),
+            }));
+        }
+        if let Err(e) = webhooks::check_url(url) {
+            return Err(HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!("notify_webhook {}", e),
+            }));
+        }
+    }
+    if let Some(addr) = req.notify_email.as_deref() {
+        if config.email.is_none() {
+            return Err(HttpResponse::BadRequest().json(ErrorResponse {
+                error: "Email notifications are not enabled on this server.".to_string(),
+            }));
+        }
+        // Only accountable callers may make the server send mail to an address.
+        if key.is_none() && user.is_none() {
+            return Err(HttpResponse::Unauthorized().json(ErrorResponse {
+                error: "Sign in or use an API key to request email notifications.".to_string(),
+            }));
+        }
+        if !email::check_address(addr) {
+            return Err(HttpResponse::BadRequest().json(ErrorResponse {
+                error: "notify_email is not a valid email address".to_string(),
+            }));
+        }
+        // Only the creator's own verified address, so the server can't mail anyone else.
+        let verified = key
+            .as_ref()
+            .and_then(|k| k.email.as_deref())
+            .or(user.as_ref().and_then(|u| u.email.as_deref()));
+        if !verified.is_some_and(|v| v.eq_ignore_ascii_case(addr)) {
+            return Err(HttpResponse::Forbidden().json(ErrorResponse {
+                error: "notify_email must be the verified email of your account or API key.".to_string(),
+            }));
+        }
+    }
+    if let Some(lang) = req.notify_lang.as_deref() {
+        if !email::LANGS.contains(&lang) {
+            return Err(HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!("notify_lang must be one of: {}", email::LANGS.join(", ")),
+            }));
+        }
+    }
+    let labels = if req.recipient_labels.is_empty() {
+        Vec::new()
+    } else {
+        // The creator is the only one who can see who has read the link.
+        if key.is_none() && user.is_none() {
+            return Err(HttpResponse::Unauthorized().json(ErrorResponse {
+                error: "Sign in or use an API key to create multi-recipient links.".to_string(),
+            }));
+        }
+        if req.one_time_view || req.one_time_password {
+            return Err(HttpResponse::BadRequest().json(ErrorResponse {
+                error: "recipient_labels cannot be combined with one_time_view or one_time_password; each recipient's link is already one-time.".to_string(),
+            }));
+        }
+        match recipients::check_labels(&req.recipient_labels) {
+            Ok(l) => l,
+            Err(error) => return Err(HttpResponse::BadRequest().json(ErrorResponse { error })),
+        }
+    };
+    let allowed_cidrs = match middleware::parse_cidrs(&req.allowed_cidrs) {
+        Ok(c) => c,
+        Err(error) => return Err(HttpResponse::BadRequest().json(ErrorResponse { error })),
+    };
+    let recipient = match req.recipient_public_key.as_deref() {
+        Some(value) => match recipient_key(pool, value, key.is_some() || user.is_some()).await {
+            Ok(k) => Some(k),
+            Err(resp) => return Err(resp),
+        },
+        None => None,
+    };
+    let now = Utc::now();
+    let schedule = match schedule(req, now) {
+        Ok(s) => s,
+        Err(error) => return Err(HttpResponse::BadRequest().json(ErrorResponse { error })),
+    };
+    let max_text_size = max_text_size(config, key.as_ref(), user.is_some());
+    let ttl_minutes = match config.policy.check(req, max_text_size, schedule.ttl_minutes) {
+        Ok(ttl) => ttl,
+        Err(v @ PolicyViolation::TextTooLarge { .. }) => {
+            return Err(HttpResponse::PayloadTooLarge().json(ErrorResponse { error: v.to_string() }));
+        }
+        Err(v) => {
+            return Err(HttpResponse::UnprocessableEntity().json(ErrorResponse { error: v.to_string() }));
+        }
+    };
+    let expires_at = schedule
+        .expires_at
+        .or_else(|| ttl_minutes.map(|m| schedule.not_before.unwrap_or(now) + Duration::minutes(m)));
+
+    let password_hash = match &req.password {
+        Some(p) if !p.is_empty() => {
+            let start = std::time::Instant::now();
+            let hashed = hash_password(p);
+            metrics.observe_argon2("hash", start.elapsed());
+            let h = match hashed {
+                Ok(h) => h,
+                Err(e) => {
+                    tracing::warn!("hash_password: {}", e);
+                    return Err(HttpResponse::InternalServerError().json(ErrorResponse {
+                        error: "server error".to_string(),
+                    }));
+                }
+            };
+            Some(h)
+        }
+        _ => None,
+    };
+
+    // Sealed content is still encrypted at rest with ENCRYPTION_KEY like any other.
+    let sealed = match &recipient {
+        Some(k) => match crypto::seal_to(&req.text, k) {
+            Ok(t) => Some(t),
+            Err(e) => {
+                tracing::warn!("seal: {}", e);
+                return Err(HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: "server error".to_string(),
+                }));
+            }
+        },
+        None => None,
+    };
+    let encrypted_text = match encrypt_text(sealed.as_deref().unwrap_or(&req.text), config.encryption_key_base64.expose()) {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::warn!("encrypt: {}", e);
+            return Err(HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "server error".to_string(),
+            }));
+        }
+    };
+
+    Ok(NewLink {
+        token: nanoid!(TOKEN_LEN),
+        link_id: random_hex(8),
+        encrypted_text,
+        password_hash,
+        expires_at: expires_at.map(|t| t.to_rfc3339()),
+        not_before: schedule.not_before.map(|t| t.to_rfc3339()),
+        idle_timeout_minutes: req.idle_timeout_minutes.filter(|m| *m > 0),
+        webhook_url: req.notify_webhook.clone(),
+        webhook_secret: req.notify_webhook.as_ref().map(|_| webhooks::generate_secret()),
+        notify_email: req.notify_email.clone(),
+        notify_lang: req.notify_email.as_ref().map(|_| req.notify_lang.clone().unwrap_or_else(|| "en".to_string())),
+        one_time_view: req.one_time_view,
+        one_time_password: req.one_time_password,
+        api_key_id: key.as_ref().map(|k| k.id),
+        user_id: user.as_ref().map(|u| u.id),
+        labels,
+        allowed_cidrs,
+    })
+}
+
+/// A checked and encrypted link from `prepare_link`, not yet stored.
+pub struct NewLink {
+    pub token: String,
+    pub link_id: String,
+    encrypted_text: String,
+    password_hash: Option<String>,
+    expires_at: Option<String>,
+    not_before: Option<String>,
+    idle_timeout_minutes: Option<u32>,
+    webhook_url: Option<String>,
+    webhook_secret: Option<String>,
+    notify_email: Option<String>,
+    notify_lang: Option<String>,
+    one_time_view: bool,
+    one_time_password: bool,
+    api_key_id: Option<i64>,
+    user_id: Option<i64>,
+    labels: Vec<String>,
+    allowed_cidrs: Option<String>,
+}
+
+/// Stores `link` and its recipients inside the caller's transaction, together with
+/// the audit entry for its creation.
+pub async fn store_link(
+    conn: &mut sqlx::SqliteConnection,
+    config: &Config,
+    link: NewLink,
+    record: &audit::Record,
+) -> sqlx::Result<CreateResponse> {
+    let r = sqlx::query(
+        "INSERT INTO links (token, public_id, encrypted_text, password_hash, expires_at, not_before, idle_timeout_minutes, webhook_url, webhook_secret, notify_email, notify_lang, one_time_view, one_time_password, api_key_id, user_id, recipient_count, allowed_cidrs) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&link.token)
+    .bind(&link.link_id)
+    .bind(&link.encrypted_text)
+    .bind(&link.password_hash)
+    .bind(&link.expires_at)
+    .bind(&link.not_before)
+    .bind(link.idle_timeout_minutes)
+    .bind(&link.webhook_url)
+    .bind(&link.webhook_secret)
+    .bind(&link.notify_email)
+    .bind(&link.notify_lang)
+    .bind(if link.one_time_view { 1 } else { 0 })
+    .bind(if link.one_time_password { 1 } else { 0 })
+    .bind(link.api_key_id)
+    .bind(link.user_id)
+    .bind(link.labels.len() as i64)
+    .bind(&link.allowed_cidrs)
+    .execute(&mut *conn)
+    .await?;
+    let recipients = recipients::insert(&mut *conn, config, r.last_insert_rowid(), &link.labels).await?;
+    audit::append(&mut *conn, config, record).await?;
+
+    let base = config.base_url.trim_end_matches('/');
+    let url = format!("{}/s/{}", base, link.token);
+    Ok(CreateResponse {
+        token: link.token,
+        link_id: link.link_id,
+        url,
+        recipients,
+        webhook_secret: link.webhook_secret,
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/unlock/{token}",
+    tag = "links",
+    params(("token" = String, Path, description = "Link token")),
+    request_body = UnlockRequest,
+    responses(
+        (status = 200, description = "Password accepted (counts as a view)", body = UnlockResponse),
+        (status = 400, description = "Link is not password-protected", body = ErrorResponse),
+        (status = 401, description = "Wrong password, or invalid API key", body = ErrorResponse),
+        (status = 403, description = "API key lacks the read scope, or the link is not available yet (see Retry-After)", body = ErrorResponse,
+            headers(("Retry-After" = u64, description = "Seconds until the link goes live"))),
+        (status = 404, description = "Link does not exist, or is not allowed from the client's network", body = ErrorResponse),
+        (status = 410, description = "Link expired or already consumed", body = ErrorResponse),
+        (status = 428, description = "Anonymous unlock without a valid proof of work while POW_ENABLED is on; retry with X-ShredLink-PoW", body = PowChallenge),
+        (status = 429, description = "API key quota exceeded", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security((), ("api_key" = []))
+)]
+pub async fn unlock_link(
+    pool: web::Data<Arc<DbPool>>,
+    config: web::Data<Config>,
+    key_limiter: web::Data<middleware::ApiKeyRateLimiter>,
+    metrics: web::Data<Metrics>,
+    http_req: HttpRequest,
+    token: web::Path<String>,
+    req: web::Json<UnlockRequest>,
+) -> HttpResponse {
+    if let Err(resp) = authorize_key(pool.get_ref(), &key_limiter, &metrics, &http_req, Usage::Read).await {
+        return resp;
+    }
+    let token = token.into_inner();
+    // Outside the link's allowed networks it looks like an unknown token.
+    let (row, recipient) = match recipients::find_link((pool.get_ref()).as_ref(), &token).await {
+        Ok(Some(found)) if middleware::ip_allowed(&found.0, &http_req) => found,
+        Ok(_) => {
+            return HttpResponse::NotFound().json(ErrorResponse {
+                error: "Link not found".to_string(),
+            });
+        }
+        Err(e) => {
+            tracing::warn!("fetch: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Server error".to_string(),
+            });
+        }
+    };
+
+    if let Err(resp) = check_available(&row, recipient.as_ref()) {
+        return resp;
+    }
+
+    let hash = match &row.password_hash {
+        Some(h) if !h.is_empty() => h,
+        _ => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                error: "This link is not password-protected.".to_string(),
+            });
+        }
+    };
+
+    let start = std::time::Instant::now();
+    let verified = verify_password(&req.password, hash).unwrap_or(false);
+    metrics.observe_argon2("verify", start.elapsed());
+    if !verified {
+        metrics.unlock_failed.inc();
+        webhooks::enqueue((pool.get_ref()).as_ref(), &config, &row, Event::UnlockFailed).await;
+        let record = audit::Record::for_link(audit::Event::UnlockFailed, &row, recipient.as_ref()).client(&config, &http_req);
+        if let Err(e) = audit::record((pool.get_ref()).as_ref(), &config, &record).await {
+            tracing::warn!("audit log: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Server error".to_string(),
+            });
+        }
+        return HttpResponse::Unauthorized().json(ErrorResponse {
+            error: "Wrong password".to_string(),
+        });
+    }
+    if let Err(resp) = claim_recipient((pool.get_ref()).as_ref(), recipient.as_ref()).await {
+        return resp;
+    }
+
+    let text = match decrypt_text(&row.encrypted_text, config.encryption_key_base64.expose()) {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::warn!("decrypt: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Server error".to_string(),
+            });
+        }
+    };
+
+    let record = audit::Record::for_link(audit::Event::Unlocked, &row, recipient.as_ref()).client(&config, &http_req);
+    let views = match count_view((pool.get_ref()).as_ref(), &config, &row, row.one_time_password != 0, &record).await {
+        Ok(v) => v,
+        Err(e) => {
+            tracing::warn!("count view: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Server error".to_string(),
+            });
+        }
+    };
+
+    metrics.links_unlocked.inc();
+    webhooks::enqueue((pool.get_ref()).as_ref(), &config, &row, Event::Unlocked).await;
+    if let Some(views) = views {
+        email::notify_opened((pool.get_ref()).as_ref(), &config, &row, views).await;
+    }
+    HttpResponse::Ok().json(UnlockResponse { text })
+}
+
+/// JSON counterpart of `get_share_page` for links without a password (used by API clients).
+#[utoipa::path(
+    post,
+    path = "/api/v1/read/{token}",
+    tag = "links",
+    params(("token" = String, Path, description = "Link token")),
+    responses(
+        (status = 200, description = "Content (counts as a view)", body = UnlockResponse),
+        (status = 401, description = "Link is password-protected (use unlock), or invalid API key", body = ErrorResponse),
+        (status = 403, description = "API key lacks the read scope, or the link is not available yet (see Retry-After)", body = ErrorResponse,
+            headers(("Retry-After" = u64, description = "Seconds until the link goes live"))),
+        (status = 404, description = "Link does not exist, or is not allowed from the client's network", body = ErrorResponse),
+        (status = 410, description = "Link expired or already consumed", body = ErrorResponse),
+        (status = 429, description = "API key quota exceeded", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security((), ("api_key" = []))
+)]
+pub async fn read_link(
+    pool: web::Data<Arc<DbPool>>,
+    config: web::Data<Config>,
+    key_limiter: web::Data<middleware::ApiKeyRateLimiter>,
+    metrics: web::Data<Metrics>,
+    http_req: HttpRequest,
+    token: web::Path<String>,
+) -> HttpResponse {
+    if let Err(resp) = authorize_key(pool.get_ref(), &key_limiter, &metrics, &http_req, Usage::Read).await {
+        return resp;
+    }
+    let token = token.into_inner();
+    // Outside the link's allowed networks it looks like an unknown token.
+    let (row, recipient) = match recipients::find_link((pool.get_ref()).as_ref(), &token).await {
+        Ok(Some(found)) if middleware::ip_allowed(&found.0, &http_req) => found,
+        Ok(_) => {
+            return HttpResponse::NotFound().json(ErrorResponse {
+                error: "Link not found".to_string(),
+            });
+        }
+        Err(e) => {
+            tracing::warn!("fetch: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Server error".to_string(),
+            });
+        }
+    };
+
+    if let Err(resp) = check_available(&row, recipient.as_ref()) {
+        return resp;
+    }
+
+    if row.password_hash.as_deref().is_some_and(|h| !h.is_empty()) {
+        return HttpResponse::Unauthorized().json(ErrorResponse {
+            error: "This link is password-protected.".to_string(),
+        });
+    }
+    if let Err(resp) = claim_recipient((pool.get_ref()).as_ref(), recipient.as_ref()).await {
+        return resp;
+    }
+
+    let text = match decrypt_text(&row.encrypted_text, config.encryption_key_base64.expose()) {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::warn!("decrypt: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Server error".to_string(),
+            });
+        }
+    };
+
+    let record = audit::Record::for_link(audit::Event::Viewed, &row, recipient.as_ref()).client(&config, &http_req);
+    let views = match count_view((pool.get_ref()).as_ref(), &config, &row, false, &record).await {
+        Ok(v) => v,
+        Err(e) => {
+            tracing::warn!("count view: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Server error".to_string(),
+            });
+        }
+    };
+    metrics.links_read.inc();
+    webhooks::enqueue((pool.get_ref()).as_ref(), &config, &row, Event::Viewed).await;
+    if let Some(views) = views {
+        email::notify_opened((pool.get_ref()).as_ref(), &config, &row, views).await;
+    }
+
+    HttpResponse::Ok().json(UnlockResponse { text })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+
+    fn at(t: DateTime<Utc>) -> Option<String> {
+        Some(t.to_rfc3339())
+    }
+
+    #[test]
+    fn schedule_accepts_a_window() {
+        let now = Utc::now();
+        let req = CreateRequest {
+            not_before: at(now + Duration::hours(1)),
+            expires_at: at(now + Duration::hours(2)),
+            ..Default::default()
+        };
+        let s = schedule(&req, now).unwrap();
+        assert_eq!(s.not_before.map(|t| t.timestamp()), Some((now + Duration::hours(1)).timestamp()));
+        assert_eq!(s.expires_at.map(|t| t.timestamp()), Some((now + Duration::hours(2)).timestamp()));
+        assert_eq!(s.ttl_minutes, Some(60));
+    }
+
+    #[test]
+    fn schedule_counts_relative_expiry_from_go_live() {
+        let now = Utc::now();
+        let req = CreateRequest {
+            not_before: at(now + Duration::hours(1)),
+            expire_minutes: Some(30),
+            expire_hours: Some(1),
+            ..Default::default()
+        };
+        let s = schedule(&req, now).unwrap();
+        assert_eq!(s.expires_at, None);
+        assert_eq!(s.ttl_minutes, Some(90));
+    }
+
+    #[test]
+    fn schedule_treats_a_past_go_live_as_now() {
+        let now = Utc::now();
+        let req = CreateRequest {
+            not_before: at(now - Duration::hours(1)),
+            ..Default::default()
+        };
+        assert_eq!(schedule(&req, now).unwrap().not_before, None);
+    }
+
+    #[test]
+    fn schedule_rejects_expiry_at_or_before_go_live() {
+        let now = Utc::now();
+        for expires in [now + Duration::hours(1), now + Duration::minutes(30)] {
+            let req = CreateRequest {
+                not_before: at(now + Duration::hours(1)),
+                expires_at: at(expires),
+                ..Default::default()
+            };
+            assert_eq!(schedule(&req, now).err().as_deref(), Some("expires_at must be after not_before"));
+        }
+        let req = CreateRequest {
+            expires_at: at(now - Duration::minutes(1)),
+            ..Default::default()
+        };
+        assert_eq!(schedule(&req, now).err().as_deref(), Some("expires_at must be in the future"));
+    }
+
+    #[test]
+    fn schedule_rejects_mixed_or_malformed_times() {
+        let now = Utc::now();
+        let req = CreateRequest {
+            expires_at: at(now + Duration::hours(1)),
+            expire_minutes: Some(5),
+            ..Default::default()
+        };
+        assert!(schedule(&req, now).is_err());
+        let req = CreateRequest {
+            not_before: Some("tomorrow".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(schedule(&req, now).err().as_deref(), Some("not_before must be an RFC 3339 timestamp"));
+    }
+
+    #[test]
+    fn not_yet_available_is_403_with_retry_after() {
+        let mut row = LinkRow::for_tests();
+        row.not_before = Some(Utc::now() + Duration::seconds(90));
+        let resp = check_available(&row, None).unwrap_err();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        let wait: i64 = resp.headers().get("Retry-After").unwrap().to_str().unwrap().parse().unwrap();
+        assert!((88..=90).contains(&wait), "Retry-After {}", wait);
+    }
+
+    #[test]
+    fn expired_is_410() {
+        let mut row = LinkRow::for_tests();
+        row.expires_at = Some(Utc::now() - Duration::seconds(1));
+        assert_eq!(check_available(&row, None).unwrap_err().status(), StatusCode::GONE);
+        row.expires_at = Some(Utc::now() + Duration::minutes(1));
+        assert!(check_available(&row, None).is_ok());
+    }
+}
@@ -0,0 +1,201 @@
+//! API keys: bearer tokens that lift the per-IP create limit in favour of a per-key
+//! quota, restrict features by scope and attribute created links to the key.
+//! Keys are random and high-entropy, so a SHA-256 hash is enough to store them at rest.
+
+use actix_web::{HttpRequest, HttpResponse};
+use anyhow::Result;
+use chrono::Utc;
+use nanoid::nanoid;
+use sqlx::FromRow;
+
+use crate::crypto::sha256_hex;
+use crate::db::DbPool;
+use shredlink_types::v1::ErrorResponse;
+
+const KEY_PREFIX: &str = "slk_";
+const KEY_RANDOM_LEN: usize = 40;
+/// Characters of the key kept in clear for identification in listings.
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+/// Feature scopes a key can be granted.
+pub const SCOPES: &[&str] = &["create", "read", "password", "one_time"];
+
+#[derive(Debug, Clone, FromRow)]
+pub struct ApiKeyRow {
+    pub id: i64,
+    pub name: String,
+    pub key_prefix: String,
+    pub scopes: String,
+    pub rate_limit_per_minute: i64,
+    /// Overrides `MAX_TEXT_SIZE_BYTES` for this key.
+    pub max_text_size_bytes: Option<i64>,
+    /// Address set by the operator that `notify_email` may name.
+    pub email: Option<String>,
+    pub create_count: i64,
+    pub read_count: i64,
+    pub last_used_at: Option<String>,
+    pub revoked_at: Option<String>,
+    pub created_at: String,
+}
+
+impl ApiKeyRow {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.split(',').any(|s| s == scope)
+    }
+}
+
+/// Which usage counter a request increments.
+#[derive(Debug, Clone, Copy)]
+pub enum Usage {
+    Create,
+    Read,
+}
+
+/// Validates a comma-separated scope list and returns it normalized.
+pub fn parse_scopes(input: &str) -> Result<String> {
+    let mut scopes: Vec<&str> = Vec::new();
+    for s in input.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        if !SCOPES.contains(&s) {
+            anyhow::bail!("unknown scope {:?}; valid scopes: {}", s, SCOPES.join(","));
+        }
+        if !scopes.contains(&s) {
+            scopes.push(s);
+        }
+    }
+    if scopes.is_empty() {
+        anyhow::bail!("at least one scope is required");
+    }
+    Ok(scopes.join(","))
+}
+
+/// Creates a key and returns (id, plaintext key). The plaintext is not stored.
+pub async fn insert(
+    pool: &DbPool,
+    name: &str,
+    scopes: &str,
+    rate_limit_per_minute: u32,
+    max_text_size_bytes: Option<u32>,
+    email: Option<&str>,
+) -> Result<(i64, String)> {
+    let key = format!("{}{}", KEY_PREFIX, nanoid!(KEY_RANDOM_LEN));
+    let r = sqlx::query(
+        "INSERT INTO api_keys (name, key_prefix, key_hash, scopes, rate_limit_per_minute, max_text_size_bytes, email) \
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(name)
+    .bind(&key[..DISPLAY_PREFIX_LEN])
+    .bind(sha256_hex(&key))
+    .bind(scopes)
+    .bind(rate_limit_per_minute as i64)
+    .bind(max_text_size_bytes.map(i64::from))
+    .bind(email)
+    .execute(pool)
+    .await?;
+    Ok((r.last_insert_rowid(), key))
+}
+
+const SELECT_API_KEY: &str = "SELECT id, name, key_prefix, scopes, rate_limit_per_minute, max_text_size_bytes, email, create_count, read_count, \
+     last_used_at, revoked_at, created_at FROM api_keys";
+
+pub async fn find_active(pool: &DbPool, key: &str) -> Result<Option<ApiKeyRow>> {
+    Ok(sqlx::query_as::<_, ApiKeyRow>(&format!(
+        "{} WHERE key_hash = ? AND revoked_at IS NULL",
+        SELECT_API_KEY
+    ))
+    .bind(sha256_hex(key))
+    .fetch_optional(pool)
+    .await?)
+}
+
+pub async fn list(pool: &DbPool) -> Result<Vec<ApiKeyRow>> {
+    Ok(sqlx::query_as::<_, ApiKeyRow>(&format!("{} ORDER BY id", SELECT_API_KEY))
+        .fetch_all(pool)
+        .await?)
+}
+
+pub async fn record_use(pool: &DbPool, id: i64, usage: Usage) {
+    let column = match usage {
+        Usage::Create => "create_count",
+        Usage::Read => "read_count",
+    };
+    let sql = format!(
+        "UPDATE api_keys SET {col} = {col} + 1, last_used_at = ? WHERE id = ?",
+        col = column
+    );
+    if let Err(e) = sqlx::query(&sql)
+        .bind(Utc::now().to_rfc3339())
+        .bind(id)
+        .execute(pool)
+        .await
+    {
+        tracing::warn!("api key usage: {}", e);
+    }
+}
+
+/// Marks a key revoked. Returns false if it does not exist or was already revoked.
+pub async fn revoke(pool: &DbPool, id: i64) -> Result<bool> {
+    let r = sqlx::query("UPDATE api_keys SET revoked_at = ? WHERE id = ? AND revoked_at IS NULL")
+        .bind(Utc::now().to_rfc3339())
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(r.rows_affected() > 0)
+}
+
+/// Deletes every link created with the key inside the caller's transaction and
+/// returns their public ids.
+pub async fn delete_links(conn: &mut sqlx::SqliteConnection, id: i64) -> Result<Vec<Option<String>>> {
+    Ok(sqlx::query_scalar("DELETE FROM links WHERE api_key_id = ? RETURNING public_id")
+        .bind(id)
+        .fetch_all(conn)
+        .await?)
+}
+
+pub async fn count_links(pool: &DbPool, id: i64) -> Result<i64> {
+    let (n,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM links WHERE api_key_id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .await?;
+    Ok(n)
+}
+
+pub fn bearer_token(req: &HttpRequest) -> Option<&str> {
+    req.headers()
+        .get(actix_web::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+}
+
+/// Resolves the request's bearer key. `Ok(None)` for anonymous requests; an
+/// unknown or revoked key is rejected rather than treated as anonymous.
+pub async fn authenticate(pool: &DbPool, req: &HttpRequest) -> Result<Option<ApiKeyRow>, HttpResponse> {
+    let Some(token) = bearer_token(req) else {
+        return Ok(None);
+    };
+    match find_active(pool, token).await {
+        Ok(Some(key)) => Ok(Some(key)),
+        Ok(None) => Err(HttpResponse::Unauthorized()
+            .insert_header(("WWW-Authenticate", "Bearer"))
+            .json(ErrorResponse {
+                error: "Invalid or revoked API key".to_string(),
+            })),
+        Err(e) => {
+            tracing::warn!("api key lookup: {}", e);
+            Err(HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Server error".to_string(),
+            }))
+        }
+    }
+}
+
+/// Rejects the request with 403 if an authenticated key lacks `scope`.
+pub fn require_scope(key: Option<&ApiKeyRow>, scope: &str) -> Result<(), HttpResponse> {
+    match key {
+        Some(k) if !k.has_scope(scope) => Err(HttpResponse::Forbidden().json(ErrorResponse {
+            error: format!("API key lacks the {:?} scope", scope),
+        })),
+        _ => Ok(()),
+    }
+}
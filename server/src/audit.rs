@@ -0,0 +1,280 @@
+//! Tamper-evident audit log: who created which link, and when it was viewed,
+//! unlocked, failed to unlock, revoked or deleted. Entries carry the link's public
+//! id, never its token or content; client IP and user agent are stored as keyed
+//! hashes. Each entry's `hash` is an HMAC-SHA256 of its own JSON (without `hash`),
+//! which includes the previous entry's hash, so `audit verify` detects edited,
+//! removed or reordered rows. Both are keyed from `AUDIT_KEY`: without it a
+//! rewritten chain cannot be resealed. Truncation from the end still needs the head
+//! hash noted down outside the server. The table is also append-only at the
+//! database level, and entries are appended in the transaction of the change they
+//! record, so a change is never kept without its entry. Without `AUDIT_KEY`
+//! nothing is appended.
+
+use actix_web::HttpRequest;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use sqlx::FromRow;
+
+use crate::accounts::User;
+use crate::api_keys::ApiKeyRow;
+use crate::config::Config;
+use crate::crypto::to_hex;
+use crate::db::DbPool;
+use crate::middleware::peer_ip;
+use crate::models::{LinkRecipientRow, LinkRow};
+
+/// `prev_hash` of the first entry.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+/// Entries read at a time by `verify` and `export`.
+const PAGE_SIZE: i64 = 1000;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Event {
+    Created,
+    Viewed,
+    Unlocked,
+    UnlockFailed,
+    /// Deleted on purpose by its owner or an operator.
+    Revoked,
+    /// Removed by cleanup after expiring or being used up.
+    Deleted,
+}
+
+impl Event {
+    fn as_str(self) -> &'static str {
+        match self {
+            Event::Created => "created",
+            Event::Viewed => "viewed",
+            Event::Unlocked => "unlocked",
+            Event::UnlockFailed => "unlock_failed",
+            Event::Revoked => "revoked",
+            Event::Deleted => "deleted",
+        }
+    }
+}
+
+/// An event about to be appended.
+pub struct Record {
+    event: Event,
+    link_id: Option<String>,
+    actor: Option<String>,
+    detail: Option<String>,
+    ip_hash: Option<String>,
+    user_agent_hash: Option<String>,
+}
+
+impl Record {
+    pub fn new(event: Event, link_id: Option<&str>) -> Self {
+        Self {
+            event,
+            link_id: link_id.map(str::to_string),
+            actor: None,
+            detail: None,
+            ip_hash: None,
+            user_agent_hash: None,
+        }
+    }
+
+    /// An event on `row`, naming the recipient for a recipient's own token.
+    pub fn for_link(event: Event, row: &LinkRow, recipient: Option<&LinkRecipientRow>) -> Self {
+        let record = Self::new(event, row.public_id.as_deref());
+        match recipient {
+            Some(r) => record.detail(format!("recipient:{}", r.label)),
+            None => record,
+        }
+    }
+
+    /// Who acted, e.g. `user:3`, `api_key:7`, `anonymous`, `admin` or `cleanup`.
+    pub fn actor(mut self, actor: impl Into<String>) -> Self {
+        self.actor = Some(actor.into());
+        self
+    }
+
+    /// Extra context such as a recipient label or the reason for a deletion.
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Hashes the client IP and user agent of `req`.
+    pub fn client(mut self, config: &Config, req: &HttpRequest) -> Self {
+        let Some(key) = derive_key(config, b"shredlink audit client v1") else {
+            return self;
+        };
+        self.ip_hash = peer_ip(req).map(|ip| keyed_hash(&key, &ip.to_canonical().to_string()));
+        self.user_agent_hash = req
+            .headers()
+            .get(actix_web::http::header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(|ua| keyed_hash(&key, ua));
+        self
+    }
+}
+
+/// `api_key:{id}`, `user:{id}` or `anonymous` for the creator of a request.
+pub fn actor(key: Option<&ApiKeyRow>, user: Option<&User>) -> String {
+    match (key, user) {
+        (Some(k), _) => format!("api_key:{}", k.id),
+        (None, Some(u)) => format!("user:{}", u.id),
+        (None, None) => "anonymous".to_string(),
+    }
+}
+
+/// A stored entry, in the field order used for hashing and export.
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct Entry {
+    pub id: i64,
+    pub created_at: String,
+    pub event: String,
+    pub link_id: Option<String>,
+    pub actor: Option<String>,
+    pub detail: Option<String>,
+    pub ip_hash: Option<String>,
+    pub user_agent_hash: Option<String>,
+    pub prev_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+}
+
+impl Entry {
+    /// HMAC-SHA256 of the entry's JSON without `hash`, under the chain key.
+    pub fn compute_hash(&self, key: &[u8]) -> String {
+        let unsealed = Entry {
+            hash: None,
+            ..self.clone()
+        };
+        keyed_hash(key, &serde_json::to_string(&unsealed).unwrap_or_default())
+    }
+}
+
+const SELECT_ENTRY: &str =
+    "SELECT id, created_at, event, link_id, actor, detail, ip_hash, user_agent_hash, prev_hash, hash FROM audit_log";
+
+/// Appends `record` inside the caller's transaction. The row is inserted first so
+/// the transaction holds the write lock before it reads the previous hash. Does
+/// nothing when `AUDIT_KEY` is unset.
+pub async fn append(conn: &mut sqlx::SqliteConnection, config: &Config, record: &Record) -> sqlx::Result<()> {
+    let Some(key) = chain_key(config) else {
+        return Ok(());
+    };
+    let created_at = Utc::now().to_rfc3339();
+    let id = sqlx::query(
+        "INSERT INTO audit_log (created_at, event, link_id, actor, detail, ip_hash, user_agent_hash) \
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&created_at)
+    .bind(record.event.as_str())
+    .bind(&record.link_id)
+    .bind(&record.actor)
+    .bind(&record.detail)
+    .bind(&record.ip_hash)
+    .bind(&record.user_agent_hash)
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+    let prev_hash = sqlx::query_scalar::<_, Option<String>>("SELECT hash FROM audit_log WHERE id < ? ORDER BY id DESC LIMIT 1")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .flatten()
+        .unwrap_or_else(|| GENESIS_HASH.to_string());
+    let entry = Entry {
+        id,
+        created_at,
+        event: record.event.as_str().to_string(),
+        link_id: record.link_id.clone(),
+        actor: record.actor.clone(),
+        detail: record.detail.clone(),
+        ip_hash: record.ip_hash.clone(),
+        user_agent_hash: record.user_agent_hash.clone(),
+        prev_hash: Some(prev_hash),
+        hash: None,
+    };
+    sqlx::query("UPDATE audit_log SET prev_hash = ?, hash = ? WHERE id = ?")
+        .bind(&entry.prev_hash)
+        .bind(entry.compute_hash(&key))
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Appends `record` in its own transaction, for events that change nothing else
+/// (a failed unlock). Anything that writes should call `append` in its own transaction.
+pub async fn record(pool: &DbPool, config: &Config, record: &Record) -> sqlx::Result<()> {
+    let mut tx = pool.begin().await?;
+    append(&mut tx, config, record).await?;
+    tx.commit().await
+}
+
+/// Result of checking the whole chain.
+pub struct Verified {
+    pub entries: i64,
+    /// Hash of the last entry; note it down outside the server to detect later truncation.
+    pub head: String,
+}
+
+/// Up to `PAGE_SIZE` entries after id `after`, in order.
+pub async fn page(pool: &DbPool, after: i64) -> sqlx::Result<Vec<Entry>> {
+    sqlx::query_as::<_, Entry>(&format!("{} WHERE id > ? ORDER BY id LIMIT ?", SELECT_ENTRY))
+        .bind(after)
+        .bind(PAGE_SIZE)
+        .fetch_all(pool)
+        .await
+}
+
+/// Walks the log in order and checks ids, links and hashes.
+pub async fn verify(pool: &DbPool, config: &Config) -> anyhow::Result<Verified> {
+    let Some(key) = chain_key(config) else {
+        anyhow::bail!("AUDIT_KEY is not set");
+    };
+    let mut expected_id = 1;
+    let mut prev = GENESIS_HASH.to_string();
+    loop {
+        let entries = page(pool, expected_id - 1).await?;
+        if entries.is_empty() {
+            break;
+        }
+        for entry in entries {
+            if entry.id != expected_id {
+                anyhow::bail!("entries {} to {} are missing", expected_id, entry.id - 1);
+            }
+            if entry.prev_hash.as_deref() != Some(prev.as_str()) {
+                anyhow::bail!("entry {} does not follow entry {}", entry.id, entry.id - 1);
+            }
+            let Some(hash) = entry.hash.clone() else {
+                anyhow::bail!("entry {} is not sealed", entry.id);
+            };
+            if hash != entry.compute_hash(&key) {
+                anyhow::bail!("entry {} was modified, or AUDIT_KEY is not the key it was written with", entry.id);
+            }
+            prev = hash;
+            expected_id += 1;
+        }
+    }
+    Ok(Verified {
+        entries: expected_id - 1,
+        head: prev,
+    })
+}
+
+fn chain_key(config: &Config) -> Option<Vec<u8>> {
+    derive_key(config, b"shredlink audit chain v1")
+}
+
+fn derive_key(config: &Config, purpose: &[u8]) -> Option<Vec<u8>> {
+    let key = config.audit_key_base64.as_ref()?.expose();
+    Some(hmac_sha256(key.trim().as_bytes(), purpose))
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn keyed_hash(key: &[u8], value: &str) -> String {
+    to_hex(&hmac_sha256(key, value.as_bytes()))
+}
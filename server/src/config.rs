@@ -1,59 +1,660 @@
 use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use ipnet::IpNet;
+use serde::Deserialize;
 use std::env;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::logging::Secret;
+use crate::policy::CreationPolicy;
 
 /// Dev-only key (32 zero bytes, base64). Do not use in production.
 const DEV_ENCRYPTION_KEY: &str = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
+/// Dev-only audit key (32 bytes of 0x01, base64). Do not use in production.
+const DEV_AUDIT_KEY: &str = "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=";
 
 #[derive(Clone)]
 pub struct Config {
     pub host: String,
     pub port: u16,
     pub database_url: String,
-    pub encryption_key_base64: String,
+    pub encryption_key_base64: Secret<String>,
+    /// HMAC key for the audit log's hash chain and client hashes. Separate from
+    /// `ENCRYPTION_KEY` so `rotate-key` leaves the log verifiable. Without it
+    /// nothing is appended to the audit log.
+    pub audit_key_base64: Option<Secret<String>>,
     pub create_rate_limit_per_minute: u32,
     pub max_text_size_bytes: usize,
     pub cleanup_interval_secs: u64,
     pub base_url: String,
+    /// Reverse proxies whose `X-Forwarded-For` is believed; empty trusts none.
+    pub trusted_proxies: Vec<IpNet>,
+    pub metrics_bind: Option<String>,
+    pub shutdown_timeout_secs: u64,
+    pub signup_enabled: bool,
+    pub session_ttl_hours: u32,
+    /// Reject anonymous `create` requests; reading links stays anonymous.
+    pub require_auth_for_create: bool,
+    pub oidc: Option<OidcConfig>,
+    pub policy: CreationPolicy,
+    pub webhooks: Option<WebhookConfig>,
+    pub email: Option<EmailConfig>,
+    pub pow: Option<PowConfig>,
+    pub dev_mode: bool,
 }
 
-impl Config {
-    pub fn from_env() -> Result<Self> {
-        let host = env::var("HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
-        let port = env::var("PORT")
-            .unwrap_or_else(|_| "8080".to_string())
-            .parse()
-            .context("PORT must be a number")?;
-        let database_url = env::var("DATABASE_URL")
-            .unwrap_or_else(|_| "sqlite:data/shredlink.db?mode=rwc".to_string());
-        let encryption_key_base64 = env::var("ENCRYPTION_KEY").unwrap_or_else(|_| {
-            tracing::warn!(
-                "ENCRYPTION_KEY not set; using dev key. Set ENCRYPTION_KEY in .env for production (e.g. openssl rand -base64 32)"
-            );
-            DEV_ENCRYPTION_KEY.to_string()
-        });
-        let create_rate_limit_per_minute = env::var("CREATE_RATE_LIMIT_PER_MINUTE")
-            .unwrap_or_else(|_| "10".to_string())
+/// Signed read-receipt webhooks, enabled when `WEBHOOK_SECRET` is set.
+#[derive(Clone, Debug)]
+pub struct WebhookConfig {
+    /// HMAC-SHA256 key for the `X-ShredLink-Signature` header.
+    pub secret: Secret<String>,
+    /// Used for links created without `notify_webhook`.
+    pub default_url: Option<String>,
+    /// Delivery attempts before an event is marked failed.
+    pub max_attempts: u32,
+    /// Allow targets that resolve to loopback or private addresses.
+    pub allow_private_targets: bool,
+}
+
+/// Proof-of-work for anonymous create and unlock requests, enabled by `POW_ENABLED`.
+#[derive(Clone, Debug)]
+pub struct PowConfig {
+    /// HMAC key for challenges; derived from `ENCRYPTION_KEY` when unset.
+    pub secret: Option<Secret<String>>,
+    /// Leading zero bits required when the server is idle.
+    pub base_difficulty: u8,
+    /// Upper bound after load and failed-unlock increases.
+    pub max_difficulty: u8,
+    /// How long an issued challenge can be solved and used.
+    pub challenge_ttl_secs: u64,
+    /// Anonymous create/unlock requests per minute above which difficulty rises.
+    pub load_threshold_per_minute: u32,
+}
+
+/// OpenID Connect single sign-on, enabled when `OIDC_ISSUER_URL` is set.
+#[derive(Clone, Debug)]
+pub struct OidcConfig {
+    pub issuer_url: String,
+    pub client_id: String,
+    /// Absent for public clients, which rely on PKCE alone.
+    pub client_secret: Option<Secret<String>>,
+    pub redirect_url: String,
+    pub scopes: String,
+    pub username_claim: String,
+    pub groups_claim: String,
+    /// If non-empty, only members of one of these groups may sign in.
+    pub allowed_groups: Vec<String>,
+}
+
+/// Email notifications, enabled when `SMTP_HOST` or `SMTP_TEST_DIR` is set.
+#[derive(Clone, Debug)]
+pub struct EmailConfig {
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_tls: SmtpTls,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<Secret<String>>,
+    /// `From:` mailbox, e.g. `ShredLink <noreply@example.com>`.
+    pub from: String,
+    /// Test mode: write `.eml` files here instead of sending.
+    pub test_dir: Option<std::path::PathBuf>,
+    /// Send attempts before a message is marked failed.
+    pub max_attempts: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SmtpTls {
+    /// Plain connection upgraded with STARTTLS (port 587).
+    StartTls,
+    /// TLS from the first byte (port 465).
+    Tls,
+    /// Unencrypted; only for a local relay or sink.
+    None,
+}
+
+impl FromStr for SmtpTls {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, ()> {
+        match s.to_ascii_lowercase().as_str() {
+            "starttls" => Ok(Self::StartTls),
+            "tls" => Ok(Self::Tls),
+            "none" => Ok(Self::None),
+            _ => Err(()),
+        }
+    }
+}
+
+/// On-disk TOML config. Every field is optional; env vars override file values.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileConfig {
+    host: Option<String>,
+    port: Option<u16>,
+    database_url: Option<String>,
+    encryption_key: Option<String>,
+    audit_key: Option<String>,
+    create_rate_limit_per_minute: Option<u32>,
+    max_text_size_bytes: Option<usize>,
+    cleanup_interval_secs: Option<u64>,
+    base_url: Option<String>,
+    trusted_proxies: Option<String>,
+    metrics_bind: Option<String>,
+    shutdown_timeout_secs: Option<u64>,
+    signup_enabled: Option<bool>,
+    session_ttl_hours: Option<u32>,
+    require_auth_for_create: Option<bool>,
+    oidc_issuer_url: Option<String>,
+    oidc_client_id: Option<String>,
+    oidc_client_secret: Option<String>,
+    oidc_redirect_url: Option<String>,
+    oidc_scopes: Option<String>,
+    oidc_username_claim: Option<String>,
+    oidc_groups_claim: Option<String>,
+    oidc_allowed_groups: Option<String>,
+    policy_default_ttl_minutes: Option<u32>,
+    policy_max_ttl_minutes: Option<u32>,
+    policy_require_password: Option<bool>,
+    policy_require_one_time: Option<bool>,
+    policy_min_password_length: Option<usize>,
+    policy_min_password_classes: Option<u8>,
+    policy_user_max_text_size_bytes: Option<usize>,
+    webhook_secret: Option<String>,
+    webhook_default_url: Option<String>,
+    webhook_max_attempts: Option<u32>,
+    webhook_allow_private_targets: Option<bool>,
+    smtp_host: Option<String>,
+    smtp_port: Option<u16>,
+    smtp_tls: Option<String>,
+    smtp_username: Option<String>,
+    smtp_password: Option<String>,
+    smtp_from: Option<String>,
+    smtp_test_dir: Option<std::path::PathBuf>,
+    smtp_max_attempts: Option<u32>,
+    pow_enabled: Option<bool>,
+    pow_secret: Option<String>,
+    pow_base_difficulty: Option<u8>,
+    pow_max_difficulty: Option<u8>,
+    pow_challenge_ttl_secs: Option<u64>,
+    pow_load_threshold_per_minute: Option<u32>,
+    dev_mode: Option<bool>,
+}
+
+impl FileConfig {
+    fn read(path: &Path) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read config file {}", path.display()))?;
+        toml::from_str(&raw).with_context(|| format!("invalid config file {}", path.display()))
+    }
+}
+
+/// Reads `name` from the environment. Unset or empty means `None`; a value that
+/// does not parse is an error instead of a silent fallback to the default.
+fn env_parse<T: FromStr>(name: &str, expected: &str) -> Result<Option<T>> {
+    match env::var(name) {
+        Ok(v) if v.trim().is_empty() => Ok(None),
+        Ok(v) => v
+            .trim()
             .parse()
+            .map(Some)
+            .map_err(|_| anyhow::anyhow!("{} must be {}, got {:?}", name, expected, v)),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(env::VarError::NotUnicode(_)) => anyhow::bail!("{} is not valid UTF-8", name),
+    }
+}
+
+fn env_bool(name: &str) -> Result<Option<bool>> {
+    match env_parse::<String>(name, "a string")? {
+        None => Ok(None),
+        Some(v) => match v.to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Ok(Some(true)),
+            "0" | "false" | "no" | "off" => Ok(Some(false)),
+            _ => anyhow::bail!("{} must be true or false, got {:?}", name, v),
+        },
+    }
+}
+
+fn is_loopback_host(url: &url::Url) -> bool {
+    matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"))
+}
+
+impl Config {
+    /// Builds the config from defaults, then the optional TOML file, then env vars,
+    /// and validates the result. `dev_mode` forces dev mode on (e.g. from `--dev`).
+    pub fn load(file: Option<&Path>, dev_mode: bool) -> Result<Self> {
+        let file = match file {
+            Some(path) => FileConfig::read(path)?,
+            None => FileConfig::default(),
+        };
+
+        let host = env_parse("HOST", "a host name")?
+            .or(file.host)
+            .unwrap_or_else(|| "127.0.0.1".to_string());
+        let port = env_parse("PORT", "a port number (1-65535)")?
+            .or(file.port)
+            .unwrap_or(8080);
+        let database_url = env_parse("DATABASE_URL", "a database URL")?
+            .or(file.database_url)
+            .unwrap_or_else(|| "sqlite:data/shredlink.db?mode=rwc".to_string());
+        let encryption_key = env_parse::<String>("ENCRYPTION_KEY", "a base64 key")?.or(file.encryption_key);
+        let audit_key = env_parse::<String>("AUDIT_KEY", "a base64 key")?.or(file.audit_key);
+        let create_rate_limit_per_minute = env_parse("CREATE_RATE_LIMIT_PER_MINUTE", "a positive integer")?
+            .or(file.create_rate_limit_per_minute)
             .unwrap_or(10);
-        let max_text_size_bytes = env::var("MAX_TEXT_SIZE_BYTES")
-            .unwrap_or_else(|_| "100000".to_string())
-            .parse()
+        let max_text_size_bytes = env_parse("MAX_TEXT_SIZE_BYTES", "a positive integer")?
+            .or(file.max_text_size_bytes)
             .unwrap_or(100_000);
-        let cleanup_interval_secs = env::var("CLEANUP_INTERVAL_SECS")
-            .unwrap_or_else(|_| "600".to_string())
-            .parse()
+        let cleanup_interval_secs = env_parse("CLEANUP_INTERVAL_SECS", "a positive integer")?
+            .or(file.cleanup_interval_secs)
             .unwrap_or(600);
-        let base_url = env::var("BASE_URL").unwrap_or_else(|_| "http://127.0.0.1:8080".to_string());
+        let base_url = env_parse("BASE_URL", "a URL")?
+            .or(file.base_url)
+            .unwrap_or_else(|| "http://127.0.0.1:8080".to_string());
+        let trusted_proxies = env_parse::<String>("TRUSTED_PROXIES", "a comma-separated list")?
+            .or(file.trusted_proxies)
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(|p| {
+                p.parse::<IpNet>()
+                    .or_else(|_| p.parse::<IpAddr>().map(IpNet::from))
+                    .map_err(|_| anyhow::anyhow!("TRUSTED_PROXIES: {:?} is not a CIDR or IP address", p))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let metrics_bind = env_parse("METRICS_BIND", "an address")?.or(file.metrics_bind);
+        let shutdown_timeout_secs = env_parse("SHUTDOWN_TIMEOUT_SECS", "a non-negative integer")?
+            .or(file.shutdown_timeout_secs)
+            .unwrap_or(30);
+        let signup_enabled = env_bool("SIGNUP_ENABLED")?.or(file.signup_enabled);
+        let session_ttl_hours = env_parse("SESSION_TTL_HOURS", "a positive integer")?
+            .or(file.session_ttl_hours)
+            .unwrap_or(168);
+        let require_auth_for_create = env_bool("REQUIRE_AUTH_FOR_CREATE")?
+            .or(file.require_auth_for_create)
+            .unwrap_or(false);
+        let oidc = match env_parse::<String>("OIDC_ISSUER_URL", "a URL")?.or(file.oidc_issuer_url) {
+            None => None,
+            Some(issuer_url) => Some(OidcConfig {
+                issuer_url: issuer_url.trim_end_matches('/').to_string(),
+                client_id: env_parse("OIDC_CLIENT_ID", "a string")?
+                    .or(file.oidc_client_id)
+                    .unwrap_or_default(),
+                client_secret: env_parse("OIDC_CLIENT_SECRET", "a string")?
+                    .or(file.oidc_client_secret)
+                    .map(Secret::new),
+                redirect_url: env_parse("OIDC_REDIRECT_URL", "a URL")?
+                    .or(file.oidc_redirect_url)
+                    .unwrap_or_else(|| format!("{}/api/v1/account/oidc/callback", base_url.trim_end_matches('/'))),
+                scopes: env_parse("OIDC_SCOPES", "a space-separated list")?
+                    .or(file.oidc_scopes)
+                    .unwrap_or_else(|| "openid profile email".to_string()),
+                username_claim: env_parse("OIDC_USERNAME_CLAIM", "a claim name")?
+                    .or(file.oidc_username_claim)
+                    .unwrap_or_else(|| "preferred_username".to_string()),
+                groups_claim: env_parse("OIDC_GROUPS_CLAIM", "a claim name")?
+                    .or(file.oidc_groups_claim)
+                    .unwrap_or_else(|| "groups".to_string()),
+                allowed_groups: env_parse::<String>("OIDC_ALLOWED_GROUPS", "a comma-separated list")?
+                    .or(file.oidc_allowed_groups)
+                    .map(|g| g.split(',').map(str::trim).filter(|g| !g.is_empty()).map(str::to_string).collect())
+                    .unwrap_or_default(),
+            }),
+        };
+        // With SSO and REQUIRE_AUTH_FOR_CREATE, local sign-up would let anyone
+        // register and create links, so it is off unless set explicitly.
+        let signup_enabled = signup_enabled.unwrap_or(!(oidc.is_some() && require_auth_for_create));
+        let policy = CreationPolicy {
+            default_ttl_minutes: env_parse("POLICY_DEFAULT_TTL_MINUTES", "a positive integer")?
+                .or(file.policy_default_ttl_minutes),
+            max_ttl_minutes: env_parse("POLICY_MAX_TTL_MINUTES", "a positive integer")?.or(file.policy_max_ttl_minutes),
+            require_password: env_bool("POLICY_REQUIRE_PASSWORD")?
+                .or(file.policy_require_password)
+                .unwrap_or(false),
+            require_one_time: env_bool("POLICY_REQUIRE_ONE_TIME")?
+                .or(file.policy_require_one_time)
+                .unwrap_or(false),
+            min_password_length: env_parse("POLICY_MIN_PASSWORD_LENGTH", "a non-negative integer")?
+                .or(file.policy_min_password_length)
+                .unwrap_or(0),
+            min_password_classes: env_parse("POLICY_MIN_PASSWORD_CLASSES", "an integer from 0 to 4")?
+                .or(file.policy_min_password_classes)
+                .unwrap_or(0),
+            user_max_text_size_bytes: env_parse("POLICY_USER_MAX_TEXT_SIZE_BYTES", "a positive integer")?
+                .or(file.policy_user_max_text_size_bytes),
+        };
+        let webhook_default_url: Option<String> = env_parse("WEBHOOK_DEFAULT_URL", "a URL")?.or(file.webhook_default_url);
+        let webhook_secret: Option<String> = env_parse("WEBHOOK_SECRET", "a string")?.or(file.webhook_secret);
+        let webhooks = match (webhook_secret, webhook_default_url) {
+            (None, None) => None,
+            (secret, default_url) => Some(WebhookConfig {
+                secret: Secret::new(secret.unwrap_or_default()),
+                default_url,
+                max_attempts: env_parse("WEBHOOK_MAX_ATTEMPTS", "a positive integer")?
+                    .or(file.webhook_max_attempts)
+                    .unwrap_or(10),
+                allow_private_targets: env_bool("WEBHOOK_ALLOW_PRIVATE_TARGETS")?
+                    .or(file.webhook_allow_private_targets)
+                    .unwrap_or(false),
+            }),
+        };
+        let smtp_host: Option<String> = env_parse("SMTP_HOST", "a host name")?.or(file.smtp_host);
+        let smtp_test_dir: Option<std::path::PathBuf> = env_parse("SMTP_TEST_DIR", "a directory")?.or(file.smtp_test_dir);
+        let email = if smtp_host.is_some() || smtp_test_dir.is_some() {
+            let smtp_tls = match env_parse::<String>("SMTP_TLS", "starttls, tls or none")?.or(file.smtp_tls) {
+                None => SmtpTls::StartTls,
+                Some(v) => v
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("SMTP_TLS must be starttls, tls or none, got {:?}", v))?,
+            };
+            Some(EmailConfig {
+                smtp_host,
+                smtp_port: env_parse("SMTP_PORT", "a port number (1-65535)")?
+                    .or(file.smtp_port)
+                    .unwrap_or(match smtp_tls {
+                        SmtpTls::StartTls => 587,
+                        SmtpTls::Tls => 465,
+                        SmtpTls::None => 25,
+                    }),
+                smtp_tls,
+                smtp_username: env_parse("SMTP_USERNAME", "a string")?.or(file.smtp_username),
+                smtp_password: env_parse("SMTP_PASSWORD", "a string")?.or(file.smtp_password).map(Secret::new),
+                from: env_parse("SMTP_FROM", "a mailbox")?.or(file.smtp_from).unwrap_or_default(),
+                test_dir: smtp_test_dir,
+                max_attempts: env_parse("SMTP_MAX_ATTEMPTS", "a positive integer")?
+                    .or(file.smtp_max_attempts)
+                    .unwrap_or(10),
+            })
+        } else {
+            None
+        };
+        let pow = if env_bool("POW_ENABLED")?.or(file.pow_enabled).unwrap_or(false) {
+            Some(PowConfig {
+                secret: env_parse("POW_SECRET", "a string")?.or(file.pow_secret).map(Secret::new),
+                base_difficulty: env_parse("POW_BASE_DIFFICULTY", "an integer from 1 to 32")?
+                    .or(file.pow_base_difficulty)
+                    .unwrap_or(16),
+                max_difficulty: env_parse("POW_MAX_DIFFICULTY", "an integer from 1 to 32")?
+                    .or(file.pow_max_difficulty)
+                    .unwrap_or(24),
+                challenge_ttl_secs: env_parse("POW_CHALLENGE_TTL_SECS", "a positive integer")?
+                    .or(file.pow_challenge_ttl_secs)
+                    .unwrap_or(300),
+                load_threshold_per_minute: env_parse("POW_LOAD_THRESHOLD_PER_MINUTE", "a positive integer")?
+                    .or(file.pow_load_threshold_per_minute)
+                    .unwrap_or(60),
+            })
+        } else {
+            None
+        };
+        let dev_mode = dev_mode || env_bool("DEV_MODE")?.or(file.dev_mode).unwrap_or(false);
+
+        let encryption_key_base64 = match encryption_key {
+            Some(k) => k,
+            None if dev_mode => {
+                tracing::warn!("ENCRYPTION_KEY not set; using dev key because dev mode is enabled");
+                DEV_ENCRYPTION_KEY.to_string()
+            }
+            None => anyhow::bail!(
+                "ENCRYPTION_KEY is not set. Generate one with `openssl rand -base64 32`, \
+                 or enable dev mode (--dev or DEV_MODE=true) to use an insecure dev key"
+            ),
+        };
+        let audit_key_base64 = match audit_key {
+            Some(k) => Some(k),
+            None if dev_mode => {
+                tracing::warn!("AUDIT_KEY not set; using dev key because dev mode is enabled");
+                Some(DEV_AUDIT_KEY.to_string())
+            }
+            None => None,
+        };
 
-        Ok(Self {
+        let config = Self {
             host,
             port,
             database_url,
-            encryption_key_base64,
+            encryption_key_base64: Secret::new(encryption_key_base64),
+            audit_key_base64: audit_key_base64.map(Secret::new),
             create_rate_limit_per_minute,
             max_text_size_bytes,
             cleanup_interval_secs,
             base_url,
-        })
+            trusted_proxies,
+            metrics_bind,
+            shutdown_timeout_secs,
+            signup_enabled,
+            session_ttl_hours,
+            require_auth_for_create,
+            oidc,
+            policy,
+            webhooks,
+            email,
+            pow,
+            dev_mode,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Dev-mode defaults, for tests.
+    #[cfg(test)]
+    pub fn for_tests() -> Self {
+        Self::load(None, true).expect("default config")
+    }
+
+    /// Checks every field and reports all problems at once.
+    fn validate(&self) -> Result<()> {
+        let mut errors = Vec::new();
+
+        if self.host.trim().is_empty() {
+            errors.push("HOST must not be empty".to_string());
+        }
+        if self.port == 0 {
+            errors.push("PORT must be between 1 and 65535".to_string());
+        }
+        if !self.database_url.starts_with("sqlite:") {
+            errors.push(format!("DATABASE_URL must be a sqlite: URL, got {:?}", self.database_url));
+        }
+        let encryption_key = self.encryption_key_base64.expose().trim();
+        match BASE64.decode(encryption_key) {
+            Ok(k) if k.len() == 32 => {}
+            Ok(k) => errors.push(format!("ENCRYPTION_KEY must decode to 32 bytes, got {}", k.len())),
+            Err(_) => errors.push("ENCRYPTION_KEY is not valid base64".to_string()),
+        }
+        if encryption_key == DEV_ENCRYPTION_KEY && !self.dev_mode {
+            errors.push("ENCRYPTION_KEY is the insecure dev key; refusing to start outside dev mode".to_string());
+        }
+        if let Some(audit_key) = &self.audit_key_base64 {
+            let audit_key = audit_key.expose().trim();
+            match BASE64.decode(audit_key) {
+                Ok(k) if k.len() == 32 => {}
+                Ok(k) => errors.push(format!("AUDIT_KEY must decode to 32 bytes, got {}", k.len())),
+                Err(_) => errors.push("AUDIT_KEY is not valid base64".to_string()),
+            }
+            if audit_key == DEV_AUDIT_KEY && !self.dev_mode {
+                errors.push("AUDIT_KEY is the insecure dev key; refusing to start outside dev mode".to_string());
+            }
+            if audit_key == encryption_key {
+                errors.push("AUDIT_KEY must differ from ENCRYPTION_KEY".to_string());
+            }
+        }
+        if self.create_rate_limit_per_minute == 0 {
+            errors.push("CREATE_RATE_LIMIT_PER_MINUTE must be at least 1".to_string());
+        }
+        if self.max_text_size_bytes == 0 {
+            errors.push("MAX_TEXT_SIZE_BYTES must be at least 1".to_string());
+        }
+        if self.cleanup_interval_secs == 0 {
+            errors.push("CLEANUP_INTERVAL_SECS must be at least 1".to_string());
+        }
+        if self.session_ttl_hours == 0 {
+            errors.push("SESSION_TTL_HOURS must be at least 1".to_string());
+        }
+        if !(self.base_url.starts_with("http://") || self.base_url.starts_with("https://")) {
+            errors.push(format!("BASE_URL must start with http:// or https://, got {:?}", self.base_url));
+        }
+        if let Some(oidc) = &self.oidc {
+            if oidc.client_id.trim().is_empty() {
+                errors.push("OIDC_CLIENT_ID is required when OIDC_ISSUER_URL is set".to_string());
+            }
+            match url::Url::parse(&oidc.issuer_url) {
+                Ok(u) if u.scheme() == "https" => {}
+                // Plain HTTP is only for a local mock provider.
+                Ok(u) if u.scheme() == "http" && (self.dev_mode || is_loopback_host(&u)) => {}
+                Ok(_) => errors.push(format!(
+                    "OIDC_ISSUER_URL must use https:// (http:// only for localhost or in dev mode), got {:?}",
+                    oidc.issuer_url
+                )),
+                Err(_) => errors.push(format!("OIDC_ISSUER_URL is not a valid URL, got {:?}", oidc.issuer_url)),
+            }
+            if url::Url::parse(&oidc.redirect_url).is_err() {
+                errors.push(format!("OIDC_REDIRECT_URL is not a valid URL, got {:?}", oidc.redirect_url));
+            }
+            if !oidc.scopes.split_whitespace().any(|s| s == "openid") {
+                errors.push("OIDC_SCOPES must include openid".to_string());
+            }
+            if self.require_auth_for_create && self.signup_enabled {
+                errors.push(
+                    "SIGNUP_ENABLED must be false with OIDC_ISSUER_URL and REQUIRE_AUTH_FOR_CREATE; local sign-up would let anyone create links"
+                        .to_string(),
+                );
+            }
+        }
+        self.policy.validate(&mut errors);
+        if let Some(webhooks) = &self.webhooks {
+            if webhooks.secret.expose().is_empty() {
+                errors.push("WEBHOOK_SECRET is required when WEBHOOK_DEFAULT_URL is set".to_string());
+            } else if webhooks.secret.expose().len() < 16 {
+                errors.push("WEBHOOK_SECRET must be at least 16 characters".to_string());
+            }
+            if webhooks.max_attempts == 0 {
+                errors.push("WEBHOOK_MAX_ATTEMPTS must be at least 1".to_string());
+            }
+            if let Some(url) = &webhooks.default_url {
+                if let Err(e) = crate::webhooks::check_url(url) {
+                    errors.push(format!("WEBHOOK_DEFAULT_URL {}, got {:?}", e, url));
+                }
+            }
+        }
+        if let Some(email) = &self.email {
+            if email.from.parse::<lettre::message::Mailbox>().is_err() {
+                errors.push(format!(
+                    "SMTP_FROM must be a mailbox like \"ShredLink <noreply@example.com>\", got {:?}",
+                    email.from
+                ));
+            }
+            if email.smtp_username.is_some() != email.smtp_password.is_some() {
+                errors.push("SMTP_USERNAME and SMTP_PASSWORD must be set together".to_string());
+            }
+            if email.max_attempts == 0 {
+                errors.push("SMTP_MAX_ATTEMPTS must be at least 1".to_string());
+            }
+            if email.smtp_port == 0 {
+                errors.push("SMTP_PORT must be between 1 and 65535".to_string());
+            }
+            // Credentials never travel in clear text except to a local relay.
+            let local = matches!(email.smtp_host.as_deref(), Some("localhost" | "127.0.0.1" | "::1"));
+            if email.smtp_tls == SmtpTls::None && email.smtp_username.is_some() && !local && !self.dev_mode {
+                errors.push("SMTP_TLS=none with credentials is only allowed for localhost or in dev mode".to_string());
+            }
+        }
+        if let Some(pow) = &self.pow {
+            if pow.secret.as_ref().is_some_and(|s| s.expose().len() < 16) {
+                errors.push("POW_SECRET must be at least 16 characters".to_string());
+            }
+            if !(1..=32).contains(&pow.base_difficulty) {
+                errors.push(format!("POW_BASE_DIFFICULTY must be from 1 to 32, got {}", pow.base_difficulty));
+            }
+            if !(1..=32).contains(&pow.max_difficulty) {
+                errors.push(format!("POW_MAX_DIFFICULTY must be from 1 to 32, got {}", pow.max_difficulty));
+            } else if pow.max_difficulty < pow.base_difficulty {
+                errors.push("POW_MAX_DIFFICULTY must not be below POW_BASE_DIFFICULTY".to_string());
+            }
+            if pow.challenge_ttl_secs == 0 {
+                errors.push("POW_CHALLENGE_TTL_SECS must be at least 1".to_string());
+            }
+            if pow.load_threshold_per_minute == 0 {
+                errors.push("POW_LOAD_THRESHOLD_PER_MINUTE must be at least 1".to_string());
+            }
+        }
+        if let Some(addr) = &self.metrics_bind {
+            if addr.parse::<SocketAddr>().is_err() {
+                errors.push(format!("METRICS_BIND must be an address like 127.0.0.1:9090, got {:?}", addr));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            anyhow::bail!("invalid configuration:\n  - {}", errors.join("\n  - "))
+        }
+    }
+
+    /// Human-readable summary with the encryption and audit keys redacted, for `--check-config`.
+    pub fn summary(&self) -> String {
+        format!(
+            "host = {}\nport = {}\ndatabase_url = {}\nencryption_key = <redacted>\naudit_key = {}\n\
+             create_rate_limit_per_minute = {}\nmax_text_size_bytes = {}\ncleanup_interval_secs = {}\n\
+             base_url = {}\ntrusted_proxies = {}\nmetrics_bind = {}\nshutdown_timeout_secs = {}\n\
+             signup_enabled = {}\nsession_ttl_hours = {}\nrequire_auth_for_create = {}\n\
+             oidc_issuer_url = {}\noidc_client_id = {}\noidc_client_secret = {}\noidc_redirect_url = {}\n\
+             oidc_allowed_groups = {}\npolicy = {:?}\n\
+             webhook_secret = {}\nwebhook_default_url = {}\nwebhook_max_attempts = {}\n\
+             smtp_host = {}\nsmtp_port = {}\nsmtp_tls = {}\nsmtp_username = {}\nsmtp_password = {}\nsmtp_from = {}\n\
+             smtp_test_dir = {}\npow_enabled = {}\npow_difficulty = {}\ndev_mode = {}",
+            self.host,
+            self.port,
+            self.database_url,
+            if self.audit_key_base64.is_some() { "<redacted>" } else { "-" },
+            self.create_rate_limit_per_minute,
+            self.max_text_size_bytes,
+            self.cleanup_interval_secs,
+            self.base_url,
+            if self.trusted_proxies.is_empty() {
+                "-".to_string()
+            } else {
+                self.trusted_proxies.iter().map(IpNet::to_string).collect::<Vec<_>>().join(",")
+            },
+            self.metrics_bind.as_deref().unwrap_or("-"),
+            self.shutdown_timeout_secs,
+            self.signup_enabled,
+            self.session_ttl_hours,
+            self.require_auth_for_create,
+            self.oidc.as_ref().map_or("-", |o| o.issuer_url.as_str()),
+            self.oidc.as_ref().map_or("-", |o| o.client_id.as_str()),
+            match self.oidc.as_ref().and_then(|o| o.client_secret.as_ref()) {
+                Some(_) => "<redacted>",
+                None => "-",
+            },
+            self.oidc.as_ref().map_or("-", |o| o.redirect_url.as_str()),
+            self.oidc
+                .as_ref()
+                .filter(|o| !o.allowed_groups.is_empty())
+                .map_or("-".to_string(), |o| o.allowed_groups.join(",")),
+            self.policy,
+            if self.webhooks.is_some() { "<redacted>" } else { "-" },
+            self.webhooks.as_ref().and_then(|w| w.default_url.as_deref()).unwrap_or("-"),
+            self.webhooks.as_ref().map_or("-".to_string(), |w| w.max_attempts.to_string()),
+            self.email.as_ref().and_then(|e| e.smtp_host.as_deref()).unwrap_or("-"),
+            self.email.as_ref().map_or("-".to_string(), |e| e.smtp_port.to_string()),
+            self.email.as_ref().map_or("-".to_string(), |e| format!("{:?}", e.smtp_tls).to_lowercase()),
+            self.email.as_ref().and_then(|e| e.smtp_username.as_deref()).unwrap_or("-"),
+            match self.email.as_ref().and_then(|e| e.smtp_password.as_ref()) {
+                Some(_) => "<redacted>",
+                None => "-",
+            },
+            self.email.as_ref().map_or("-", |e| e.from.as_str()),
+            self.email
+                .as_ref()
+                .and_then(|e| e.test_dir.as_ref())
+                .map_or("-".to_string(), |d| d.display().to_string()),
+            self.pow.is_some(),
+            self.pow
+                .as_ref()
+                .map_or("-".to_string(), |p| format!("{}-{}", p.base_difficulty, p.max_difficulty)),
+            self.dev_mode,
+        )
     }
 }
@@ -4,11 +4,48 @@ use argon2::{
     Argon2,
 };
 use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
 use anyhow::Result;
-use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use base64::engine::general_purpose::{STANDARD as BASE64, URL_SAFE_NO_PAD as BASE64_URL};
+use base64::Engine;
+use hkdf::Hkdf;
+use x25519_dalek::{EphemeralSecret, PublicKey};
 
 const NONCE_LEN: usize = 12;
 
+/// Recipient public keys are `slpub_` + base64url of the 32-byte X25519 key; the
+/// matching secret keys (kept by the recipient, never sent here) use `slsec_`.
+pub const PUBLIC_KEY_PREFIX: &str = "slpub_";
+/// Content sealed to a recipient key: this prefix + base64(ephemeral public key ||
+/// nonce || AES-256-GCM ciphertext). Opened by the CLI/client library and
+/// `public/envelope.js`, which must derive the key the same way.
+pub const SEALED_PREFIX: &str = "shredlink:seal1:";
+const SEAL_INFO: &[u8] = b"shredlink seal v1";
+
+/// Returns a fresh random 32-byte key, base64-encoded, suitable for ENCRYPTION_KEY.
+pub fn generate_key() -> String {
+    let mut key = [0u8; 32];
+    OsRng.fill_bytes(&mut key);
+    BASE64.encode(key)
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Hex SHA-256 digest. Used for high-entropy secrets (API keys, session ids)
+/// that need fast lookup; user passwords go through Argon2 instead.
+pub fn sha256_hex(input: &str) -> String {
+    to_hex(&Sha256::digest(input.as_bytes()))
+}
+
+/// `len` random bytes, hex-encoded.
+pub fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    OsRng.fill_bytes(&mut bytes);
+    to_hex(&bytes)
+}
+
 pub fn hash_password(password: &str) -> Result<String> {
     let salt = SaltString::generate(&mut OsRng);
     let argon2 = Argon2::default();
@@ -60,3 +97,57 @@ pub fn decrypt_text(encrypted_b64: &str, key_b64: &str) -> Result<String> {
         .map_err(|_| anyhow::anyhow!("decrypt failed"))?;
     String::from_utf8(plaintext).map_err(|e| anyhow::anyhow!("utf8: {}", e))
 }
+
+/// Parses an `slpub_...` recipient key.
+pub fn parse_public_key(input: &str) -> Result<PublicKey> {
+    let body = input
+        .trim()
+        .strip_prefix(PUBLIC_KEY_PREFIX)
+        .ok_or_else(|| anyhow::anyhow!("public key must start with {}", PUBLIC_KEY_PREFIX))?;
+    let bytes: [u8; 32] = BASE64_URL
+        .decode(body)
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| anyhow::anyhow!("public key must be 32 bytes, base64url-encoded"))?;
+    let key = PublicKey::from(bytes);
+    // Low-order points would give a shared secret anyone can compute.
+    if !EphemeralSecret::random_from_rng(OsRng).diffie_hellman(&key).was_contributory() {
+        anyhow::bail!("public key is not usable");
+    }
+    Ok(key)
+}
+
+/// Whether `text` has the shape of `seal_to` output: the prefix, then base64 of an
+/// ephemeral key, a nonce and at least an AES-GCM tag. Says nothing about who can open it.
+pub fn is_sealed(text: &str) -> bool {
+    text.strip_prefix(SEALED_PREFIX)
+        .and_then(|b| BASE64.decode(b).ok())
+        .is_some_and(|raw| raw.len() >= 32 + NONCE_LEN + 16)
+}
+
+/// Hybrid encryption to a recipient key (X25519 + HKDF-SHA256 + AES-256-GCM), in
+/// the spirit of age: a fresh ephemeral key per message, so only the holder of the
+/// recipient's secret key can open the result.
+pub fn seal_to(plaintext: &str, recipient: &PublicKey) -> Result<String> {
+    let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+    let ephemeral_public = PublicKey::from(&ephemeral);
+    let shared = ephemeral.diffie_hellman(recipient);
+    let mut salt = [0u8; 64];
+    salt[..32].copy_from_slice(ephemeral_public.as_bytes());
+    salt[32..].copy_from_slice(recipient.as_bytes());
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(&salt), shared.as_bytes())
+        .expand(SEAL_INFO, &mut key)
+        .map_err(|e| anyhow::anyhow!("hkdf: {}", e))?;
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| anyhow::anyhow!("cipher init: {}", e))?;
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = cipher
+        .encrypt((&nonce).into(), plaintext.as_bytes())
+        .map_err(|e| anyhow::anyhow!("encrypt: {}", e))?;
+    let mut out = Vec::with_capacity(32 + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(ephemeral_public.as_bytes());
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(format!("{}{}", SEALED_PREFIX, BASE64.encode(&out)))
+}
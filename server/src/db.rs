@@ -5,6 +5,21 @@ use std::path::Path;
 use std::str::FromStr;
 
 use crate::config::Config;
+use crate::crypto::{decrypt_text, encrypt_text};
+
+pub const SELECT_LINK_BY_TOKEN: &str = "SELECT id, token, public_id, encrypted_text, password_hash, expires_at, not_before, idle_timeout_minutes, last_accessed_at, webhook_url, webhook_secret, notify_email, notify_lang, one_time_view, one_time_password, view_count, password_used, recipient_count, allowed_cidrs, created_at FROM links WHERE token = ?";
+
+pub const SELECT_LINK_BY_ID: &str = "SELECT id, token, public_id, encrypted_text, password_hash, expires_at, not_before, idle_timeout_minutes, last_accessed_at, webhook_url, webhook_secret, notify_email, notify_lang, one_time_view, one_time_password, view_count, password_used, recipient_count, allowed_cidrs, created_at FROM links WHERE id = ?";
+
+pub const SELECT_LINKS_BY_USER: &str = "SELECT id, token, public_id, encrypted_text, password_hash, expires_at, not_before, idle_timeout_minutes, last_accessed_at, webhook_url, webhook_secret, notify_email, notify_lang, one_time_view, one_time_password, view_count, password_used, recipient_count, allowed_cidrs, created_at FROM links WHERE user_id = ? ORDER BY created_at DESC, id DESC";
+
+/// SQL condition matching links idle for longer than their `idle_timeout_minutes`;
+/// `?1` is the current time.
+pub const IDLE_EXPIRED: &str = "(idle_timeout_minutes IS NOT NULL AND \
+    datetime(COALESCE(last_accessed_at, not_before, created_at), '+' || idle_timeout_minutes || ' minutes') < datetime(?1))";
+
+/// Plaintext stored (encrypted) in `key_canary` to verify the configured key.
+pub const KEY_CANARY_PLAINTEXT: &str = "shredlink-key-canary";
 
 pub type DbPool = SqlitePool;
 
@@ -30,13 +45,69 @@ pub async fn create_pool(config: &Config) -> Result<SqlitePool> {
     new_pool(&config.database_url).await
 }
 
-pub async fn run_migrations(pool: &SqlitePool) -> Result<()> {
+/// A new database in the temp directory with every migration applied, for tests.
+#[cfg(test)]
+pub async fn test_pool() -> SqlitePool {
+    let path = std::env::temp_dir().join(format!("shredlink-test-{}.db", crate::crypto::random_hex(8)));
+    let pool = new_pool(&format!("sqlite:{}", path.display())).await.expect("test database");
+    run_migrations(&pool).await.expect("migrations");
+    pool
+}
+
+/// Checkpoints the WAL into the main database file and closes all connections.
+pub async fn close_pool(pool: &SqlitePool) {
+    if let Err(e) = sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(pool).await {
+        tracing::warn!("wal checkpoint: {}", e);
+    }
+    pool.close().await;
+}
+
+async fn migrator() -> Result<Migrator> {
     let migrations_dir = std::env::var("MIGRATIONS_DIR")
         .ok()
         .map(std::path::PathBuf::from)
         .filter(|p| p.exists())
         .unwrap_or_else(|| std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("migrations"));
-    let migrator = Migrator::new(migrations_dir).await?;
-    migrator.run(pool).await?;
+    Ok(Migrator::new(migrations_dir).await?)
+}
+
+pub async fn run_migrations(pool: &SqlitePool) -> Result<()> {
+    migrator().await?.run(pool).await?;
+    Ok(())
+}
+
+/// Versions of known migrations that are not (successfully) applied to the database.
+pub async fn pending_migrations(pool: &SqlitePool) -> Result<Vec<i64>> {
+    let migrator = migrator().await?;
+    let applied: Vec<(i64,)> = sqlx::query_as("SELECT version FROM _sqlx_migrations WHERE success = 1")
+        .fetch_all(pool)
+        .await?;
+    let applied: std::collections::HashSet<i64> = applied.into_iter().map(|(v,)| v).collect();
+    Ok(migrator
+        .iter()
+        .map(|m| m.version)
+        .filter(|v| !applied.contains(v))
+        .collect())
+}
+
+/// Stores the key canary on first start; existing canaries are left untouched.
+pub async fn ensure_key_canary(pool: &SqlitePool, key_b64: &str) -> Result<()> {
+    let encrypted = encrypt_text(KEY_CANARY_PLAINTEXT, key_b64)?;
+    sqlx::query("INSERT OR IGNORE INTO key_canary (id, encrypted_value) VALUES (1, ?)")
+        .bind(&encrypted)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Returns an error if the stored canary cannot be decrypted with `key_b64`.
+pub async fn check_key_canary(pool: &SqlitePool, key_b64: &str) -> Result<()> {
+    let (encrypted,): (String,) = sqlx::query_as("SELECT encrypted_value FROM key_canary WHERE id = 1")
+        .fetch_one(pool)
+        .await?;
+    let plaintext = decrypt_text(&encrypted, key_b64)?;
+    if plaintext != KEY_CANARY_PLAINTEXT {
+        anyhow::bail!("canary mismatch");
+    }
     Ok(())
 }
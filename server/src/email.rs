@@ -0,0 +1,494 @@
+//! Email notifications: "your secret was opened" and "your secret expired unread".
+//! Messages are rendered in the link's language (`en` or `fa`) when the event
+//! happens, queued in `email_outbox`, and sent by a background task with the same
+//! backoff as webhooks. With `SMTP_TEST_DIR` set they are written there as `.eml`
+//! files instead. Like webhook events, messages never contain the token or content.
+//! They only go to the creator's own verified address, so the server cannot be
+//! used to mail arbitrary people.
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Address, AsyncFileTransport, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use sqlx::FromRow;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::{Config, EmailConfig, SmtpTls};
+use crate::db::DbPool;
+use crate::handlers::html_escape;
+use crate::metrics::Metrics;
+use crate::models::LinkRow;
+use crate::webhooks::{backoff_secs, FAILED_RETENTION_DAYS};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const BATCH_SIZE: i64 = 20;
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+pub const LANGS: &[&str] = &["en", "fa"];
+/// How long an emailed confirmation code stays valid.
+pub const VERIFICATION_TTL_MINUTES: i64 = 30;
+
+#[derive(Clone, Copy, Debug)]
+enum Notice {
+    Opened,
+    ExpiredUnread,
+}
+
+/// Validates a `notify_email` address.
+pub fn check_address(addr: &str) -> bool {
+    addr.len() <= 254 && addr.parse::<Address>().is_ok()
+}
+
+struct Rendered {
+    subject: String,
+    text: String,
+    html: String,
+}
+
+fn render(notice: Notice, lang: &str, link_id: &str, created_at: DateTime<Utc>, at: DateTime<Utc>, base_url: &str) -> Rendered {
+    let created = created_at.format("%Y-%m-%d %H:%M UTC").to_string();
+    let at = at.format("%Y-%m-%d %H:%M UTC").to_string();
+    let (subject, greeting, body, footer) = match (lang, notice) {
+        ("fa", Notice::Opened) => (
+            "راز شما در ShredLink باز شد".to_string(),
+            "سلام،",
+            format!("رازی که به اشتراک گذاشتید (لینک {}، ساخته‌شده در {}) در {} باز شد.", link_id, created, at),
+            format!("این پیام به‌طور خودکار از {} ارسال شده است.", base_url),
+        ),
+        ("fa", Notice::ExpiredUnread) => (
+            "راز شما در ShredLink بدون باز شدن منقضی شد".to_string(),
+            "سلام،",
+            format!(
+                "رازی که به اشتراک گذاشتید (لینک {}، ساخته‌شده در {}) در {} بدون اینکه باز شود منقضی شد. اگر گیرنده هنوز به آن نیاز دارد، یک لینک جدید بسازید.",
+                link_id, created, at
+            ),
+            format!("این پیام به‌طور خودکار از {} ارسال شده است.", base_url),
+        ),
+        (_, Notice::Opened) => (
+            "Your ShredLink secret was opened".to_string(),
+            "Hello,",
+            format!("The secret you shared (link {}, created {}) was opened at {}.", link_id, created, at),
+            format!("This is an automated message from {}.", base_url),
+        ),
+        (_, Notice::ExpiredUnread) => (
+            "Your ShredLink secret expired unread".to_string(),
+            "Hello,",
+            format!(
+                "The secret you shared (link {}, created {}) expired at {} without being opened. If the recipient still needs it, create a new link.",
+                link_id, created, at
+            ),
+            format!("This is an automated message from {}.", base_url),
+        ),
+    };
+    compose(lang, subject, greeting, body, footer)
+}
+
+/// Message with the code that confirms `notify_email` for an account.
+fn render_verification(lang: &str, code: &str, base_url: &str) -> Rendered {
+    let (subject, greeting, body, footer) = match lang {
+        "fa" => (
+            "ایمیل خود را در ShredLink تأیید کنید".to_string(),
+            "سلام،",
+            format!(
+                "کد تأیید شما: {}\nاین کد را در صفحه حساب وارد کنید. تا {} دقیقه معتبر است. اگر شما درخواست نکرده‌اید، این پیام را نادیده بگیرید.",
+                code, VERIFICATION_TTL_MINUTES
+            ),
+            format!("این پیام به‌طور خودکار از {} ارسال شده است.", base_url),
+        ),
+        _ => (
+            "Confirm your email for ShredLink".to_string(),
+            "Hello,",
+            format!(
+                "Your confirmation code is: {}\nEnter it on your account page. It is valid for {} minutes. If you did not ask for this, ignore this message.",
+                code, VERIFICATION_TTL_MINUTES
+            ),
+            format!("This is an automated message from {}.", base_url),
+        ),
+    };
+    compose(lang, subject, greeting, body, footer)
+}
+
+fn compose(lang: &str, subject: String, greeting: &str, body: String, footer: String) -> Rendered {
+    let (html_lang, dir, font) = match lang {
+        "fa" => ("fa", "rtl", "Vazirmatn,Tahoma,sans-serif"),
+        _ => ("en", "ltr", "'DM Sans',system-ui,sans-serif"),
+    };
+    let text = format!("{}\n\n{}\n\n-- \n{}\n", greeting, body, footer);
+    let html = format!(
+        r#"<!DOCTYPE html><html lang="{}" dir="{}"><head><meta charset="utf-8"><title>{}</title></head>
+<body style="margin:0;padding:24px;background:#f8fafc;font-family:{};color:#1e293b">
+<div style="max-width:480px;margin:0 auto;background:#fff;border:1px solid #e2e8f0;border-radius:16px;padding:24px">
+<p style="margin:0 0 12px">{}</p>
+<p style="margin:0 0 16px;line-height:1.6">{}</p>
+<p style="margin:0;font-size:12px;color:#64748b">{}</p>
+</div></body></html>"#,
+        html_lang,
+        dir,
+        html_escape(&subject),
+        font,
+        html_escape(greeting),
+        html_escape(&body),
+        html_escape(&footer),
+    );
+    Rendered { subject, text, html }
+}
+
+async fn insert<'e, E: sqlx::SqliteExecutor<'e>>(executor: E, recipient: &str, msg: &Rendered, now: &str) -> sqlx::Result<()> {
+    sqlx::query("INSERT INTO email_outbox (recipient, subject, text_body, html_body, next_attempt_at) VALUES (?, ?, ?, ?, ?)")
+        .bind(recipient)
+        .bind(&msg.subject)
+        .bind(&msg.text)
+        .bind(&msg.html)
+        .bind(now)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Queues an "opened" message if email is enabled, the link has a `notify_email`
+/// and this is its first view. `views` is the view count returned by the atomic
+/// increment for this view, so only one of several concurrent first reads sends.
+/// Errors are logged and never fail the request.
+pub async fn notify_opened(pool: &DbPool, config: &Config, row: &LinkRow, views: i64) {
+    if config.email.is_none() || views != 1 {
+        return;
+    }
+    let (Some(recipient), Some(link_id)) = (row.notify_email.as_deref(), row.public_id.as_deref()) else {
+        return;
+    };
+    let now = Utc::now();
+    let lang = row.notify_lang.as_deref().unwrap_or("en");
+    let msg = render(Notice::Opened, lang, link_id, row.created_at, now, config.base_url.trim_end_matches('/'));
+    if let Err(e) = insert(pool, recipient, &msg, &now.to_rfc3339()).await {
+        tracing::warn!("email enqueue: {}", e);
+    }
+}
+
+/// Queues the confirmation code for `recipient`, who is confirming their address.
+pub async fn send_verification(pool: &DbPool, config: &Config, recipient: &str, code: &str, lang: &str) -> sqlx::Result<()> {
+    let msg = render_verification(lang, code, config.base_url.trim_end_matches('/'));
+    insert(pool, recipient, &msg, &Utc::now().to_rfc3339()).await
+}
+
+#[derive(FromRow)]
+struct Unread {
+    public_id: String,
+    notify_email: String,
+    notify_lang: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+/// Queues "expired unread" messages for links matching `expired` (an SQL condition
+/// where `?1` is `now`) that were never opened, before cleanup deletes them.
+pub async fn enqueue_expired_unread(conn: &mut sqlx::SqliteConnection, config: &Config, expired: &str, now: DateTime<Utc>) -> anyhow::Result<()> {
+    let sql = format!(
+        "SELECT public_id, notify_email, notify_lang, created_at FROM links \
+         WHERE notify_email IS NOT NULL AND public_id IS NOT NULL AND view_count = 0 AND ({})",
+        expired
+    );
+    let rows: Vec<Unread> = sqlx::query_as(&sql).bind(now.to_rfc3339()).fetch_all(&mut *conn).await?;
+    let base_url = config.base_url.trim_end_matches('/');
+    for row in rows {
+        let lang = row.notify_lang.as_deref().unwrap_or("en");
+        let msg = render(Notice::ExpiredUnread, lang, &row.public_id, row.created_at, now, base_url);
+        insert(&mut *conn, &row.notify_email, &msg, &now.to_rfc3339()).await?;
+    }
+    Ok(())
+}
+
+enum Transport {
+    Smtp(AsyncSmtpTransport<Tokio1Executor>),
+    File(AsyncFileTransport<Tokio1Executor>),
+}
+
+impl Transport {
+    fn new(config: &EmailConfig) -> anyhow::Result<Self> {
+        if let Some(dir) = &config.test_dir {
+            std::fs::create_dir_all(dir)?;
+            return Ok(Self::File(AsyncFileTransport::new(dir)));
+        }
+        let host = config.smtp_host.as_deref().unwrap_or("localhost");
+        let mut builder = match config.smtp_tls {
+            SmtpTls::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+        }
+        .port(config.smtp_port)
+        .timeout(Some(SMTP_TIMEOUT));
+        if let (Some(user), Some(pass)) = (&config.smtp_username, &config.smtp_password) {
+            builder = builder.credentials(Credentials::new(user.clone(), pass.expose().clone()));
+        }
+        Ok(Self::Smtp(builder.build()))
+    }
+
+    /// Sends `msg`; the flag says whether retrying could help.
+    async fn send(&self, msg: Message) -> Result<(), (String, bool)> {
+        match self {
+            Self::Smtp(t) => t.send(msg).await.map(|_| ()).map_err(|e| {
+                let permanent = e.is_permanent();
+                (e.to_string(), permanent)
+            }),
+            Self::File(t) => t.send(msg).await.map(|_| ()).map_err(|e| (e.to_string(), false)),
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct Outgoing {
+    id: i64,
+    recipient: String,
+    subject: String,
+    text_body: String,
+    html_body: String,
+    attempts: i64,
+}
+
+async fn send_due(pool: &DbPool, config: &EmailConfig, from: &Mailbox, transport: &Transport, metrics: &Metrics) -> anyhow::Result<()> {
+    let due: Vec<Outgoing> = sqlx::query_as(
+        "SELECT id, recipient, subject, text_body, html_body, attempts FROM email_outbox \
+         WHERE failed_at IS NULL AND datetime(next_attempt_at) <= datetime(?) ORDER BY id LIMIT ?",
+    )
+    .bind(Utc::now().to_rfc3339())
+    .bind(BATCH_SIZE)
+    .fetch_all(pool)
+    .await?;
+
+    for out in due {
+        let result = match out.recipient.parse::<Mailbox>() {
+            Err(e) => Err((e.to_string(), true)),
+            Ok(to) => match Message::builder()
+                .from(from.clone())
+                .to(to)
+                .subject(out.subject.clone())
+                .multipart(MultiPart::alternative_plain_html(out.text_body.clone(), out.html_body.clone()))
+            {
+                Err(e) => Err((e.to_string(), true)),
+                Ok(msg) => transport.send(msg).await,
+            },
+        };
+        let (error, permanent) = match result {
+            Ok(()) => {
+                sqlx::query("DELETE FROM email_outbox WHERE id = ?").bind(out.id).execute(pool).await?;
+                metrics.emails_sent.inc();
+                continue;
+            }
+            Err(e) => e,
+        };
+        let attempts = out.attempts + 1;
+        let now = Utc::now();
+        if permanent || attempts >= config.max_attempts as i64 {
+            tracing::warn!("email {} gave up after {} attempt(s): {}", out.id, attempts, error);
+            metrics.emails_failed.inc();
+            sqlx::query("UPDATE email_outbox SET attempts = ?, last_error = ?, failed_at = ? WHERE id = ?")
+                .bind(attempts)
+                .bind(&error)
+                .bind(now.to_rfc3339())
+                .bind(out.id)
+                .execute(pool)
+                .await?;
+        } else {
+            let backoff = backoff_secs(attempts);
+            tracing::info!("email {} failed ({}); retrying in {}s", out.id, error, backoff);
+            sqlx::query("UPDATE email_outbox SET attempts = ?, last_error = ?, next_attempt_at = ? WHERE id = ?")
+                .bind(attempts)
+                .bind(&error)
+                .bind((now + ChronoDuration::seconds(backoff)).to_rfc3339())
+                .bind(out.id)
+                .execute(pool)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Sends queued messages until `shutdown` flips to true.
+pub async fn run_outbox(pool: Arc<DbPool>, config: EmailConfig, metrics: Metrics, mut shutdown: tokio::sync::watch::Receiver<bool>) {
+    let from: Mailbox = match config.from.parse() {
+        Ok(m) => m,
+        Err(e) => {
+            tracing::warn!("email disabled: invalid SMTP_FROM: {}", e);
+            return;
+        }
+    };
+    let transport = match Transport::new(&config) {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::warn!("email disabled: {}", e);
+            return;
+        }
+    };
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.changed() => break,
+        }
+        if let Err(e) = send_due(pool.as_ref(), &config, &from, &transport, &metrics).await {
+            tracing::warn!("email outbox: {}", e);
+        }
+    }
+    tracing::info!("email outbox stopped");
+}
+
+/// Drops messages that gave up more than a week ago.
+pub async fn delete_old_failures(pool: &DbPool) -> anyhow::Result<u64> {
+    let cutoff = Utc::now() - ChronoDuration::days(FAILED_RETENTION_DAYS);
+    let r = sqlx::query("DELETE FROM email_outbox WHERE failed_at IS NOT NULL AND datetime(failed_at) < datetime(?)")
+        .bind(cutoff.to_rfc3339())
+        .execute(pool)
+        .await?;
+    Ok(r.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::v1::count_view;
+    use crate::audit;
+    use crate::db::{self, SELECT_LINK_BY_ID};
+
+    const OWNER: &str = "owner@example.com";
+
+    fn email_config(test_dir: Option<std::path::PathBuf>) -> EmailConfig {
+        EmailConfig {
+            smtp_host: None,
+            smtp_port: 25,
+            smtp_tls: SmtpTls::None,
+            smtp_username: None,
+            smtp_password: None,
+            from: "ShredLink <noreply@example.com>".to_string(),
+            test_dir,
+            max_attempts: 3,
+        }
+    }
+
+    fn config() -> Config {
+        let mut config = Config::for_tests();
+        config.email = Some(email_config(None));
+        config
+    }
+
+    fn temp_dir() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("shredlink-mail-{}", crate::crypto::random_hex(8)))
+    }
+
+    /// Inserts a link with `notify_email` set and returns it.
+    async fn link(pool: &DbPool, expires_at: Option<DateTime<Utc>>) -> LinkRow {
+        let id = sqlx::query("INSERT INTO links (token, public_id, encrypted_text, expires_at, notify_email, notify_lang) VALUES (?, ?, 'x', ?, ?, 'en')")
+            .bind(crate::crypto::random_hex(8))
+            .bind(crate::crypto::random_hex(8))
+            .bind(expires_at.map(|t| t.to_rfc3339()))
+            .bind(OWNER)
+            .execute(pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+        sqlx::query_as(SELECT_LINK_BY_ID).bind(id).fetch_one(pool).await.unwrap()
+    }
+
+    async fn queued(pool: &DbPool) -> Vec<(String, String)> {
+        sqlx::query_as("SELECT recipient, subject FROM email_outbox ORDER BY id").fetch_all(pool).await.unwrap()
+    }
+
+    #[actix_web::test]
+    async fn opened_is_queued_for_the_first_view_only() {
+        let pool = db::test_pool().await;
+        let config = config();
+        let row = link(&pool, None).await;
+        for _ in 0..3 {
+            let record = audit::Record::for_link(audit::Event::Viewed, &row, None);
+            let views = count_view(&pool, &config, &row, false, &record).await.unwrap().unwrap();
+            notify_opened(&pool, &config, &row, views).await;
+        }
+        assert_eq!(queued(&pool).await, vec![(OWNER.to_string(), "Your ShredLink secret was opened".to_string())]);
+    }
+
+    #[actix_web::test]
+    async fn opened_needs_email_enabled_and_an_address() {
+        let pool = db::test_pool().await;
+        let mut row = link(&pool, None).await;
+        notify_opened(&pool, &Config::for_tests(), &row, 1).await;
+        row.notify_email = None;
+        notify_opened(&pool, &config(), &row, 1).await;
+        assert!(queued(&pool).await.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn expired_unread_is_queued_for_unopened_links() {
+        let pool = db::test_pool().await;
+        let now = Utc::now();
+        let unread = link(&pool, Some(now - ChronoDuration::minutes(1))).await;
+        let opened = link(&pool, Some(now - ChronoDuration::minutes(1))).await;
+        link(&pool, Some(now + ChronoDuration::minutes(1))).await;
+        sqlx::query("UPDATE links SET view_count = 1 WHERE id = ?").bind(opened.id).execute(&pool).await.unwrap();
+
+        let mut conn = pool.acquire().await.unwrap();
+        let expired = "expires_at IS NOT NULL AND datetime(expires_at) < datetime(?1)";
+        enqueue_expired_unread(&mut conn, &config(), expired, now).await.unwrap();
+        drop(conn);
+        let (text,): (String,) = sqlx::query_as("SELECT text_body FROM email_outbox").fetch_one(&pool).await.unwrap();
+        assert!(text.contains(unread.public_id.as_deref().unwrap()));
+        assert_eq!(queued(&pool).await.len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn delivers_through_the_file_sink() {
+        let pool = db::test_pool().await;
+        let config = config();
+        let metrics = Metrics::new().unwrap();
+        send_verification(&pool, &config, OWNER, "123456", "en").await.unwrap();
+
+        let dir = temp_dir();
+        let email = email_config(Some(dir.clone()));
+        let transport = Transport::new(&email).unwrap();
+        send_due(&pool, &email, &email.from.parse().unwrap(), &transport, &metrics).await.unwrap();
+
+        assert!(queued(&pool).await.is_empty());
+        assert_eq!(metrics.emails_sent.get(), 1);
+        let files: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|e| e.unwrap().path()).collect();
+        assert_eq!(files.len(), 1);
+        let eml = std::fs::read_to_string(&files[0]).unwrap();
+        assert!(eml.contains(&format!("To: {}", OWNER)));
+        assert!(eml.contains("123456"));
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[actix_web::test]
+    async fn retries_with_backoff_then_gives_up() {
+        let pool = db::test_pool().await;
+        let config = config();
+        let metrics = Metrics::new().unwrap();
+        send_verification(&pool, &config, OWNER, "123456", "en").await.unwrap();
+        let email = email_config(None);
+        let from = email.from.parse().unwrap();
+        // The sink's directory does not exist, so every write fails.
+        let transport = Transport::File(AsyncFileTransport::new(temp_dir()));
+
+        let mut previous: Option<DateTime<Utc>> = None;
+        for attempt in 1..email.max_attempts as i64 {
+            let before = Utc::now();
+            send_due(&pool, &email, &from, &transport, &metrics).await.unwrap();
+            let (attempts, next, error): (i64, DateTime<Utc>, Option<String>) =
+                sqlx::query_as("SELECT attempts, next_attempt_at, last_error FROM email_outbox").fetch_one(&pool).await.unwrap();
+            assert_eq!(attempts, attempt);
+            assert!(error.is_some());
+            let expected = before + ChronoDuration::seconds(backoff_secs(attempt));
+            assert!((next - expected).num_seconds().abs() <= 1, "attempt {}: next {} expected {}", attempt, next, expected);
+            assert!(previous.is_none_or(|p| next > p));
+            previous = Some(next);
+            // Not due yet: nothing is tried.
+            send_due(&pool, &email, &from, &transport, &metrics).await.unwrap();
+            let (unchanged,): (i64,) = sqlx::query_as("SELECT attempts FROM email_outbox").fetch_one(&pool).await.unwrap();
+            assert_eq!(unchanged, attempt);
+            sqlx::query("UPDATE email_outbox SET next_attempt_at = ?").bind(Utc::now().to_rfc3339()).execute(&pool).await.unwrap();
+        }
+
+        send_due(&pool, &email, &from, &transport, &metrics).await.unwrap();
+        let (attempts, failed_at): (i64, Option<String>) =
+            sqlx::query_as("SELECT attempts, failed_at FROM email_outbox").fetch_one(&pool).await.unwrap();
+        assert_eq!(attempts, email.max_attempts as i64);
+        assert!(failed_at.is_some());
+        assert_eq!(metrics.emails_failed.get(), 1);
+        assert_eq!(metrics.emails_sent.get(), 0);
+    }
+}
@@ -1,121 +1,48 @@
+use actix_web::http::header::{HeaderValue, RETRY_AFTER};
 use actix_web::{web, HttpRequest, HttpResponse};
-use chrono::{Duration, Utc};
-use nanoid::nanoid;
+use chrono::{DateTime, Utc};
 
 use std::sync::Arc;
 
+use crate::api::{recipients, requests, v1};
+use crate::audit;
 use crate::config::Config;
-use crate::crypto::{decrypt_text, encrypt_text, hash_password, verify_password};
+use crate::crypto::decrypt_text;
 use crate::db::DbPool;
-use crate::middleware;
-use crate::models::{CreateRequest, CreateResponse, ErrorResponse, LinkRow, UnlockRequest, UnlockResponse};
+use crate::metrics::Metrics;
+use crate::middleware::{self, LinkState};
+use crate::models::{LinkRecipientRow, LinkRow};
+use crate::email;
+use crate::webhooks::{self, Event};
 
-const TOKEN_LEN: usize = 16;
-
-pub async fn create_link(
-    pool: web::Data<Arc<DbPool>>,
-    config: web::Data<Config>,
-    rate_limiter: web::Data<middleware::CreateRateLimiter>,
-    http_req: HttpRequest,
-    req: web::Json<CreateRequest>,
-) -> HttpResponse {
-    if let Some(ip) = middleware::peer_ip(&http_req) {
-        if !rate_limiter.check(&ip) {
-            return HttpResponse::TooManyRequests().json(ErrorResponse {
-                error: "Too many requests. Try again later.".to_string(),
-            });
-        }
-    }
-    if req.text.is_empty() {
-        return HttpResponse::BadRequest().json(ErrorResponse {
-            error: "text is required".to_string(),
-        });
-    }
-    if req.text.len() > config.max_text_size_bytes {
-        return HttpResponse::PayloadTooLarge().json(ErrorResponse {
-            error: format!("text exceeds max size ({} bytes)", config.max_text_size_bytes),
-        });
-    }
-
-    let expire_minutes = req.expire_minutes.unwrap_or(0);
-    let expire_hours = req.expire_hours.unwrap_or(0);
-    let total_mins = expire_minutes as i64 + expire_hours as i64 * 60;
-    let expires_at = if total_mins > 0 {
-        Some(Utc::now() + Duration::minutes(total_mins))
-    } else {
-        None
-    };
-
-    let password_hash = match &req.password {
-        Some(p) if !p.is_empty() => {
-            let h = match hash_password(p) {
-                Ok(h) => h,
-                Err(e) => {
-                    tracing::warn!("hash_password: {}", e);
-                    return HttpResponse::InternalServerError().json(ErrorResponse {
-                        error: "server error".to_string(),
-                    });
-                }
-            };
-            Some(h)
-        }
-        _ => None,
-    };
-
-    let encrypted_text = match encrypt_text(&req.text, &config.encryption_key_base64) {
-        Ok(t) => t,
-        Err(e) => {
-            tracing::warn!("encrypt: {}", e);
-            return HttpResponse::InternalServerError().json(ErrorResponse {
-                error: "server error".to_string(),
-            });
-        }
-    };
-
-    let token = nanoid!(TOKEN_LEN);
-    let one_time_view = if req.one_time_view { 1 } else { 0 };
-    let one_time_password = if req.one_time_password { 1 } else { 0 };
-    let expires_at_str = expires_at.map(|t| t.to_rfc3339());
-
-    if let Err(e) = sqlx::query(
-        "INSERT INTO links (token, encrypted_text, password_hash, expires_at, one_time_view, one_time_password) \
-         VALUES (?, ?, ?, ?, ?, ?)",
+#[utoipa::path(
+    get,
+    path = "/s/{token}",
+    tag = "links",
+    params(("token" = String, Path, description = "Link token")),
+    responses(
+        (status = 200, description = "HTML page with the content (counts as a view)", content_type = "text/html"),
+        (status = 302, description = "Redirect to the password page for protected links",
+            headers(("Location" = String, description = "/unlock.html?token={token}"))),
+        (status = 403, description = "Link is scheduled and not available yet; the page counts down to it", content_type = "text/html",
+            headers(("Retry-After" = u64, description = "Seconds until the link goes live"))),
+        (status = 404, description = "Link does not exist, or is not allowed from the client's network", content_type = "text/html"),
+        (status = 410, description = "Link expired or already consumed", content_type = "text/html"),
+        (status = 500, description = "Server error", content_type = "text/html"),
     )
-    .bind(&token)
-    .bind(&encrypted_text)
-    .bind(&password_hash)
-    .bind(&expires_at_str)
-    .bind(one_time_view)
-    .bind(one_time_password)
-    .execute((pool.get_ref()).as_ref())
-    .await
-    {
-        tracing::warn!("insert: {}", e);
-        return HttpResponse::InternalServerError().json(ErrorResponse {
-            error: "server error".to_string(),
-        });
-    }
-
-    let base = config.base_url.trim_end_matches('/');
-    let url = format!("{}/s/{}", base, token);
-    HttpResponse::Ok().json(CreateResponse { token, url })
-}
-
+)]
 pub async fn get_share_page(
     pool: web::Data<Arc<DbPool>>,
     config: web::Data<Config>,
+    metrics: web::Data<Metrics>,
+    http_req: HttpRequest,
     token: web::Path<String>,
 ) -> HttpResponse {
     let token = token.into_inner();
-    let row = match sqlx::query_as::<_, LinkRow>(
-        "SELECT id, token, encrypted_text, password_hash, expires_at, one_time_view, one_time_password, view_count, password_used, created_at FROM links WHERE token = ?",
-    )
-    .bind(&token)
-    .fetch_optional((pool.get_ref()).as_ref())
-    .await
-    {
-        Ok(Some(r)) => r,
-        Ok(None) => {
+    // Outside the link's allowed networks it looks like an unknown token.
+    let (row, recipient) = match recipients::find_link((pool.get_ref()).as_ref(), &token).await {
+        Ok(Some(found)) if middleware::ip_allowed(&found.0, &http_req) => found,
+        Ok(_) => {
             return html_response(404, "Not found", "This link does not exist or has been removed.", "یافت نشد", "این لینک وجود ندارد یا حذف شده است.");
         }
         Err(e) => {
@@ -124,23 +51,108 @@ pub async fn get_share_page(
         }
     };
 
-    if middleware::is_link_expired_or_consumed(&row) {
-        return html_response(410, "Expired", "This link has expired or has already been used.", "منقضی شده", "این لینک منقضی شده یا قبلاً استفاده شده است.");
+    match recipients::link_state(&row, recipient.as_ref()) {
+        LinkState::Available => {}
+        LinkState::NotYetAvailable(at) => return not_yet_available(at),
+        LinkState::Expired | LinkState::Consumed => {
+            return html_response(410, "Expired", "This link has expired or has already been used.", "منقضی شده", "این لینک منقضی شده یا قبلاً استفاده شده است.");
+        }
     }
 
     if let Some(ref hash) = row.password_hash {
         if hash.is_empty() {
-            return show_decrypted(pool, config, row).await;
+            return show_decrypted(pool, config, metrics, &http_req, row, recipient).await;
         }
         return HttpResponse::Found()
             .append_header(("Location", format!("/unlock.html?token={}", token)))
             .finish();
     }
 
-    show_decrypted(pool, config, row).await
+    show_decrypted(pool, config, metrics, &http_req, row, recipient).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/r/{token}",
+    tag = "requests",
+    params(("token" = String, Path, description = "Token from the request link")),
+    responses(
+        (status = 302, description = "Redirect to the form for answering the request",
+            headers(("Location" = String, description = "/request.html?token={token}"))),
+        (status = 404, description = "Request does not exist", content_type = "text/html"),
+        (status = 410, description = "Request expired or already answered", content_type = "text/html"),
+        (status = 500, description = "Server error", content_type = "text/html"),
+    )
+)]
+pub async fn get_request_page(pool: web::Data<Arc<DbPool>>, token: web::Path<String>) -> HttpResponse {
+    let token = token.into_inner();
+    match requests::find_open((pool.get_ref()).as_ref(), &token).await {
+        Ok(Some(_)) => HttpResponse::Found()
+            .append_header(("Location", format!("/request.html?token={}", token)))
+            .finish(),
+        Ok(None) => match sqlx::query_scalar::<_, i64>("SELECT id FROM secret_requests WHERE token = ?")
+            .bind(&token)
+            .fetch_optional((pool.get_ref()).as_ref())
+            .await
+        {
+            Ok(Some(_)) => html_response(
+                410,
+                "Closed",
+                "This request has expired or has already been answered.",
+                "بسته شده",
+                "این درخواست منقضی شده یا قبلاً پاسخ داده شده است.",
+            ),
+            Ok(None) => html_response(404, "Not found", "This link does not exist or has been removed.", "یافت نشد", "این لینک وجود ندارد یا حذف شده است."),
+            Err(e) => {
+                tracing::warn!("fetch request: {}", e);
+                html_response(500, "Error", "Something went wrong.", "خطا", "مشکلی پیش آمد.")
+            }
+        },
+        Err(_) => html_response(500, "Error", "Something went wrong.", "خطا", "مشکلی پیش آمد."),
+    }
+}
+
+/// Page for a scheduled link: counts down to `at` and reloads once it is live.
+fn not_yet_available(at: DateTime<Utc>) -> HttpResponse {
+    let countdown = format!(
+        r#"<p id="countdown" class="text-3xl font-semibold tabular-nums text-indigo-600 dark:text-indigo-400 mb-2" dir="ltr" data-until="{}"></p>
+<script>
+(function(){{
+  var el=document.getElementById('countdown');
+  var until=Date.parse(el.getAttribute('data-until'));
+  function pad(n){{return n<10?'0'+n:''+n;}}
+  function tick(){{
+    var left=Math.max(0,Math.ceil((until-Date.now())/1000));
+    var d=Math.floor(left/86400),h=Math.floor(left%86400/3600),m=Math.floor(left%3600/60),s=left%60;
+    el.textContent=(d>0?d+'d ':'')+pad(h)+':'+pad(m)+':'+pad(s);
+    if(left===0){{location.reload();return;}}
+    setTimeout(tick,1000);
+  }}
+  tick();
+}})();
+</script>"#,
+        at.to_rfc3339()
+    );
+    let wait = (at - Utc::now()).num_seconds().max(1);
+    let mut res = html_page(
+        403,
+        "Not available yet",
+        "This link becomes available in:",
+        "هنوز در دسترس نیست",
+        "این لینک در این زمان در دسترس قرار می‌گیرد:",
+        &countdown,
+    );
+    if let Ok(v) = HeaderValue::from_str(&wait.to_string()) {
+        res.headers_mut().insert(RETRY_AFTER, v);
+    }
+    res
 }
 
 fn html_response(status: u16, title_en: &str, msg_en: &str, title_fa: &str, msg_fa: &str) -> HttpResponse {
+    html_page(status, title_en, msg_en, title_fa, msg_fa, "")
+}
+
+fn html_page(status: u16, title_en: &str, msg_en: &str, title_fa: &str, msg_fa: &str, extra: &str) -> HttpResponse {
     let body = format!(
         r#"<!DOCTYPE html><html lang="en" dir="ltr"><head><meta charset="utf-8"><meta name="viewport" content="width=device-width,initial-scale=1"><title>ShredLink</title>
 <script>
@@ -168,6 +180,7 @@ fn html_response(status: u16, title_en: &str, msg_en: &str, title_fa: &str, msg_
 <div class="bg-white dark:bg-slate-800 rounded-2xl shadow-sm border border-slate-200 dark:border-slate-700 p-8 max-w-md w-full text-center">
   <h1 id="err-title" class="text-xl font-semibold text-slate-800 dark:text-slate-100 mb-2" data-en="{}" data-fa="{}">{}</h1>
   <p id="err-msg" class="text-slate-600 dark:text-slate-400 mb-4" data-en="{}" data-fa="{}">{}</p>
+  {}
   <a href="/" id="err-back" class="mt-4 inline-block text-indigo-600 dark:text-indigo-400 hover:underline" data-en="Back home" data-fa="بازگشت به خانه">Back home</a>
 </div>
 <script>
@@ -184,7 +197,7 @@ fn html_response(status: u16, title_en: &str, msg_en: &str, title_fa: &str, msg_
 }})();
 </script>
 </body></html>"#,
-        title_en, title_fa, title_en, msg_en, msg_fa, msg_en
+        title_en, title_fa, title_en, msg_en, msg_fa, msg_en, extra
     );
     HttpResponse::build(actix_web::http::StatusCode::from_u16(status).unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR))
         .content_type("text/html; charset=utf-8")
@@ -194,9 +207,24 @@ fn html_response(status: u16, title_en: &str, msg_en: &str, title_fa: &str, msg_
 async fn show_decrypted(
     pool: web::Data<Arc<DbPool>>,
     config: web::Data<Config>,
+    metrics: web::Data<Metrics>,
+    http_req: &HttpRequest,
     mut row: LinkRow,
+    recipient: Option<LinkRecipientRow>,
 ) -> HttpResponse {
-    let text = match decrypt_text(&row.encrypted_text, &config.encryption_key_base64) {
+    if let Some(recipient) = &recipient {
+        match recipients::claim((pool.get_ref()).as_ref(), recipient).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return html_response(410, "Expired", "This link has expired or has already been used.", "منقضی شده", "این لینک منقضی شده یا قبلاً استفاده شده است.");
+            }
+            Err(e) => {
+                tracing::warn!("claim recipient: {}", e);
+                return html_response(500, "Error", "Something went wrong.", "خطا", "مشکلی پیش آمد.");
+            }
+        }
+    }
+    let text = match decrypt_text(&row.encrypted_text, config.encryption_key_base64.expose()) {
         Ok(t) => t,
         Err(e) => {
             tracing::warn!("decrypt: {}", e);
@@ -204,13 +232,20 @@ async fn show_decrypted(
         }
     };
 
-    let new_view_count = row.view_count + 1;
-    let _ = sqlx::query("UPDATE links SET view_count = ? WHERE id = ?")
-        .bind(new_view_count)
-        .bind(row.id)
-        .execute((pool.get_ref()).as_ref())
-        .await;
-    row.view_count = new_view_count;
+    let record = audit::Record::for_link(audit::Event::Viewed, &row, recipient.as_ref()).client(&config, http_req);
+    let views = match v1::count_view((pool.get_ref()).as_ref(), &config, &row, false, &record).await {
+        Ok(v) => v,
+        Err(e) => {
+            tracing::warn!("count view: {}", e);
+            return html_response(500, "Error", "Something went wrong.", "خطا", "مشکلی پیش آمد.");
+        }
+    };
+    if let Some(views) = views {
+        row.view_count = views;
+        email::notify_opened((pool.get_ref()).as_ref(), &config, &row, views).await;
+    }
+    metrics.links_read.inc();
+    webhooks::enqueue((pool.get_ref()).as_ref(), &config, &row, Event::Viewed).await;
 
     let display = html_escape(&text);
     let raw_json = serde_json::to_string(&text).unwrap_or_default();
@@ -220,7 +255,7 @@ async fn show_decrypted(
         .body(body)
 }
 
-fn html_escape(s: &str) -> String {
+pub fn html_escape(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
@@ -254,6 +289,7 @@ fn content_view_html(display_content: &str, raw_content_json: &str) -> String {
 </main>
 </div>
 <script type="application/json" id="content-payload">{}</script>
+<script src="/envelope.js"></script>
 <script>
 (function(){{
   var dark = localStorage.getItem('shredlink_dark')==='1' || (!localStorage.getItem('shredlink_dark') && window.matchMedia('(prefers-color-scheme: dark)').matches);
@@ -264,6 +300,15 @@ fn content_view_html(display_content: &str, raw_content_json: &str) -> String {
   var payloadEl = document.getElementById('content-payload');
   var rawText = '';
   try {{ if(payloadEl && payloadEl.textContent) rawText = JSON.parse(payloadEl.textContent); }} catch(e) {{}}
+  if(window.shredlinkEnvelope && window.shredlinkEnvelope.isEnvelope(rawText)){{
+    var displayEl = document.getElementById('content-display');
+    var sealed = window.shredlinkEnvelope.isSealed(rawText);
+    window.shredlinkEnvelope.open(rawText).then(function(t){{ rawText = t; displayEl.textContent = t; }}, function(){{
+      rawText = '';
+      if(sealed) displayEl.textContent = lang==='fa' ? 'این محتوا برای کلید یک گیرنده مهر شده است. آن را در مرورگری که کلید شما را دارد باز کنید یا از shredlink get استفاده کنید.' : 'This content is sealed to a recipient key. Open it in the browser that holds your key, or with shredlink get.';
+      else displayEl.textContent = lang==='fa' ? 'رمزگشایی محتوا ممکن نشد. کامل بودن لینک را بررسی کنید.' : 'Could not decrypt content. Check that the link is complete.';
+    }});
+  }}
   function doCopy(){{
     var ta = document.createElement('textarea');
     ta.value = rawText;
@@ -311,78 +356,3 @@ fn content_view_html(display_content: &str, raw_content_json: &str) -> String {
         safe_json
     )
 }
-
-pub async fn unlock_link(
-    pool: web::Data<Arc<DbPool>>,
-    config: web::Data<Config>,
-    token: web::Path<String>,
-    req: web::Json<UnlockRequest>,
-) -> HttpResponse {
-    let token = token.into_inner();
-    let row = match sqlx::query_as::<_, LinkRow>(
-        "SELECT id, token, encrypted_text, password_hash, expires_at, one_time_view, one_time_password, view_count, password_used, created_at FROM links WHERE token = ?",
-    )
-    .bind(&token)
-    .fetch_optional((pool.get_ref()).as_ref())
-    .await
-    {
-        Ok(Some(r)) => r,
-        Ok(None) => {
-            return HttpResponse::NotFound().json(ErrorResponse {
-                error: "Link not found".to_string(),
-            });
-        }
-        Err(e) => {
-            tracing::warn!("fetch: {}", e);
-            return HttpResponse::InternalServerError().json(ErrorResponse {
-                error: "Server error".to_string(),
-            });
-        }
-    };
-
-    if middleware::is_link_expired_or_consumed(&row) {
-        return HttpResponse::Gone().json(ErrorResponse {
-            error: "This link has expired or has already been used.".to_string(),
-        });
-    }
-
-    let hash = match &row.password_hash {
-        Some(h) if !h.is_empty() => h,
-        _ => {
-            return HttpResponse::BadRequest().json(ErrorResponse {
-                error: "This link is not password-protected.".to_string(),
-            });
-        }
-    };
-
-    if !verify_password(&req.password, hash).unwrap_or(false) {
-        return HttpResponse::Unauthorized().json(ErrorResponse {
-            error: "Wrong password".to_string(),
-        });
-    }
-
-    let text = match decrypt_text(&row.encrypted_text, &config.encryption_key_base64) {
-        Ok(t) => t,
-        Err(e) => {
-            tracing::warn!("decrypt: {}", e);
-            return HttpResponse::InternalServerError().json(ErrorResponse {
-                error: "Server error".to_string(),
-            });
-        }
-    };
-
-    if row.one_time_password != 0 {
-        let _ = sqlx::query("UPDATE links SET password_used = 1, view_count = view_count + 1 WHERE id = ?")
-            .bind(row.id)
-            .execute((pool.get_ref()).as_ref())
-            .await;
-    } else {
-        let _ = sqlx::query("UPDATE links SET view_count = view_count + 1 WHERE id = ?")
-            .bind(row.id)
-            .execute((pool.get_ref()).as_ref())
-            .await;
-    }
-
-    HttpResponse::Ok().json(UnlockResponse { text })
-}
-
@@ -0,0 +1,104 @@
+use actix_web::{web, HttpResponse};
+use chrono::{TimeZone, Utc};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::db::{self, DbPool};
+use crate::middleware::CleanupStatus;
+use crate::models::{HealthCheck, HealthResponse};
+
+/// Grace period on top of two cleanup intervals before the task counts as stalled.
+const CLEANUP_GRACE_SECS: i64 = 60;
+
+fn check(result: anyhow::Result<Option<String>>) -> HealthCheck {
+    match result {
+        Ok(detail) => HealthCheck { ok: true, detail },
+        Err(e) => HealthCheck {
+            ok: false,
+            detail: Some(e.to_string()),
+        },
+    }
+}
+
+/// Liveness: the process is up and serving requests.
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "operations",
+    responses((status = 200, description = "Process is alive", body = HealthResponse))
+)]
+pub async fn healthz() -> HttpResponse {
+    HttpResponse::Ok().json(HealthResponse {
+        status: "ok",
+        checks: BTreeMap::new(),
+    })
+}
+
+/// Readiness: database, migrations, encryption key and cleanup task are all healthy.
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "operations",
+    responses(
+        (status = 200, description = "All checks pass", body = HealthResponse),
+        (status = 503, description = "At least one check failed", body = HealthResponse),
+    )
+)]
+pub async fn readyz(
+    pool: web::Data<Arc<DbPool>>,
+    config: web::Data<Config>,
+    cleanup: web::Data<CleanupStatus>,
+) -> HttpResponse {
+    let pool = (pool.get_ref()).as_ref();
+    let mut checks = BTreeMap::new();
+
+    let database = sqlx::query("SELECT 1")
+        .execute(pool)
+        .await
+        .map(|_| None)
+        .map_err(anyhow::Error::from);
+    checks.insert("database", check(database));
+
+    let migrations = db::pending_migrations(pool).await.and_then(|pending| {
+        if pending.is_empty() {
+            Ok(None)
+        } else {
+            Err(anyhow::anyhow!("pending migrations: {:?}", pending))
+        }
+    });
+    checks.insert("migrations", check(migrations));
+
+    let key = db::check_key_canary(pool, config.encryption_key_base64.expose())
+        .await
+        .map(|_| None)
+        .map_err(|_| anyhow::anyhow!("ENCRYPTION_KEY does not decrypt the stored canary"));
+    checks.insert("encryption_key", check(key));
+
+    let max_age = config.cleanup_interval_secs as i64 * 2 + CLEANUP_GRACE_SECS;
+    let last_run = cleanup
+        .last_run()
+        .and_then(|t| Utc.timestamp_opt(t, 0).single())
+        .map(|t| format!("last run {}", t.to_rfc3339()));
+    let cleanup_check = if cleanup.is_recent(max_age) {
+        Ok(last_run)
+    } else {
+        Err(anyhow::anyhow!(
+            "no cleanup pass in the last {}s ({})",
+            max_age,
+            last_run.unwrap_or_else(|| "never run".to_string())
+        ))
+    };
+    checks.insert("cleanup", check(cleanup_check));
+
+    let ready = checks.values().all(|c| c.ok);
+    let body = HealthResponse {
+        status: if ready { "ready" } else { "not_ready" },
+        checks,
+    };
+    if ready {
+        HttpResponse::Ok().json(body)
+    } else {
+        HttpResponse::ServiceUnavailable().json(body)
+    }
+}
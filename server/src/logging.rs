@@ -0,0 +1,254 @@
+//! Log output: text or JSON lines (`LOG_FORMAT`), a request span carrying the
+//! `X-Request-Id`, method, route pattern and status, and a redaction pass over
+//! every formatted line before it is written. Keys and passwords from the
+//! configuration are held in `Secret`, which never formats its value. The line
+//! redaction is a best-effort safety net on top: it masks the token, key and
+//! content shapes it knows, but cannot recognise a secret in an arbitrary string.
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::Error;
+use regex::Regex;
+use std::fmt;
+use std::io::Write;
+use std::sync::LazyLock;
+use std::time::Instant;
+use tracing::Instrument;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::EnvFilter;
+
+use crate::crypto::random_hex;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+/// Longest client-supplied request id that is passed through; longer or unusual
+/// ones are replaced with a fresh id.
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines.
+    Text,
+    /// One JSON object per line, with the request span's fields.
+    Json,
+}
+
+/// Installs the global subscriber. `RUST_LOG` adjusts the level (default `info`
+/// for the server).
+pub fn init(format: LogFormat) -> anyhow::Result<()> {
+    let filter = EnvFilter::from_default_env().add_directive("shredlink_server=info".parse()?);
+    // No ANSI styling: escape codes between a field name and its value would hide
+    // the field from redaction.
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_ansi(false)
+        .with_writer(Redacted);
+    match format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().with_current_span(true).with_span_list(false).init(),
+    }
+    Ok(())
+}
+
+/// A value that must not reach the logs: `Debug` and `Display` print `[redacted]`.
+/// Read it with `expose`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[redacted]")
+    }
+}
+
+impl<T> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[redacted]")
+    }
+}
+
+/// Patterns and replacements, applied in order. Field-name rules match both the
+/// text format (`password=...`) and JSON (`"password":"..."`, also when escaped
+/// inside a JSON string).
+static RULES: LazyLock<Vec<(Regex, &'static str)>> = LazyLock::new(|| {
+    const FIELDS: &str = "password|passwd|text|plaintext|content|encrypted_text|password_hash|secret|token|api_key|authorization|cookie|new_key|encryption_key";
+    // A quoted string, a string quoted with escaped quotes (inside a JSON message),
+    // or a bare word.
+    const QUOTED: &str = r#""(?:[^"\\]|\\.)*""#;
+    const ESCAPED: &str = r#"\\"(?:[^"\\]|\\\\\\"|\\[^"])*\\""#;
+    const BARE: &str = r#"[^\s"\\,;&}]+"#;
+    [
+        // Link and request tokens in paths and query strings.
+        (r"(/(?:s|r|read|unlock|links|submit)/)[A-Za-z0-9_-]{8,}", "${1}[redacted]"),
+        (r"(?i)(\btoken=)[^&\s\x22\\]+", "${1}[redacted]"),
+        // Client-side keys in URL fragments.
+        (r"#[A-Za-z0-9_-]{20,}", "#[redacted]"),
+        // API keys, recipient secret keys, webhook secrets, bearer credentials and session cookies.
+        (r"\bslk_[A-Za-z0-9_-]+", "slk_[redacted]"),
+        (r"\bslsec_[A-Za-z0-9_-]+", "slsec_[redacted]"),
+        (r"\bwhsec_[A-Za-z0-9_-]+", "whsec_[redacted]"),
+        (r"(?i)(\bbearer\s+)[^\s\x22\\]+", "${1}[redacted]"),
+        (r"(shredlink_session=)[^;\s\x22\\]+", "${1}[redacted]"),
+        // Envelopes, sealed content and shares.
+        (r"shredlink:(v1|seal1|share1):[A-Za-z0-9+/=:_-]+", "shredlink:${1}:[redacted]"),
+        // Sensitive fields as JSON members, plain or escaped inside a message
+        // string, and as key=value pairs. JSON stays valid.
+        (&format!(r#"(?i)("(?:{FIELDS})"\s*:\s*)(?:{QUOTED}|{BARE})"#), "${1}\"[redacted]\""),
+        (&format!(r#"(?i)(\\"(?:{FIELDS})\\"\s*:\s*)(?:{ESCAPED}|{BARE})"#), r#"${1}\"[redacted]\""#),
+        (&format!(r#"(?i)(\b(?:{FIELDS})=)(?:{QUOTED}|{ESCAPED}|{BARE})"#), "${1}[redacted]"),
+    ]
+    .into_iter()
+    .map(|(pattern, replacement)| (Regex::new(pattern).expect("valid redaction pattern"), replacement))
+    .collect()
+});
+
+/// Masks tokens, keys, passwords and content in `line`.
+pub fn redact(line: &str) -> String {
+    let mut out = line.to_string();
+    for (pattern, replacement) in RULES.iter() {
+        if let std::borrow::Cow::Owned(replaced) = pattern.replace_all(&out, *replacement) {
+            out = replaced;
+        }
+    }
+    out
+}
+
+/// `MakeWriter` that redacts each event as a whole before it reaches stdout.
+struct Redacted;
+
+/// Buffers one formatted event; written out when dropped.
+struct RedactedLine(Vec<u8>);
+
+impl<'a> MakeWriter<'a> for Redacted {
+    type Writer = RedactedLine;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactedLine(Vec::new())
+    }
+}
+
+impl Write for RedactedLine {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for RedactedLine {
+    fn drop(&mut self) {
+        if self.0.is_empty() {
+            return;
+        }
+        let line = redact(&String::from_utf8_lossy(&self.0));
+        let _ = std::io::stdout().lock().write_all(line.as_bytes());
+    }
+}
+
+fn valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+}
+
+/// Runs the request inside a `request` span with `request_id`, `method`, `route`
+/// (the matched pattern, never the raw path) and `status`, logs its completion,
+/// and echoes the id in `X-Request-Id`. A well-formed incoming id is kept so it
+/// can be followed across a proxy.
+pub async fn request_span(req: ServiceRequest, next: Next<impl MessageBody>) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| random_hex(16));
+    let route = req.match_pattern().unwrap_or_else(|| "unmatched".to_string());
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %req.method(),
+        route = %route,
+        status = tracing::field::Empty,
+    );
+    let start = Instant::now();
+    let mut res = next.call(req).instrument(span.clone()).await?;
+    span.record("status", res.status().as_u16());
+    span.in_scope(|| tracing::info!(latency_ms = start.elapsed().as_millis() as u64, "request finished"));
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        res.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_tokens_and_keys_in_text() {
+        assert_eq!(redact("GET /s/AbCdEfGh12345678 200"), "GET /s/[redacted] 200");
+        assert_eq!(redact("POST /api/v1/unlock/AbCdEfGh12345678"), "POST /api/v1/unlock/[redacted]");
+        assert_eq!(redact("callback?token=abc123&state=x"), "callback?token=[redacted]&state=x");
+        assert_eq!(redact("open /s/x#AbCdEfGhIjKlMnOpQrStUv"), "open /s/x#[redacted]");
+        assert_eq!(redact("key slk_AbCdEf123 revoked"), "key slk_[redacted] revoked");
+        assert_eq!(redact("Authorization: Bearer abc.def"), "Authorization: Bearer [redacted]");
+        assert_eq!(redact("cookie shredlink_session=abc123; other=1"), "cookie shredlink_session=[redacted]; other=1");
+        assert_eq!(redact("got shredlink:seal1:AAAA:BBBB="), "got shredlink:seal1:[redacted]");
+    }
+
+    #[test]
+    fn redacts_fields_in_text() {
+        assert_eq!(redact("password=hunter2 user=bob"), "password=[redacted] user=bob");
+        assert_eq!(redact(r#"text="two words" status=200"#), "text=[redacted] status=200");
+        assert_eq!(redact("Secret=abc, next"), "Secret=[redacted], next");
+        assert_eq!(redact("route=/s/{token} status=200"), "route=/s/{token} status=200");
+    }
+
+    #[test]
+    fn redacts_json_members() {
+        let line = r#"{"level":"INFO","fields":{"password":"hunter2","text":"a \"quoted\" secret","views":3,"token":42}}"#;
+        let out = redact(line);
+        assert_eq!(
+            out,
+            r#"{"level":"INFO","fields":{"password":"[redacted]","text":"[redacted]","views":3,"token":"[redacted]"}}"#
+        );
+        assert!(serde_json::from_str::<serde_json::Value>(&out).is_ok());
+    }
+
+    #[test]
+    fn redacts_json_escaped_inside_a_message() {
+        // A request body logged as a string, then written as a JSON line.
+        let body = r#"{"text":"a \"quoted\" secret","notify_lang":"en"}"#;
+        let line = serde_json::json!({ "fields": { "message": format!("body: {}", body) } }).to_string();
+        let out = redact(&line);
+        let value: serde_json::Value = serde_json::from_str(&out).expect("still valid JSON");
+        assert_eq!(
+            value["fields"]["message"],
+            r#"body: {"text":"[redacted]","notify_lang":"en"}"#
+        );
+    }
+
+    #[test]
+    fn secret_never_formats_its_value() {
+        let secret = Secret::new("hunter2".to_string());
+        assert_eq!(format!("{:?}", secret), "[redacted]");
+        assert_eq!(format!("{}", secret), "[redacted]");
+        assert_eq!(format!("{:?}", Some(secret.clone())), "Some([redacted])");
+        assert_eq!(secret.expose(), "hunter2");
+    }
+}
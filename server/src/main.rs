@@ -1,34 +1,197 @@
+use actix_web::dev::Service;
+use actix_web::middleware::from_fn;
 use actix_web::{web, App, HttpServer};
-use tracing_subscriber::EnvFilter;
+use clap::{Parser, Subcommand};
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
 
+mod accounts;
+mod admin;
+mod api;
+mod api_keys;
+mod audit;
 mod config;
 mod crypto;
 mod db;
+mod email;
 mod handlers;
+mod health;
+mod logging;
+mod metrics;
 mod middleware;
 mod models;
+mod oidc;
+mod openapi;
+mod policy;
+mod pow;
+mod shamir;
+mod webhooks;
 
 use config::Config;
-use handlers::{create_link, get_share_page, unlock_link};
-use middleware::cleanup_expired_links;
+use handlers::{get_request_page, get_share_page};
+use logging::LogFormat;
+use metrics::{metrics_handler, Metrics};
+use middleware::{cleanup_expired_links, CleanupStatus};
+
+#[derive(Parser)]
+#[command(name = "shredlink-server", version, about = "ShredLink secure text-to-link server")]
+struct Cli {
+    /// Path to a TOML config file; environment variables override its values.
+    #[arg(long, short = 'c', env = "SHREDLINK_CONFIG", global = true)]
+    config: Option<std::path::PathBuf>,
+    /// Allow the insecure built-in dev encryption key.
+    #[arg(long, global = true)]
+    dev: bool,
+    /// Log output: human-readable text or one JSON object per line.
+    #[arg(long, env = "LOG_FORMAT", value_enum, default_value_t = LogFormat::Text, global = true)]
+    log_format: LogFormat,
+    /// Validate the configuration, print it (key redacted) and exit.
+    #[arg(long)]
+    check_config: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the HTTP server (default).
+    Serve,
+    /// Apply pending database migrations and exit.
+    Migrate,
+    /// Delete expired or consumed links now.
+    PurgeExpired,
+    /// Print link counts.
+    Stats,
+    /// Delete a link by token or share URL.
+    Revoke { token: String },
+    /// Re-encrypt all stored content from ENCRYPTION_KEY to a new key.
+    RotateKey {
+        /// New base64 key; a fresh one is generated and printed if omitted.
+        #[arg(long, env = "NEW_ENCRYPTION_KEY")]
+        new_key: Option<String>,
+    },
+    /// Print a new random base64 encryption key.
+    GenKey,
+    /// Manage API keys.
+    #[command(subcommand)]
+    ApiKey(ApiKeyCommand),
+    /// Inspect the tamper-evident audit log.
+    #[command(subcommand)]
+    Audit(AuditCommand),
+}
+
+#[derive(Subcommand)]
+enum AuditCommand {
+    /// Check the hash chain and print the entry count and head hash.
+    Verify,
+    /// Print entries as JSON lines.
+    Export {
+        /// Only entries with a larger id (for incremental exports).
+        #[arg(long, default_value_t = 0)]
+        after: i64,
+    },
+}
+
+#[derive(Subcommand)]
+enum ApiKeyCommand {
+    /// Create a key and print it once; only its hash is stored.
+    Create {
+        #[arg(long)]
+        name: String,
+        /// Creates/reads per minute allowed for this key.
+        #[arg(long, default_value_t = 120)]
+        rate_limit: u32,
+        /// Comma-separated scopes: create, read, password, one_time.
+        #[arg(long, default_value = "create,read,password,one_time")]
+        scopes: String,
+        /// Text size limit in bytes for this key (default: MAX_TEXT_SIZE_BYTES).
+        #[arg(long)]
+        max_size: Option<u32>,
+        /// Verified owner address; links created with the key may set it as `notify_email`.
+        #[arg(long)]
+        email: Option<String>,
+    },
+    /// List keys with their usage and attributed links.
+    List,
+    /// Revoke a key; its links stay readable.
+    Revoke { id: i64 },
+    /// Delete every link created with a key.
+    RevokeLinks { id: i64 },
+}
 
 #[actix_web::main]
 async fn main() -> anyhow::Result<()> {
     dotenvy::dotenv().ok();
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env().add_directive("shredlink_server=info".parse()?))
-        .init();
+    let cli = Cli::parse();
+    logging::init(cli.log_format)?;
 
-    let config = Config::from_env()?;
+    if let Some(Command::GenKey) = cli.command {
+        println!("{}", crypto::generate_key());
+        return Ok(());
+    }
+    let config = Config::load(cli.config.as_deref(), cli.dev)?;
+    if cli.check_config {
+        println!("{}", config.summary());
+        println!("configuration OK");
+        return Ok(());
+    }
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve(config).await,
+        Command::Migrate => admin::migrate(&config).await,
+        Command::PurgeExpired => admin::purge_expired(&config).await,
+        Command::Stats => admin::stats(&config).await,
+        Command::Revoke { token } => admin::revoke(&config, &token).await,
+        Command::RotateKey { new_key } => admin::rotate_key(&config, new_key).await,
+        Command::ApiKey(cmd) => match cmd {
+            ApiKeyCommand::Create {
+                name,
+                rate_limit,
+                scopes,
+                max_size,
+                email,
+            } => admin::api_key_create(&config, &name, rate_limit, &scopes, max_size, email.as_deref()).await,
+            ApiKeyCommand::List => admin::api_key_list(&config).await,
+            ApiKeyCommand::Revoke { id } => admin::api_key_revoke(&config, id).await,
+            ApiKeyCommand::RevokeLinks { id } => admin::api_key_revoke_links(&config, id).await,
+        },
+        Command::Audit(cmd) => match cmd {
+            AuditCommand::Verify => admin::audit_verify(&config).await,
+            AuditCommand::Export { after } => admin::audit_export(&config, after).await,
+        },
+        Command::GenKey => unreachable!("handled before config is loaded"),
+    }
+}
+
+async fn serve(config: Config) -> anyhow::Result<()> {
     let pool = Arc::new(db::create_pool(&config).await?);
     db::run_migrations(&pool).await?;
+    db::ensure_key_canary(&pool, config.encryption_key_base64.expose()).await?;
+    if let Err(e) = db::check_key_canary(&pool, config.encryption_key_base64.expose()).await {
+        tracing::warn!("ENCRYPTION_KEY does not match stored data: {}", e);
+    }
+    if config.audit_key_base64.is_none() {
+        tracing::warn!("AUDIT_KEY is not set; the audit log is disabled. Generate one with `openssl rand -base64 32`");
+    }
+    let metrics = Metrics::new()?;
+    let cleanup_status = CleanupStatus::new();
 
     let pool_cleanup = pool.clone();
-    let cleanup_interval_secs = config.cleanup_interval_secs;
-    actix_web::rt::spawn(async move {
-        cleanup_expired_links(pool_cleanup, cleanup_interval_secs).await;
+    let config_cleanup = config.clone();
+    let metrics_cleanup = metrics.clone();
+    let cleanup_status_task = cleanup_status.clone();
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let webhook_task = config.webhooks.clone().map(|webhooks| {
+        actix_web::rt::spawn(webhooks::run_deliveries(pool.clone(), webhooks, metrics.clone(), shutdown_rx.clone()))
+    });
+    let email_task = config.email.clone().map(|email| {
+        actix_web::rt::spawn(email::run_outbox(pool.clone(), email, metrics.clone(), shutdown_rx.clone()))
     });
+    let cleanup_task = actix_web::rt::spawn(async move {
+        cleanup_expired_links(pool_cleanup, config_cleanup, metrics_cleanup, cleanup_status_task, shutdown_rx).await;
+    });
+    let pool_shutdown = pool.clone();
+    let shutdown_timeout_secs = config.shutdown_timeout_secs;
 
     let bind = format!("{}:{}", config.host, config.port);
     tracing::info!("Listening on {}", bind);
@@ -46,22 +209,164 @@ async fn main() -> anyhow::Result<()> {
                 .join("public")
         });
     let rate_limiter = middleware::CreateRateLimiter::new(config.create_rate_limit_per_minute);
-    HttpServer::new(move || {
-        App::new()
+    let key_limiter = middleware::ApiKeyRateLimiter::default();
+    let login_limiter = middleware::LoginRateLimiter::default();
+    let pow = pow::Pow::new(&config);
+    let oidc_client = match &config.oidc {
+        Some(oidc) => Some(web::Data::new(oidc::OidcClient::new(oidc.clone())?)),
+        None => None,
+    };
+    let metrics_bind = config.metrics_bind.clone();
+    let metrics_server = match &metrics_bind {
+        Some(addr) => {
+            tracing::info!("Metrics listening on {}", addr);
+            let pool = pool.clone();
+            let metrics = metrics.clone();
+            Some(
+                HttpServer::new(move || {
+                    App::new()
+                        .app_data(web::Data::new(pool.clone()))
+                        .app_data(web::Data::new(metrics.clone()))
+                        .route("/metrics", web::get().to(metrics_handler))
+                })
+                .workers(1)
+                .disable_signals()
+                .shutdown_timeout(shutdown_timeout_secs)
+                .bind(addr)?
+                .run(),
+            )
+        }
+        None => None,
+    };
+    let server = HttpServer::new(move || {
+        let metrics_mw = metrics.clone();
+        let mut app = App::new()
             .app_data(web::Data::new(pool.clone()))
             .app_data(web::Data::new(config.clone()))
             .app_data(web::Data::new(rate_limiter.clone()))
+            .app_data(web::Data::new(key_limiter.clone()))
+            .app_data(web::Data::new(login_limiter.clone()))
+            .app_data(web::Data::new(pow.clone()))
+            .app_data(web::Data::new(metrics.clone()))
+            .app_data(web::Data::new(cleanup_status.clone()))
+            .wrap_fn(move |req, srv| {
+                let metrics = metrics_mw.clone();
+                let start = std::time::Instant::now();
+                let method = req.method().to_string();
+                let route = req.match_pattern().unwrap_or_else(|| "unmatched".to_string());
+                let fut = srv.call(req);
+                async move {
+                    let res = fut.await;
+                    // An `Err` still becomes a response; count it with the status it renders as.
+                    let status = match &res {
+                        Ok(res) => res.status(),
+                        Err(err) => err.as_response_error().status_code(),
+                    };
+                    metrics.observe_request(&method, &route, status.as_u16(), start.elapsed());
+                    res
+                }
+            })
+            .wrap(from_fn(logging::request_span));
+        if let Some(oidc) = &oidc_client {
+            app = app.app_data(oidc.clone());
+        }
+        if metrics_bind.is_none() {
+            app = app.route("/metrics", web::get().to(metrics_handler));
+        }
+        app.route("/healthz", web::get().to(health::healthz))
+            .route("/readyz", web::get().to(health::readyz))
+            .configure(api::configure)
             .service(
-                web::resource("/api/create").route(web::post().to(create_link)),
+                web::scope("/api")
+                    .route("/openapi.json", web::get().to(openapi::openapi_json))
+                    .route("/docs", web::get().to(openapi::docs_page))
+                    .configure(api::configure_legacy),
             )
-            .service(web::scope("/api").route("/unlock/{token}", web::post().to(unlock_link)))
             .route("/s/{token}", web::get().to(get_share_page))
+            .route("/r/{token}", web::get().to(get_request_page))
             .service(
                 actix_files::Files::new("/", public_dir.clone()).index_file("index.html"),
             )
     })
+    .disable_signals()
+    .shutdown_timeout(shutdown_timeout_secs)
     .bind(&bind)?
-    .run()
-    .await?;
+    .run();
+
+    let server_handle = server.handle();
+    let metrics_handle = metrics_server.as_ref().map(|s| s.handle());
+    let servers = async move {
+        match metrics_server {
+            Some(metrics_server) => tokio::try_join!(server, metrics_server).map(|_| ()),
+            None => server.await,
+        }
+    };
+    tokio::pin!(servers);
+    // Draining requests, stopping the background tasks and closing the database
+    // share one deadline, counted from the signal.
+    let deadline = tokio::select! {
+        res = &mut servers => {
+            res?;
+            Instant::now() + Duration::from_secs(shutdown_timeout_secs)
+        }
+        _ = shutdown_signal() => {
+            let deadline = Instant::now() + Duration::from_secs(shutdown_timeout_secs);
+            tracing::info!("Shutdown signal received; draining in-flight requests");
+            if let Some(handle) = metrics_handle {
+                actix_web::rt::spawn(handle.stop(true));
+            }
+            actix_web::rt::spawn(server_handle.stop(true));
+            match tokio::time::timeout_at(deadline, &mut servers).await {
+                Ok(res) => res?,
+                Err(_) => tracing::warn!("in-flight requests did not finish within {}s", shutdown_timeout_secs),
+            }
+            deadline
+        }
+    };
+
+    let _ = shutdown_tx.send(true);
+    if tokio::time::timeout_at(deadline, cleanup_task).await.is_err() {
+        tracing::warn!("cleanup task did not finish within {}s", shutdown_timeout_secs);
+    }
+    if let Some(task) = webhook_task {
+        // Undelivered events stay queued and are sent after the next start.
+        if tokio::time::timeout_at(deadline, task).await.is_err() {
+            tracing::warn!("webhook task did not finish within {}s", shutdown_timeout_secs);
+        }
+    }
+    if let Some(task) = email_task {
+        // Unsent messages stay in the outbox.
+        if tokio::time::timeout_at(deadline, task).await.is_err() {
+            tracing::warn!("email task did not finish within {}s", shutdown_timeout_secs);
+        }
+    }
+    if tokio::time::timeout_at(deadline, db::close_pool(&pool_shutdown)).await.is_err() {
+        tracing::warn!("database did not close within {}s", shutdown_timeout_secs);
+    }
+    tracing::info!("Shutdown complete");
     Ok(())
 }
+
+/// Resolves on SIGINT (Ctrl+C) or, on Unix, SIGTERM.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut term) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = term.recv() => {}
+                }
+            }
+            Err(e) => {
+                tracing::warn!("cannot install SIGTERM handler: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
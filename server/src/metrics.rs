@@ -0,0 +1,182 @@
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts,
+    Registry, TextEncoder,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::db::{DbPool, IDLE_EXPIRED};
+
+/// Process-wide Prometheus metrics. Labels are limited to route patterns,
+/// methods and status codes so tokens and content never end up in a series.
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    pub links_created: IntCounter,
+    pub links_read: IntCounter,
+    pub links_unlocked: IntCounter,
+    pub unlock_failed: IntCounter,
+    pub rate_limited: IntCounter,
+    pub live_links: IntGauge,
+    request_duration: HistogramVec,
+    argon2_duration: HistogramVec,
+    pub cleanup_runs: IntCounter,
+    pub cleanup_errors: IntCounter,
+    pub cleanup_deleted: IntCounter,
+    cleanup_duration: Histogram,
+    requests: IntCounterVec,
+    pub webhooks_delivered: IntCounter,
+    pub webhooks_failed: IntCounter,
+    pub emails_sent: IntCounter,
+    pub emails_failed: IntCounter,
+}
+
+impl Metrics {
+    pub fn new() -> anyhow::Result<Self> {
+        let registry = Registry::new_custom(Some("shredlink".to_string()), None)?;
+
+        let links_created = IntCounter::new("links_created_total", "Links created")?;
+        let links_read = IntCounter::new("links_read_total", "Share pages rendered with content")?;
+        let links_unlocked = IntCounter::new("links_unlocked_total", "Successful password unlocks")?;
+        let unlock_failed = IntCounter::new("unlock_failed_total", "Unlock attempts with a wrong password")?;
+        let rate_limited = IntCounter::new("rate_limited_total", "Requests rejected by the rate limiter")?;
+        let live_links = IntGauge::new("live_links", "Links that are neither expired nor consumed")?;
+        let request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency"),
+            &["method", "route", "status"],
+        )?;
+        let requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests served"),
+            &["method", "route", "status"],
+        )?;
+        let argon2_duration = HistogramVec::new(
+            HistogramOpts::new("argon2_duration_seconds", "Time spent hashing or verifying passwords")
+                .buckets(vec![0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5]),
+            &["op"],
+        )?;
+        let cleanup_runs = IntCounter::new("cleanup_runs_total", "Cleanup passes executed")?;
+        let cleanup_errors = IntCounter::new("cleanup_errors_total", "Cleanup passes that failed")?;
+        let cleanup_deleted = IntCounter::new("cleanup_deleted_links_total", "Links deleted by cleanup")?;
+        let cleanup_duration = Histogram::with_opts(HistogramOpts::new(
+            "cleanup_duration_seconds",
+            "Duration of cleanup passes",
+        ))?;
+
+        let webhooks_delivered = IntCounter::new("webhooks_delivered_total", "Webhook events delivered")?;
+        let webhooks_failed = IntCounter::new("webhooks_failed_total", "Webhook events dropped after their last attempt")?;
+        let emails_sent = IntCounter::new("emails_sent_total", "Notification emails sent")?;
+        let emails_failed = IntCounter::new("emails_failed_total", "Notification emails dropped after their last attempt")?;
+
+        registry.register(Box::new(links_created.clone()))?;
+        registry.register(Box::new(links_read.clone()))?;
+        registry.register(Box::new(links_unlocked.clone()))?;
+        registry.register(Box::new(unlock_failed.clone()))?;
+        registry.register(Box::new(rate_limited.clone()))?;
+        registry.register(Box::new(live_links.clone()))?;
+        registry.register(Box::new(request_duration.clone()))?;
+        registry.register(Box::new(requests.clone()))?;
+        registry.register(Box::new(argon2_duration.clone()))?;
+        registry.register(Box::new(cleanup_runs.clone()))?;
+        registry.register(Box::new(cleanup_errors.clone()))?;
+        registry.register(Box::new(cleanup_deleted.clone()))?;
+        registry.register(Box::new(cleanup_duration.clone()))?;
+        registry.register(Box::new(webhooks_delivered.clone()))?;
+        registry.register(Box::new(webhooks_failed.clone()))?;
+        registry.register(Box::new(emails_sent.clone()))?;
+        registry.register(Box::new(emails_failed.clone()))?;
+
+        Ok(Self {
+            registry,
+            links_created,
+            links_read,
+            links_unlocked,
+            unlock_failed,
+            rate_limited,
+            live_links,
+            request_duration,
+            argon2_duration,
+            cleanup_runs,
+            cleanup_errors,
+            cleanup_deleted,
+            cleanup_duration,
+            requests,
+            webhooks_delivered,
+            webhooks_failed,
+            emails_sent,
+            emails_failed,
+        })
+    }
+
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        let status = status.to_string();
+        let labels = [method, route, status.as_str()];
+        self.request_duration
+            .with_label_values(&labels)
+            .observe(elapsed.as_secs_f64());
+        self.requests.with_label_values(&labels).inc();
+    }
+
+    pub fn observe_argon2(&self, op: &str, elapsed: Duration) {
+        self.argon2_duration
+            .with_label_values(&[op])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn observe_cleanup(&self, result: &anyhow::Result<u64>, elapsed: Duration) {
+        self.cleanup_runs.inc();
+        self.cleanup_duration.observe(elapsed.as_secs_f64());
+        match result {
+            Ok(deleted) => self.cleanup_deleted.inc_by(*deleted),
+            Err(_) => self.cleanup_errors.inc(),
+        }
+    }
+
+    fn render(&self) -> anyhow::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buf)?;
+        Ok(buf)
+    }
+}
+
+async fn count_live_links(pool: &DbPool) -> anyhow::Result<i64> {
+    let sql = format!(
+        "SELECT COUNT(*) FROM links WHERE (expires_at IS NULL OR datetime(expires_at) >= datetime(?1)) \
+         AND NOT (one_time_view = 1 AND view_count > 0) \
+         AND NOT (one_time_password = 1 AND password_used = 1) \
+         AND NOT (recipient_count > 0 AND view_count >= recipient_count) \
+         AND NOT {}",
+        IDLE_EXPIRED
+    );
+    let (count,): (i64,) = sqlx::query_as(&sql)
+        .bind(Utc::now().to_rfc3339())
+        .fetch_one(pool)
+        .await?;
+    Ok(count)
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "operations",
+    responses((status = 200, description = "Prometheus text exposition format", content_type = "text/plain"))
+)]
+pub async fn metrics_handler(
+    pool: web::Data<Arc<DbPool>>,
+    metrics: web::Data<Metrics>,
+) -> HttpResponse {
+    match count_live_links((pool.get_ref()).as_ref()).await {
+        Ok(n) => metrics.live_links.set(n),
+        Err(e) => tracing::warn!("metrics live links: {}", e),
+    }
+    match metrics.render() {
+        Ok(body) => HttpResponse::Ok()
+            .content_type(prometheus::TEXT_FORMAT)
+            .body(body),
+        Err(e) => {
+            tracing::warn!("metrics encode: {}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
@@ -1,12 +1,19 @@
+use governor::clock::{Clock, DefaultClock};
 use governor::{Quota, RateLimiter};
+use ipnet::IpNet;
+use std::collections::HashMap;
 use std::net::IpAddr;
 use std::num::NonZeroU32;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use crate::db::DbPool;
+use crate::audit;
+use crate::config::Config;
+use crate::db::{DbPool, IDLE_EXPIRED};
+use crate::metrics::Metrics;
 use crate::models::LinkRow;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 
 #[derive(Clone)]
 pub struct CreateRateLimiter(Arc<governor::DefaultKeyedRateLimiter<IpAddr>>);
@@ -18,57 +25,447 @@ impl CreateRateLimiter {
         Self(Arc::new(RateLimiter::keyed(quota)))
     }
 
-    pub fn check(&self, key: &IpAddr) -> bool {
-        self.0.check_key(key).is_ok()
+    /// `Err` carries how long the caller has to wait before the next request is allowed.
+    pub fn check(&self, key: &IpAddr) -> Result<(), Duration> {
+        self.0
+            .check_key(key)
+            .map_err(|not_until| not_until.wait_time_from(DefaultClock::default().now()))
     }
 }
 
+/// Per-IP limit on sign-in and sign-up attempts, separate from the create quota.
+#[derive(Clone)]
+pub struct LoginRateLimiter(CreateRateLimiter);
+
+impl Default for LoginRateLimiter {
+    fn default() -> Self {
+        Self(CreateRateLimiter::new(Self::ATTEMPTS_PER_MINUTE))
+    }
+}
+
+impl LoginRateLimiter {
+    const ATTEMPTS_PER_MINUTE: u32 = 10;
+
+    pub fn check(&self, key: &IpAddr) -> Result<(), Duration> {
+        self.0.check(key)
+    }
+}
+
+/// Per-API-key rate limits. Each key gets its own limiter with its configured
+/// quota; the limiter is rebuilt if the key's quota changes.
+#[derive(Clone, Default)]
+pub struct ApiKeyRateLimiter(Arc<Mutex<HashMap<i64, KeyLimiter>>>);
+
+/// A key's limiter together with the quota it was built for.
+type KeyLimiter = (u32, Arc<governor::DefaultDirectRateLimiter>);
+
+impl ApiKeyRateLimiter {
+    /// `Err` carries how long the caller has to wait before the next request is allowed.
+    pub fn check(&self, key_id: i64, requests_per_minute: u32) -> Result<(), Duration> {
+        let limiter = {
+            let mut map = self.0.lock().unwrap_or_else(|e| e.into_inner());
+            let entry = map.entry(key_id).or_insert_with(|| (0, Arc::new(direct_limiter(requests_per_minute))));
+            if entry.0 != requests_per_minute {
+                *entry = (requests_per_minute, Arc::new(direct_limiter(requests_per_minute)));
+            }
+            entry.1.clone()
+        };
+        limiter
+            .check()
+            .map_err(|not_until| not_until.wait_time_from(DefaultClock::default().now()))
+    }
+}
+
+fn direct_limiter(requests_per_minute: u32) -> governor::DefaultDirectRateLimiter {
+    let rpm = NonZeroU32::new(requests_per_minute.max(1)).unwrap();
+    RateLimiter::direct(Quota::per_minute(rpm))
+}
+
+/// Tracks when the cleanup task last completed a pass, for readiness checks.
+#[derive(Clone)]
+pub struct CleanupStatus {
+    started_at: i64,
+    last_run: Arc<AtomicI64>,
+}
+
+impl CleanupStatus {
+    pub fn new() -> Self {
+        Self {
+            started_at: Utc::now().timestamp(),
+            last_run: Arc::new(AtomicI64::new(0)),
+        }
+    }
+
+    fn mark_run(&self) {
+        self.last_run.store(Utc::now().timestamp(), Ordering::Relaxed);
+    }
+
+    /// Unix timestamp of the last completed pass, if any.
+    pub fn last_run(&self) -> Option<i64> {
+        match self.last_run.load(Ordering::Relaxed) {
+            0 => None,
+            t => Some(t),
+        }
+    }
+
+    /// True if a pass completed (or the process started) within `max_age_secs`.
+    pub fn is_recent(&self, max_age_secs: i64) -> bool {
+        let reference = self.last_run().unwrap_or(self.started_at);
+        Utc::now().timestamp() - reference <= max_age_secs
+    }
+}
+
+/// The client address. Forwarding headers are only believed when the direct peer
+/// is one of `TRUSTED_PROXIES`: then `X-Forwarded-For` is read from the right, and
+/// the first entry that is not itself a trusted proxy is the client. Anything to
+/// its left was sent by the client and is ignored, as is `Forwarded`.
 pub fn peer_ip(req: &actix_web::HttpRequest) -> Option<IpAddr> {
-    req.connection_info()
-        .realip_remote_addr()
-        .and_then(|s| s.split(',').next())
-        .and_then(|s| s.trim().parse().ok())
+    let peer = req.peer_addr()?.ip().to_canonical();
+    let trusted: &[IpNet] = match req.app_data::<actix_web::web::Data<Config>>() {
+        Some(config) if !config.trusted_proxies.is_empty() => &config.trusted_proxies,
+        _ => return Some(peer),
+    };
+    let is_trusted = |ip: &IpAddr| trusted.iter().any(|net| net.contains(ip));
+    let mut client = peer;
+    let entries: Vec<&str> = req
+        .headers()
+        .get_all("x-forwarded-for")
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .collect();
+    for entry in entries.iter().rev() {
+        if !is_trusted(&client) {
+            break;
+        }
+        let entry = entry.trim();
+        match entry
+            .parse::<IpAddr>()
+            .or_else(|_| entry.parse::<std::net::SocketAddr>().map(|a| a.ip()))
+        {
+            Ok(ip) => client = ip.to_canonical(),
+            // A malformed hop ends the chain at the last proxy we trust.
+            Err(_) => break,
+        }
+    }
+    Some(client)
+}
+
+/// Most networks a single link may be restricted to.
+pub const MAX_ALLOWED_CIDRS: usize = 32;
+
+/// Parses `allowed_cidrs` from a create request (CIDRs or bare addresses) into the
+/// comma-separated form stored on the link; `None` if the list is empty.
+pub fn parse_cidrs(values: &[String]) -> Result<Option<String>, String> {
+    if values.len() > MAX_ALLOWED_CIDRS {
+        return Err(format!("at most {} allowed_cidrs are allowed", MAX_ALLOWED_CIDRS));
+    }
+    let mut nets = Vec::with_capacity(values.len());
+    for value in values {
+        let value = value.trim();
+        let net = value
+            .parse::<IpNet>()
+            .or_else(|_| value.parse::<IpAddr>().map(IpNet::from))
+            .map_err(|_| format!("allowed_cidrs: {:?} is not a CIDR or IP address", value))?;
+        nets.push(net.trunc().to_string());
+    }
+    Ok((!nets.is_empty()).then(|| nets.join(",")))
+}
+
+/// Whether the client may open `row`: always for unrestricted links, otherwise only
+/// if `peer_ip` is known and inside one of its networks.
+pub fn ip_allowed(row: &LinkRow, req: &actix_web::HttpRequest) -> bool {
+    let Some(cidrs) = row.allowed_cidrs.as_deref().filter(|c| !c.is_empty()) else {
+        return true;
+    };
+    let Some(ip) = peer_ip(req).map(|ip| ip.to_canonical()) else {
+        return false;
+    };
+    cidrs
+        .split(',')
+        .filter_map(|c| c.parse::<IpNet>().ok())
+        .any(|net| net.contains(&ip))
 }
 
-pub async fn cleanup_expired_links(pool: std::sync::Arc<DbPool>, interval_secs: u64) {
-    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+/// Runs cleanup every `CLEANUP_INTERVAL_SECS` until `shutdown` flips to true. A pass
+/// that is already running when shutdown is requested is allowed to finish.
+pub async fn cleanup_expired_links(
+    pool: std::sync::Arc<DbPool>,
+    config: Config,
+    metrics: Metrics,
+    status: CleanupStatus,
+    mut shutdown: tokio::sync::watch::Receiver<bool>,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs(config.cleanup_interval_secs));
     interval.tick().await;
     loop {
-        interval.tick().await;
-        if let Err(e) = delete_expired_or_invalid(pool.as_ref()).await {
-            tracing::warn!("cleanup error: {}", e);
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.changed() => break,
+        }
+        let start = std::time::Instant::now();
+        let result = delete_expired_or_invalid(pool.as_ref(), &config).await;
+        if let Err(e) = crate::accounts::delete_expired_sessions(pool.as_ref()).await {
+            tracing::warn!("session cleanup: {}", e);
+        }
+        if let Err(e) = crate::oidc::delete_stale_logins(pool.as_ref()).await {
+            tracing::warn!("oidc login cleanup: {}", e);
+        }
+        if let Err(e) = crate::webhooks::delete_old_failures(pool.as_ref()).await {
+            tracing::warn!("webhook cleanup: {}", e);
+        }
+        if let Err(e) = crate::email::delete_old_failures(pool.as_ref()).await {
+            tracing::warn!("email outbox cleanup: {}", e);
+        }
+        if let Err(e) = crate::api::requests::delete_expired(pool.as_ref()).await {
+            tracing::warn!("secret request cleanup: {}", e);
+        }
+        metrics.observe_cleanup(&result, start.elapsed());
+        match result {
+            Ok(_) => status.mark_run(),
+            Err(e) => tracing::warn!("cleanup error: {}", e),
+        }
+        if *shutdown.borrow() {
+            break;
         }
     }
+    tracing::info!("cleanup task stopped");
 }
 
-async fn delete_expired_or_invalid(pool: &DbPool) -> anyhow::Result<u64> {
-    let now = Utc::now();
-    let r = sqlx::query(
-        "DELETE FROM links WHERE expires_at IS NOT NULL AND datetime(expires_at) < datetime(?) \
-         OR (one_time_view = 1 AND view_count > 0) \
-         OR (one_time_password = 1 AND password_used = 1)",
-    )
-    .bind(now.to_rfc3339())
-    .execute(pool)
-    .await?;
-    let deleted = r.rows_affected();
+/// Deletes expired and consumed links, first queueing `link.deleted` webhook
+/// events and "expired unread" emails for them when those are enabled, and logs
+/// each deletion in the audit log.
+pub async fn delete_expired_or_invalid(pool: &DbPool, config: &Config) -> anyhow::Result<u64> {
+    let now_at = Utc::now();
+    let now = now_at.to_rfc3339();
+    let expired = format!("(expires_at IS NOT NULL AND datetime(expires_at) < datetime(?1)) OR {}", IDLE_EXPIRED);
+    let consumed = "(one_time_view = 1 AND view_count > 0) OR (one_time_password = 1 AND password_used = 1) \
+        OR (recipient_count > 0 AND view_count >= recipient_count)";
+    let mut tx = pool.begin().await?;
+    if let Some(webhooks) = &config.webhooks {
+        crate::webhooks::enqueue_deletions(&mut tx, webhooks, &expired, consumed, &now).await?;
+    }
+    if config.email.is_some() {
+        crate::email::enqueue_expired_unread(&mut tx, config, &expired, now_at).await?;
+    }
+    let sql = format!(
+        "DELETE FROM links WHERE {expired} OR {consumed} \
+         RETURNING public_id, CASE WHEN {expired} THEN 'expired' ELSE 'consumed' END",
+    );
+    let removed: Vec<(Option<String>, String)> = sqlx::query_as(&sql).bind(&now).fetch_all(&mut *tx).await?;
+    for (link_id, reason) in &removed {
+        let record = audit::Record::new(audit::Event::Deleted, link_id.as_deref())
+            .actor("cleanup")
+            .detail(reason);
+        audit::append(&mut tx, config, &record).await?;
+    }
+    tx.commit().await?;
+    let deleted = removed.len() as u64;
     if deleted > 0 {
         tracing::info!("cleanup deleted {} expired/invalid links", deleted);
     }
     Ok(deleted)
 }
 
-pub fn is_link_expired_or_consumed(row: &LinkRow) -> bool {
-    if let Some(exp) = row.expires_at {
-        if exp < Utc::now() {
-            return true;
+/// Whether a link can be opened right now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkState {
+    Available,
+    /// Scheduled with `not_before`; readable from the given time.
+    NotYetAvailable(DateTime<Utc>),
+    Expired,
+    /// One-time link that has already been viewed or unlocked, or a multi-recipient
+    /// link that every recipient has read.
+    Consumed,
+}
+
+pub fn link_state(row: &LinkRow) -> LinkState {
+    let now = Utc::now();
+    if row.expires_at.is_some_and(|exp| exp < now) {
+        return LinkState::Expired;
+    }
+    if let Some(idle) = row.idle_timeout_minutes {
+        let since = row.last_accessed_at.or(row.not_before).unwrap_or(row.created_at);
+        if since + chrono::Duration::minutes(idle) < now {
+            return LinkState::Expired;
         }
     }
-    if row.one_time_view != 0 && row.view_count > 0 {
-        return true;
+    if (row.one_time_view != 0 && row.view_count > 0)
+        || (row.one_time_password != 0 && row.password_used != 0)
+        || (row.recipient_count > 0 && row.view_count >= row.recipient_count)
+    {
+        return LinkState::Consumed;
     }
-    if row.one_time_password != 0 && row.password_used != 0 {
-        return true;
+    match row.not_before {
+        Some(at) if at > now => LinkState::NotYetAvailable(at),
+        _ => LinkState::Available,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+    use actix_web::web::Data;
+
+    fn request(peer: &str, forwarded_for: &[&str], trusted: &str) -> actix_web::HttpRequest {
+        let mut config = Config::for_tests();
+        config.trusted_proxies = trusted.split(',').filter(|s| !s.is_empty()).map(|s| s.parse().unwrap()).collect();
+        let mut req = TestRequest::default().peer_addr(peer.parse().unwrap()).app_data(Data::new(config));
+        for value in forwarded_for {
+            req = req.append_header(("X-Forwarded-For", *value));
+        }
+        req.to_http_request()
+    }
+
+    fn ip(s: &str) -> Option<IpAddr> {
+        Some(s.parse().unwrap())
+    }
+
+    #[test]
+    fn forwarded_for_is_ignored_without_trusted_proxies() {
+        assert_eq!(peer_ip(&request("203.0.113.7:4000", &["198.51.100.1"], "")), ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn forwarded_for_is_ignored_from_an_untrusted_peer() {
+        let req = request("203.0.113.7:4000", &["198.51.100.1"], "10.0.0.0/8");
+        assert_eq!(peer_ip(&req), ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn forwarded_for_is_walked_right_to_left_past_trusted_hops() {
+        let req = request("10.0.0.1:4000", &["198.51.100.1, 10.0.0.3", "10.0.0.2"], "10.0.0.0/8");
+        assert_eq!(peer_ip(&req), ip("198.51.100.1"));
+    }
+
+    #[test]
+    fn spoofed_leftmost_entry_is_ignored() {
+        // The client sent "1.2.3.4" itself; the proxy appended the real address.
+        let req = request("10.0.0.1:4000", &["1.2.3.4, 198.51.100.1"], "10.0.0.0/8");
+        assert_eq!(peer_ip(&req), ip("198.51.100.1"));
+    }
+
+    #[test]
+    fn malformed_hop_stops_at_the_last_trusted_proxy() {
+        let req = request("10.0.0.1:4000", &["198.51.100.1, garbage, 10.0.0.2"], "10.0.0.0/8");
+        assert_eq!(peer_ip(&req), ip("10.0.0.2"));
+    }
+
+    #[test]
+    fn forwarded_entries_may_carry_ports_and_mapped_addresses() {
+        let req = request("10.0.0.1:4000", &["[::ffff:198.51.100.1]:5000"], "10.0.0.0/8");
+        assert_eq!(peer_ip(&req), ip("198.51.100.1"));
+        let req = request("[::ffff:10.0.0.1]:4000", &["198.51.100.1"], "10.0.0.0/8");
+        assert_eq!(peer_ip(&req), ip("198.51.100.1"));
+    }
+
+    #[test]
+    fn parse_cidrs_normalizes_networks_and_addresses() {
+        let values = ["192.168.1.77/24".to_string(), " 2001:db8::1 ".to_string()];
+        assert_eq!(parse_cidrs(&values).unwrap().as_deref(), Some("192.168.1.0/24,2001:db8::1/128"));
+        assert_eq!(parse_cidrs(&[]).unwrap(), None);
+    }
+
+    #[test]
+    fn parse_cidrs_rejects_malformed_networks() {
+        for bad in ["10.0.0.0/33", "10.0.0", "example.com", "", "::1/129"] {
+            assert!(parse_cidrs(&[bad.to_string()]).is_err(), "{:?} was accepted", bad);
+        }
+        let many = vec!["10.0.0.1".to_string(); MAX_ALLOWED_CIDRS + 1];
+        assert!(parse_cidrs(&many).is_err());
+    }
+
+    #[test]
+    fn ip_allowed_checks_the_links_networks() {
+        let mut link = LinkRow::for_tests();
+        assert!(ip_allowed(&link, &request("203.0.113.7:4000", &[], "")));
+        link.allowed_cidrs = parse_cidrs(&["198.51.100.0/24".to_string()]).unwrap();
+        assert!(ip_allowed(&link, &request("198.51.100.20:4000", &[], "")));
+        assert!(!ip_allowed(&link, &request("203.0.113.7:4000", &[], "")));
+        // A forwarded address only counts when it comes through a trusted proxy.
+        assert!(!ip_allowed(&link, &request("203.0.113.7:4000", &["198.51.100.20"], "")));
+        assert!(ip_allowed(&link, &request("10.0.0.1:4000", &["198.51.100.20"], "10.0.0.0/8")));
+    }
+
+    #[test]
+    fn ip_allowed_matches_ipv4_mapped_ipv6_peers() {
+        let mut link = LinkRow::for_tests();
+        link.allowed_cidrs = parse_cidrs(&["198.51.100.0/24".to_string()]).unwrap();
+        assert!(ip_allowed(&link, &request("[::ffff:198.51.100.20]:4000", &[], "")));
+        assert!(!ip_allowed(&link, &request("[::ffff:203.0.113.7]:4000", &[], "")));
+    }
+
+    #[test]
+    fn link_state_before_go_live_is_not_yet_available() {
+        let mut link = LinkRow::for_tests();
+        let at = Utc::now() + chrono::Duration::minutes(5);
+        link.not_before = Some(at);
+        link.expires_at = Some(at + chrono::Duration::minutes(5));
+        assert_eq!(link_state(&link), LinkState::NotYetAvailable(at));
+        link.not_before = Some(Utc::now() - chrono::Duration::seconds(1));
+        assert_eq!(link_state(&link), LinkState::Available);
+    }
+
+    #[test]
+    fn link_state_after_expiry_is_expired() {
+        let mut link = LinkRow::for_tests();
+        link.expires_at = Some(Utc::now() - chrono::Duration::seconds(1));
+        assert_eq!(link_state(&link), LinkState::Expired);
+        link.expires_at = Some(Utc::now() + chrono::Duration::minutes(1));
+        assert_eq!(link_state(&link), LinkState::Available);
+    }
+
+    #[test]
+    fn idle_timeout_runs_from_the_last_view() {
+        let mut link = LinkRow::for_tests();
+        link.created_at = Utc::now() - chrono::Duration::hours(1);
+        link.idle_timeout_minutes = Some(5);
+        assert_eq!(link_state(&link), LinkState::Expired);
+        link.last_accessed_at = Some(Utc::now() - chrono::Duration::minutes(1));
+        assert_eq!(link_state(&link), LinkState::Available);
+        link.last_accessed_at = Some(Utc::now() - chrono::Duration::minutes(6));
+        assert_eq!(link_state(&link), LinkState::Expired);
+    }
+
+    #[test]
+    fn idle_timeout_of_an_unread_link_runs_from_go_live() {
+        let mut link = LinkRow::for_tests();
+        link.created_at = Utc::now() - chrono::Duration::hours(1);
+        link.idle_timeout_minutes = Some(5);
+        link.not_before = Some(Utc::now() - chrono::Duration::minutes(1));
+        assert_eq!(link_state(&link), LinkState::Available);
+        link.not_before = Some(Utc::now() - chrono::Duration::minutes(6));
+        assert_eq!(link_state(&link), LinkState::Expired);
+    }
+
+    #[actix_web::test]
+    async fn cleanup_and_reads_honor_the_idle_timeout() {
+        let pool = crate::db::test_pool().await;
+        let config = Config::for_tests();
+        let hour_ago = (Utc::now() - chrono::Duration::hours(1)).to_rfc3339();
+        let insert = |token: &'static str, last_accessed_at: Option<String>| {
+            sqlx::query("INSERT INTO links (token, encrypted_text, idle_timeout_minutes, last_accessed_at, created_at) VALUES (?, 'x', 5, ?, ?)")
+                .bind(token)
+                .bind(last_accessed_at)
+                .bind(&hour_ago)
+        };
+        insert("idle", None).execute(&pool).await.unwrap();
+        insert("read", Some((Utc::now() - chrono::Duration::minutes(1)).to_rfc3339())).execute(&pool).await.unwrap();
+        let reread = insert("reread", Some((Utc::now() - chrono::Duration::minutes(4)).to_rfc3339()))
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+
+        // A read resets the timer.
+        let row: LinkRow = sqlx::query_as(crate::db::SELECT_LINK_BY_ID).bind(reread).fetch_one(&pool).await.unwrap();
+        let record = audit::Record::for_link(audit::Event::Viewed, &row, None);
+        crate::api::v1::count_view(&pool, &config, &row, false, &record).await.unwrap();
+        let row: LinkRow = sqlx::query_as(crate::db::SELECT_LINK_BY_ID).bind(reread).fetch_one(&pool).await.unwrap();
+        assert!(row.last_accessed_at.is_some_and(|t| Utc::now() - t < chrono::Duration::seconds(5)));
+
+        assert_eq!(delete_expired_or_invalid(&pool, &config).await.unwrap(), 1);
+        let left: Vec<(String,)> = sqlx::query_as("SELECT token FROM links ORDER BY token").fetch_all(&pool).await.unwrap();
+        assert_eq!(left, vec![("read".to_string(),), ("reread".to_string(),)]);
     }
-    false
 }
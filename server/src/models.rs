@@ -1,53 +1,105 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use sqlx::FromRow;
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
 
 #[derive(Debug, FromRow)]
 #[allow(dead_code)]
 pub struct LinkRow {
     pub id: i64,
     pub token: String,
+    /// Shareable id used in webhook events instead of the token.
+    pub public_id: Option<String>,
     pub encrypted_text: String,
     pub password_hash: Option<String>,
     pub expires_at: Option<DateTime<Utc>>,
+    pub not_before: Option<DateTime<Utc>>,
+    pub idle_timeout_minutes: Option<i64>,
+    pub last_accessed_at: Option<DateTime<Utc>>,
+    pub webhook_url: Option<String>,
+    /// Signs events sent to `webhook_url`; `None` for `WEBHOOK_SECRET`.
+    pub webhook_secret: Option<String>,
+    /// Owner address for "opened" and "expired unread" emails.
+    pub notify_email: Option<String>,
+    pub notify_lang: Option<String>,
     pub one_time_view: i32,
     pub one_time_password: i32,
     pub view_count: i64,
     pub password_used: i32,
+    /// Number of named recipients of a multi-recipient link; 0 for ordinary links.
+    pub recipient_count: i64,
+    /// Comma-separated networks the link may be opened from; `None` for anywhere.
+    pub allowed_cidrs: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct CreateRequest {
-    pub text: String,
-    pub password: Option<String>,
-    #[serde(default)]
-    pub expire_minutes: Option<u32>,
-    #[serde(default)]
-    pub expire_hours: Option<u32>,
-    #[serde(default)]
-    pub one_time_view: bool,
-    #[serde(default)]
-    pub one_time_password: bool,
+#[cfg(test)]
+impl LinkRow {
+    /// An unrestricted link created now, for tests.
+    pub fn for_tests() -> Self {
+        Self {
+            id: 1,
+            token: "t".to_string(),
+            public_id: None,
+            encrypted_text: String::new(),
+            password_hash: None,
+            expires_at: None,
+            not_before: None,
+            idle_timeout_minutes: None,
+            last_accessed_at: None,
+            webhook_url: None,
+            webhook_secret: None,
+            notify_email: None,
+            notify_lang: None,
+            one_time_view: 0,
+            one_time_password: 0,
+            view_count: 0,
+            password_used: 0,
+            recipient_count: 0,
+            allowed_cidrs: None,
+            created_at: Utc::now(),
+        }
+    }
 }
 
-#[derive(Debug, Serialize)]
-pub struct CreateResponse {
-    pub token: String,
-    pub url: String,
+/// One named recipient's one-time token for a multi-recipient link.
+#[derive(Debug, FromRow)]
+pub struct LinkRecipientRow {
+    pub id: i64,
+    pub link_id: i64,
+    pub label: String,
+    pub viewed_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct UnlockRequest {
-    pub password: String,
+#[derive(Debug, FromRow)]
+pub struct SecretRequestRow {
+    pub id: i64,
+    pub public_id: String,
+    pub token: String,
+    pub note: Option<String>,
+    /// Recipient key (`slpub_...`) the answer is sealed to.
+    pub public_key: Option<String>,
+    pub encrypted_text: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    pub submitted_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct UnlockResponse {
-    pub text: String,
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HealthCheck {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HealthResponse {
+    /// `ok` (liveness), `ready` or `not_ready`.
+    #[schema(value_type = String)]
+    pub status: &'static str,
+    /// Per-dependency results keyed by check name (`database`, `migrations`, `encryption_key`, `cleanup`).
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    #[schema(value_type = BTreeMap<String, HealthCheck>)]
+    pub checks: BTreeMap<&'static str, HealthCheck>,
 }
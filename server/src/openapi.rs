@@ -0,0 +1,136 @@
+use actix_web::HttpResponse;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use shredlink_types::v1::{
+    AccountOptions, AccountRequest, AccountResponse, CreatePolicy, EmailConfirmRequest, EmailRequest, CreateRequest, CreateResponse, CreateSecretRequest, ErrorResponse,
+    LinkStatus, LinkSummary, PowChallenge, PublicKeyRequest, RecipientLink, RecipientStatus, SecretRequestCreated, SecretRequestInfo, SecretRequestStatus, SecretRequestSummary, SplitRequest,
+    SplitResponse, SubmitSecretRequest, UnlockRequest, UnlockResponse,
+};
+
+use crate::api::{account, pow, recipients, requests, split, v1};
+use crate::models::{HealthCheck, HealthResponse};
+use crate::{handlers, health, metrics};
+
+/// OpenAPI 3 document built from the `#[utoipa::path]` annotations on the handlers.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "ShredLink API",
+        description = "Share sensitive text via short-lived, encrypted links.\n\n\
+            The JSON API is versioned under `/api/v1`. The unversioned `/api/create`, \
+            `/api/read/{token}` and `/api/unlock/{token}` routes are deprecated aliases of v1 \
+            and respond with a `Deprecation` header."
+    ),
+    paths(
+        v1::get_policy,
+        v1::create_link,
+        v1::read_link,
+        v1::unlock_link,
+        split::split_secret,
+        recipients::list_recipients,
+        pow::get_challenge,
+        account::register,
+        account::login,
+        account::logout,
+        account::options,
+        account::oidc_login,
+        account::oidc_callback,
+        account::me,
+        account::set_public_key,
+        account::delete_public_key,
+        account::set_email,
+        account::confirm_email,
+        account::list_links,
+        account::revoke_link,
+        requests::create_request,
+        requests::list_requests,
+        requests::read_request,
+        requests::delete_request,
+        requests::request_info,
+        requests::submit_request,
+        handlers::get_share_page,
+        handlers::get_request_page,
+        health::healthz,
+        health::readyz,
+        metrics::metrics_handler,
+    ),
+    components(schemas(
+        CreatePolicy,
+        CreateRequest,
+        CreateResponse,
+        RecipientLink,
+        RecipientStatus,
+        SplitRequest,
+        SplitResponse,
+        PowChallenge,
+        UnlockRequest,
+        UnlockResponse,
+        ErrorResponse,
+        AccountRequest,
+        AccountResponse,
+        AccountOptions,
+        PublicKeyRequest,
+        EmailRequest,
+        EmailConfirmRequest,
+        LinkStatus,
+        LinkSummary,
+        CreateSecretRequest,
+        SecretRequestCreated,
+        SecretRequestStatus,
+        SecretRequestSummary,
+        SecretRequestInfo,
+        SubmitSecretRequest,
+        HealthCheck,
+        HealthResponse,
+    )),
+    modifiers(&ApiKeyAuth),
+    tags(
+        (name = "links", description = "Create and read links"),
+        (name = "account", description = "Optional accounts and the dashboard of a user's links (cookie session)"),
+        (name = "requests", description = "Secret requests: ask someone to send you a secret (cookie session or API key)"),
+        (name = "operations", description = "Probes and metrics"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Registers the optional `Authorization: Bearer slk_...` API key scheme.
+struct ApiKeyAuth;
+
+impl Modify for ApiKeyAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some("API key created with `shredlink-server api-key create`"))
+                    .build(),
+            ),
+        );
+    }
+}
+
+pub async fn openapi_json() -> HttpResponse {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+/// Interactive docs (Swagger UI from a CDN, like the Tailwind build on the other pages).
+pub async fn docs_page() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(
+            r#"<!DOCTYPE html><html lang="en"><head><meta charset="utf-8"><meta name="viewport" content="width=device-width,initial-scale=1"><title>ShredLink – API</title>
+<link rel="stylesheet" href="https://cdn.jsdelivr.net/npm/swagger-ui-dist@5/swagger-ui.css">
+</head><body>
+<div id="swagger-ui"></div>
+<script src="https://cdn.jsdelivr.net/npm/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+<script>
+window.onload = function () {
+  window.ui = SwaggerUIBundle({ url: '/api/openapi.json', dom_id: '#swagger-ui', deepLinking: true });
+};
+</script>
+</body></html>"#,
+        )
+}
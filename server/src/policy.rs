@@ -0,0 +1,129 @@
+//! Organization-wide rules every new link must satisfy, configured with the
+//! `POLICY_*` settings. With the defaults nothing is enforced.
+
+use thiserror::Error;
+
+use shredlink_types::v1::{CreatePolicy, CreateRequest};
+
+#[derive(Clone, Debug, Default)]
+pub struct CreationPolicy {
+    /// Applied when a request asks for no expiry.
+    pub default_ttl_minutes: Option<u32>,
+    /// Longest allowed lifetime; links without expiry are rejected when set.
+    pub max_ttl_minutes: Option<u32>,
+    pub require_password: bool,
+    /// Every link must be one-time view or one-time password (multi-recipient
+    /// links count as one-time).
+    pub require_one_time: bool,
+    pub min_password_length: usize,
+    /// How many of lowercase, uppercase, digits and symbols a password must mix (0-4).
+    pub min_password_classes: u8,
+    /// Size limit for signed-in users instead of `MAX_TEXT_SIZE_BYTES`.
+    pub user_max_text_size_bytes: Option<usize>,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PolicyViolation {
+    #[error("text exceeds max size ({limit} bytes)")]
+    TextTooLarge { limit: usize },
+    #[error("links must expire within {max} minutes")]
+    ExpiryRequired { max: u32 },
+    #[error("expiry of {requested} minutes exceeds the maximum of {max} minutes")]
+    TtlTooLong { requested: i64, max: u32 },
+    #[error("links must be password-protected")]
+    PasswordRequired,
+    #[error("links must be one-time (one_time_view or one_time_password)")]
+    OneTimeRequired,
+    #[error("password must be at least {min} characters")]
+    PasswordTooShort { min: usize },
+    #[error("password must mix at least {min} of: lowercase, uppercase, digits, symbols")]
+    PasswordTooWeak { min: u8 },
+}
+
+fn password_classes(password: &str) -> u8 {
+    let has = |f: fn(&char) -> bool| password.chars().any(|c| f(&c)) as u8;
+    has(char::is_ascii_lowercase)
+        + has(char::is_ascii_uppercase)
+        + has(char::is_ascii_digit)
+        + has(|c| !c.is_ascii_alphanumeric())
+}
+
+impl CreationPolicy {
+    /// Checks `req` against the policy. `max_text_size` is the limit for this
+    /// caller (anonymous, user or API key); `requested_ttl` is the lifetime the
+    /// request asks for in minutes, counted from when the link becomes readable.
+    /// Returns the lifetime to apply, or `None` for a link that never expires.
+    pub fn check(&self, req: &CreateRequest, max_text_size: usize, requested_ttl: Option<i64>) -> Result<Option<i64>, PolicyViolation> {
+        if req.text.len() > max_text_size {
+            return Err(PolicyViolation::TextTooLarge { limit: max_text_size });
+        }
+
+        let ttl = requested_ttl.or(self.default_ttl_minutes.map(i64::from));
+        if let Some(max) = self.max_ttl_minutes {
+            match ttl {
+                None => return Err(PolicyViolation::ExpiryRequired { max }),
+                Some(t) if t > max as i64 => return Err(PolicyViolation::TtlTooLong { requested: t, max }),
+                Some(_) => {}
+            }
+        }
+
+        match req.password.as_deref().filter(|p| !p.is_empty()) {
+            None if self.require_password => return Err(PolicyViolation::PasswordRequired),
+            None => {}
+            Some(p) => {
+                if p.chars().count() < self.min_password_length {
+                    return Err(PolicyViolation::PasswordTooShort {
+                        min: self.min_password_length,
+                    });
+                }
+                if password_classes(p) < self.min_password_classes {
+                    return Err(PolicyViolation::PasswordTooWeak {
+                        min: self.min_password_classes,
+                    });
+                }
+            }
+        }
+        // Each recipient's token of a multi-recipient link is one-time as well.
+        if self.require_one_time && !(req.one_time_view || req.one_time_password || !req.recipient_labels.is_empty()) {
+            return Err(PolicyViolation::OneTimeRequired);
+        }
+        Ok(ttl)
+    }
+
+    /// The rules as published to clients.
+    pub fn describe(&self, max_text_size_bytes: usize) -> CreatePolicy {
+        CreatePolicy {
+            default_ttl_minutes: self.default_ttl_minutes,
+            max_ttl_minutes: self.max_ttl_minutes,
+            require_password: self.require_password,
+            require_one_time: self.require_one_time,
+            min_password_length: self.min_password_length,
+            min_password_classes: self.min_password_classes,
+            max_text_size_bytes,
+        }
+    }
+
+    /// Consistency checks for `Config::validate`.
+    pub fn validate(&self, errors: &mut Vec<String>) {
+        if self.max_ttl_minutes == Some(0) {
+            errors.push("POLICY_MAX_TTL_MINUTES must be at least 1".to_string());
+        }
+        if self.default_ttl_minutes == Some(0) {
+            errors.push("POLICY_DEFAULT_TTL_MINUTES must be at least 1".to_string());
+        }
+        if let (Some(default), Some(max)) = (self.default_ttl_minutes, self.max_ttl_minutes) {
+            if default > max {
+                errors.push(format!(
+                    "POLICY_DEFAULT_TTL_MINUTES ({}) must not exceed POLICY_MAX_TTL_MINUTES ({})",
+                    default, max
+                ));
+            }
+        }
+        if self.min_password_classes > 4 {
+            errors.push("POLICY_MIN_PASSWORD_CLASSES must be between 0 and 4".to_string());
+        }
+        if self.user_max_text_size_bytes == Some(0) {
+            errors.push("POLICY_USER_MAX_TEXT_SIZE_BYTES must be at least 1".to_string());
+        }
+    }
+}
@@ -0,0 +1,452 @@
+//! Optional proof-of-work for anonymous `create`, `split` and `unlock` requests.
+//! Challenges are stateless: `{action}.{difficulty}.{expires}.{nonce}.{mac}`, where
+//! the HMAC also covers the client's address, so a challenge cannot be replayed
+//! from elsewhere or have its difficulty lowered. Only used nonces are remembered
+//! (in memory, until they expire). Difficulty rises while anonymous traffic is
+//! above `POW_LOAD_THRESHOLD_PER_MINUTE` and after failed unlocks from an address;
+//! a raise applies to newly issued challenges, not to ones already being solved.
+
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpResponse};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+
+use crate::accounts;
+use crate::api_keys;
+use crate::config::{Config, PowConfig};
+use crate::crypto::{random_hex, to_hex};
+use crate::db::DbPool;
+use crate::middleware::peer_ip;
+use shredlink_types::v1::PowChallenge;
+
+/// Request header carrying `{challenge}:{solution}`.
+pub const POW_HEADER: &str = "X-ShredLink-PoW";
+/// How long a failed unlock keeps raising the difficulty for its address.
+const FAILURE_WINDOW_SECS: i64 = 15 * 60;
+const MAX_SOLUTION_LEN: usize = 64;
+/// Size at which the in-memory maps are swept for stale entries.
+const SWEEP_AT: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Create,
+    Unlock,
+}
+
+impl Action {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Create => "create",
+            Self::Unlock => "unlock",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "create" => Some(Self::Create),
+            "unlock" => Some(Self::Unlock),
+            _ => None,
+        }
+    }
+}
+
+/// Shared proof-of-work state; a no-op unless `POW_ENABLED` is set.
+#[derive(Clone)]
+pub struct Pow(Option<Arc<Inner>>);
+
+struct Inner {
+    config: PowConfig,
+    key: Vec<u8>,
+    /// Nonces already spent, with the expiry of their challenge.
+    used: Mutex<HashMap<String, i64>>,
+    /// Anonymous guarded requests: (current minute, its count, previous minute's count).
+    load: Mutex<(i64, u32, u32)>,
+    /// Failed unlocks per address: (count, time of the latest).
+    failures: Mutex<HashMap<IpAddr, (u32, i64)>>,
+}
+
+impl Pow {
+    pub fn new(config: &Config) -> Self {
+        Self(config.pow.clone().map(|pow| {
+            // Without POW_SECRET the key is derived from ENCRYPTION_KEY so challenges
+            // survive restarts and work across instances sharing that key.
+            let key = match &pow.secret {
+                Some(secret) => secret.expose().as_bytes().to_vec(),
+                None => hmac_sha256(config.encryption_key_base64.expose().trim().as_bytes(), b"shredlink pow v1").to_vec(),
+            };
+            Arc::new(Inner {
+                config: pow,
+                key,
+                used: Mutex::new(HashMap::new()),
+                load: Mutex::new((0, 0, 0)),
+                failures: Mutex::new(HashMap::new()),
+            })
+        }))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// A fresh challenge at the difficulty currently required from `ip`, or `None`
+    /// when proof-of-work is disabled.
+    pub fn issue(&self, action: Action, ip: Option<IpAddr>) -> Option<PowChallenge> {
+        let inner = self.0.as_ref()?;
+        let difficulty = inner.difficulty(ip);
+        let expires_at = Utc::now().timestamp() + inner.config.challenge_ttl_secs as i64;
+        let payload = format!("{}.{}.{}.{}", action.as_str(), difficulty, expires_at, random_hex(16));
+        let mac = to_hex(&inner.sign(&payload, ip));
+        Some(PowChallenge {
+            error: None,
+            challenge: format!("{}.{}", payload, mac),
+            difficulty,
+            expires_at,
+        })
+    }
+
+    /// Checks a `{challenge}:{solution}` header value against the difficulty the
+    /// challenge was issued with, and spends its nonce.
+    pub fn verify(&self, value: &str, action: Action, ip: Option<IpAddr>) -> Result<(), &'static str> {
+        let Some(inner) = &self.0 else {
+            return Ok(());
+        };
+        let invalid = "Invalid proof of work";
+        let (challenge, solution) = value.trim().rsplit_once(':').ok_or(invalid)?;
+        if solution.is_empty() || solution.len() > MAX_SOLUTION_LEN {
+            return Err(invalid);
+        }
+        let parts: Vec<&str> = challenge.split('.').collect();
+        let [act, difficulty, expires_at, nonce, mac] = parts[..] else {
+            return Err(invalid);
+        };
+        let difficulty: u8 = difficulty.parse().map_err(|_| invalid)?;
+        let expires_at: i64 = expires_at.parse().map_err(|_| invalid)?;
+        let mac = from_hex(mac).ok_or(invalid)?;
+        let (payload, _) = challenge.rsplit_once('.').ok_or(invalid)?;
+        inner.mac(payload, ip).verify_slice(&mac).map_err(|_| invalid)?;
+        if Action::parse(act) != Some(action) {
+            return Err("Proof of work was issued for another action");
+        }
+        let now = Utc::now().timestamp();
+        if expires_at < now {
+            return Err("Proof-of-work challenge expired");
+        }
+        if leading_zero_bits(&Sha256::digest(value.trim().as_bytes())) < u32::from(difficulty) {
+            return Err(invalid);
+        }
+        let mut used = inner.used.lock().unwrap_or_else(|e| e.into_inner());
+        if used.len() >= SWEEP_AT {
+            used.retain(|_, exp| *exp >= now);
+        }
+        if used.insert(nonce.to_string(), expires_at).is_some() {
+            return Err("Proof of work was already used");
+        }
+        Ok(())
+    }
+
+    fn record_request(&self) {
+        if let Some(inner) = &self.0 {
+            let minute = Utc::now().timestamp() / 60;
+            let mut load = inner.load.lock().unwrap_or_else(|e| e.into_inner());
+            if load.0 != minute {
+                let previous = if load.0 == minute - 1 { load.1 } else { 0 };
+                *load = (minute, 0, previous);
+            }
+            load.1 = load.1.saturating_add(1);
+        }
+    }
+
+    fn record_failure(&self, ip: Option<IpAddr>) {
+        let (Some(inner), Some(ip)) = (&self.0, ip) else {
+            return;
+        };
+        let now = Utc::now().timestamp();
+        let mut failures = inner.failures.lock().unwrap_or_else(|e| e.into_inner());
+        if failures.len() >= SWEEP_AT {
+            failures.retain(|_, (_, at)| now - *at < FAILURE_WINDOW_SECS);
+        }
+        let entry = failures.entry(ip).or_insert((0, now));
+        if now - entry.1 >= FAILURE_WINDOW_SECS {
+            entry.0 = 0;
+        }
+        *entry = (entry.0.saturating_add(1), now);
+    }
+}
+
+impl Inner {
+    /// Base difficulty, plus one bit for each doubling of anonymous traffic over the
+    /// threshold and one per recent failed unlock from `ip`, capped at the maximum.
+    fn difficulty(&self, ip: Option<IpAddr>) -> u8 {
+        let mut extra = 0u32;
+        let minute = Utc::now().timestamp() / 60;
+        let rate = {
+            let load = self.load.lock().unwrap_or_else(|e| e.into_inner());
+            match minute - load.0 {
+                0 => load.1.max(load.2),
+                1 => load.1,
+                _ => 0,
+            }
+        };
+        let mut threshold = self.config.load_threshold_per_minute;
+        while rate > threshold && extra < 32 {
+            extra += 1;
+            threshold = threshold.saturating_mul(2);
+        }
+        if let Some(ip) = ip {
+            let failures = self.failures.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some((count, at)) = failures.get(&ip) {
+                if Utc::now().timestamp() - at < FAILURE_WINDOW_SECS {
+                    extra = extra.saturating_add(*count);
+                }
+            }
+        }
+        let difficulty = u32::from(self.config.base_difficulty).saturating_add(extra);
+        difficulty.min(u32::from(self.config.max_difficulty)) as u8
+    }
+
+    fn mac(&self, payload: &str, ip: Option<IpAddr>) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(payload.as_bytes());
+        mac.update(b"|");
+        mac.update(ip.map(|ip| ip.to_canonical().to_string()).unwrap_or_default().as_bytes());
+        mac
+    }
+
+    fn sign(&self, payload: &str, ip: Option<IpAddr>) -> Vec<u8> {
+        self.mac(payload, ip).finalize().into_bytes().to_vec()
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.is_ascii() {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut bits = 0;
+    for &byte in hash {
+        if byte != 0 {
+            return bits + byte.leading_zeros();
+        }
+        bits += 8;
+    }
+    bits
+}
+
+/// True if the request carries credentials that exempt it: an active API key or a
+/// live session. Merely sending an `Authorization` header is not enough.
+async fn is_authenticated(req: &ServiceRequest) -> bool {
+    let Some(pool) = req.app_data::<web::Data<Arc<DbPool>>>() else {
+        return false;
+    };
+    if let Some(token) = api_keys::bearer_token(req.request()) {
+        return matches!(api_keys::find_active(pool.get_ref(), token).await, Ok(Some(_)));
+    }
+    if req.cookie(accounts::SESSION_COOKIE).is_none() {
+        return false;
+    }
+    matches!(accounts::session_user(pool.get_ref(), req.request()).await, Ok(Some(_)))
+}
+
+async fn guard(action: Action, req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse<BoxBody>, Error> {
+    let pow = match req.app_data::<web::Data<Pow>>() {
+        Some(pow) if pow.is_enabled() => pow.clone(),
+        _ => return Ok(next.call(req).await?.map_into_boxed_body()),
+    };
+    if is_authenticated(&req).await {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+    let ip = peer_ip(req.request()).map(|ip| ip.to_canonical());
+    pow.record_request();
+    let proof = req.headers().get(POW_HEADER).and_then(|v| v.to_str().ok()).map(str::to_string);
+    let rejected = match proof {
+        None => Some("Proof of work required"),
+        Some(value) => pow.verify(&value, action, ip).err(),
+    };
+    if let Some(error) = rejected {
+        let challenge = pow.issue(action, ip).map(|c| PowChallenge {
+            error: Some(error.to_string()),
+            ..c
+        });
+        return Ok(req.into_response(HttpResponse::PreconditionRequired().json(challenge)));
+    }
+    let res = next.call(req).await?;
+    if action == Action::Unlock && res.status() == actix_web::http::StatusCode::UNAUTHORIZED {
+        pow.record_failure(ip);
+    }
+    Ok(res.map_into_boxed_body())
+}
+
+/// Route middleware for anonymous `create` and `split`.
+pub async fn require_for_create(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse<BoxBody>, Error> {
+    guard(Action::Create, req, next).await
+}
+
+/// Route middleware for anonymous `unlock`; a 401 (wrong password) raises the
+/// difficulty for the caller's address.
+pub async fn require_for_unlock(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse<BoxBody>, Error> {
+    guard(Action::Unlock, req, next).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logging::Secret;
+
+    const IP: &str = "203.0.113.7";
+
+    fn pow(base_difficulty: u8) -> Pow {
+        Pow(Some(Arc::new(Inner {
+            config: PowConfig {
+                secret: Some(Secret::new("test secret, 16+ chars".to_string())),
+                base_difficulty,
+                max_difficulty: 24,
+                challenge_ttl_secs: 300,
+                load_threshold_per_minute: 60,
+            },
+            key: b"test secret, 16+ chars".to_vec(),
+            used: Mutex::new(HashMap::new()),
+            load: Mutex::new((0, 0, 0)),
+            failures: Mutex::new(HashMap::new()),
+        })))
+    }
+
+    fn ip() -> Option<IpAddr> {
+        IP.parse().ok()
+    }
+
+    /// The first solution whose hash has at least (or, with `enough` false, fewer
+    /// than) `difficulty` leading zero bits.
+    fn solve(challenge: &str, difficulty: u8, enough: bool) -> String {
+        (0u64..)
+            .map(|n| format!("{}:{}", challenge, n))
+            .find(|v| (leading_zero_bits(&Sha256::digest(v.as_bytes())) >= u32::from(difficulty)) == enough)
+            .unwrap()
+    }
+
+    fn issue(pow: &Pow, action: Action) -> PowChallenge {
+        pow.issue(action, ip()).unwrap()
+    }
+
+    #[test]
+    fn solved_challenge_is_accepted() {
+        let pow = pow(6);
+        let c = issue(&pow, Action::Create);
+        assert_eq!(c.difficulty, 6);
+        assert_eq!(pow.verify(&solve(&c.challenge, c.difficulty, true), Action::Create, ip()), Ok(()));
+    }
+
+    #[test]
+    fn tampered_mac_is_rejected() {
+        let pow = pow(4);
+        let c = issue(&pow, Action::Create);
+        let (payload, mac) = c.challenge.rsplit_once('.').unwrap();
+        let flipped = if mac.starts_with('0') { "1" } else { "0" };
+        let forged = format!("{}.{}{}", payload, flipped, &mac[1..]);
+        assert_eq!(pow.verify(&solve(&forged, 4, true), Action::Create, ip()), Err("Invalid proof of work"));
+    }
+
+    #[test]
+    fn lowered_difficulty_breaks_the_mac() {
+        let pow = pow(8);
+        let c = issue(&pow, Action::Create);
+        let forged = c.challenge.replacen("create.8.", "create.1.", 1);
+        assert_eq!(pow.verify(&solve(&forged, 1, true), Action::Create, ip()), Err("Invalid proof of work"));
+    }
+
+    #[test]
+    fn challenge_is_bound_to_the_address() {
+        let pow = pow(4);
+        let c = issue(&pow, Action::Create);
+        let other = "198.51.100.1".parse().ok();
+        assert_eq!(pow.verify(&solve(&c.challenge, 4, true), Action::Create, other), Err("Invalid proof of work"));
+    }
+
+    #[test]
+    fn challenge_is_bound_to_the_action() {
+        let pow = pow(4);
+        let c = issue(&pow, Action::Create);
+        assert_eq!(
+            pow.verify(&solve(&c.challenge, 4, true), Action::Unlock, ip()),
+            Err("Proof of work was issued for another action")
+        );
+    }
+
+    #[test]
+    fn expired_challenge_is_rejected() {
+        let pow = pow(4);
+        let inner = pow.0.as_ref().unwrap();
+        let payload = format!("create.4.{}.{}", Utc::now().timestamp() - 1, random_hex(16));
+        let challenge = format!("{}.{}", payload, to_hex(&inner.sign(&payload, ip())));
+        assert_eq!(
+            pow.verify(&solve(&challenge, 4, true), Action::Create, ip()),
+            Err("Proof-of-work challenge expired")
+        );
+    }
+
+    #[test]
+    fn too_little_work_is_rejected() {
+        let pow = pow(8);
+        let c = issue(&pow, Action::Create);
+        assert_eq!(pow.verify(&solve(&c.challenge, 8, false), Action::Create, ip()), Err("Invalid proof of work"));
+    }
+
+    #[test]
+    fn difficulty_raised_after_issue_applies_to_new_challenges_only() {
+        let pow = pow(4);
+        let c = issue(&pow, Action::Unlock);
+        pow.record_failure(ip());
+        let next = issue(&pow, Action::Unlock);
+        assert_eq!(next.difficulty, 5);
+        assert_eq!(pow.verify(&solve(&c.challenge, 4, true), Action::Unlock, ip()), Ok(()));
+        assert_eq!(pow.verify(&solve(&next.challenge, 5, false), Action::Unlock, ip()), Err("Invalid proof of work"));
+        assert_eq!(pow.verify(&solve(&next.challenge, 5, true), Action::Unlock, ip()), Ok(()));
+    }
+
+    #[test]
+    fn solution_cannot_be_replayed() {
+        let pow = pow(4);
+        let c = issue(&pow, Action::Create);
+        let value = solve(&c.challenge, 4, true);
+        assert_eq!(pow.verify(&value, Action::Create, ip()), Ok(()));
+        assert_eq!(pow.verify(&value, Action::Create, ip()), Err("Proof of work was already used"));
+        // A different solution to the same challenge spends the same nonce.
+        let other = (0u64..)
+            .map(|n| format!("{}:x{}", c.challenge, n))
+            .find(|v| leading_zero_bits(&Sha256::digest(v.as_bytes())) >= 4)
+            .unwrap();
+        assert_eq!(pow.verify(&other, Action::Create, ip()), Err("Proof of work was already used"));
+    }
+
+    #[test]
+    fn malformed_values_are_rejected() {
+        let pow = pow(4);
+        for value in ["", "nope", "create.4.1.abc:1", "a.b.c.d.e:1", &format!("{}:", issue(&pow, Action::Create).challenge)] {
+            assert_eq!(pow.verify(value, Action::Create, ip()), Err("Invalid proof of work"), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn disabled_pow_issues_nothing_and_accepts_anything() {
+        let pow = Pow(None);
+        assert!(pow.issue(Action::Create, ip()).is_none());
+        assert_eq!(pow.verify("", Action::Create, ip()), Ok(()));
+    }
+}
@@ -0,0 +1,137 @@
+//! Shamir secret sharing over GF(256), byte by byte. Shares are plain text so they
+//! can be stored as ordinary links:
+//! `shredlink:share1:{set}:{threshold}:{x}:{base64 y-bytes}`.
+//! Recombining happens on the client (`public/combine.js`, the client library).
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use rand_core::{OsRng, RngCore};
+
+pub const SHARE_PREFIX: &str = "shredlink:share1:";
+pub const MIN_SHARES: u8 = 2;
+pub const MAX_SHARES: u8 = 20;
+
+/// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x + 1, without
+/// data-dependent branches.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = 0u8.wrapping_sub(a >> 7);
+        a = (a << 1) ^ (carry & 0x1b);
+        b >>= 1;
+    }
+    product
+}
+
+/// Splits `secret` into `shares` texts, any `threshold` of which recover it.
+/// Callers check `MIN_SHARES <= threshold <= shares <= MAX_SHARES`.
+pub fn split(secret: &[u8], shares: u8, threshold: u8) -> Vec<String> {
+    let set = crate::crypto::random_hex(4);
+    let mut ys = vec![Vec::with_capacity(secret.len()); shares as usize];
+    let mut coefficients = vec![0u8; threshold as usize];
+    for &byte in secret {
+        coefficients[0] = byte;
+        OsRng.fill_bytes(&mut coefficients[1..]);
+        for (i, y) in ys.iter_mut().enumerate() {
+            let x = i as u8 + 1;
+            // Horner's rule, highest coefficient first.
+            let value = coefficients.iter().rev().fold(0u8, |acc, &c| gf_mul(acc, x) ^ c);
+            y.push(value);
+        }
+    }
+    coefficients.fill(0);
+    ys.iter()
+        .enumerate()
+        .map(|(i, y)| format!("{}{}:{}:{}:{}", SHARE_PREFIX, set, threshold, i + 1, BASE64.encode(y)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone)]
+    struct Share {
+        x: u8,
+        y: Vec<u8>,
+    }
+
+    fn parse(text: &str) -> Share {
+        let parts: Vec<&str> = text.strip_prefix(SHARE_PREFIX).unwrap().split(':').collect();
+        Share {
+            x: parts[2].parse().unwrap(),
+            y: BASE64.decode(parts[3]).unwrap(),
+        }
+    }
+
+    fn gf_inv(a: u8) -> u8 {
+        (1..=255u8).find(|&b| gf_mul(a, b) == 1).unwrap()
+    }
+
+    /// Lagrange interpolation at x = 0 over whatever shares are given.
+    fn interpolate(shares: &[Share]) -> Vec<u8> {
+        let mut secret = vec![0u8; shares[0].y.len()];
+        for (i, si) in shares.iter().enumerate() {
+            let basis = shares
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .fold(1u8, |acc, (_, sj)| gf_mul(acc, gf_mul(sj.x, gf_inv(sj.x ^ si.x))));
+            for (out, &y) in secret.iter_mut().zip(&si.y) {
+                *out ^= gf_mul(y, basis);
+            }
+        }
+        secret
+    }
+
+    /// Every subset of `0..n` with `k` members.
+    fn subsets(n: usize, k: usize) -> Vec<Vec<usize>> {
+        (0u32..1 << n)
+            .filter(|m| m.count_ones() as usize == k)
+            .map(|m| (0..n).filter(|i| m & (1 << i) != 0).collect())
+            .collect()
+    }
+
+    #[test]
+    fn gf_mul_matches_known_products() {
+        assert_eq!(gf_mul(0x53, 0xca), 0x01);
+        assert_eq!(gf_mul(0x57, 0x83), 0xc1);
+        assert_eq!(gf_mul(0, 0xff), 0);
+        assert_eq!(gf_mul(1, 0xab), 0xab);
+    }
+
+    #[test]
+    fn any_threshold_shares_recover_the_secret() {
+        let secret = b"correct horse battery staple \xf0\x9f\x94\x91";
+        for (n, k) in [(2u8, 2u8), (3, 2), (5, 3), (6, 6)] {
+            let shares: Vec<Share> = split(secret, n, k).iter().map(|s| parse(s)).collect();
+            for subset in subsets(n as usize, k as usize) {
+                let picked: Vec<Share> = subset.iter().map(|&i| shares[i].clone()).collect();
+                assert_eq!(interpolate(&picked), secret, "n={} k={} subset={:?}", n, k, subset);
+            }
+        }
+    }
+
+    #[test]
+    fn fewer_than_threshold_shares_do_not_recover_the_secret() {
+        let secret = [0x42u8; 32];
+        let shares: Vec<Share> = split(&secret, 5, 3).iter().map(|s| parse(s)).collect();
+        for subset in subsets(5, 2) {
+            let picked: Vec<Share> = subset.iter().map(|&i| shares[i].clone()).collect();
+            assert_ne!(interpolate(&picked), secret, "subset={:?}", subset);
+        }
+    }
+
+    #[test]
+    fn shares_have_distinct_indexes_and_one_set() {
+        let texts = split(b"s", 4, 2);
+        let sets: Vec<&str> = texts
+            .iter()
+            .map(|t| t.strip_prefix(SHARE_PREFIX).unwrap().split(':').next().unwrap())
+            .collect();
+        assert!(sets.iter().all(|s| *s == sets[0]));
+        let xs: Vec<u8> = texts.iter().map(|t| parse(t).x).collect();
+        assert_eq!(xs, vec![1, 2, 3, 4]);
+        assert!(texts.iter().all(|t| t.contains(":2:")));
+    }
+}
@@ -0,0 +1,393 @@
+//! Read receipts: signed JSON events POSTed to a link's webhook. Events are queued
+//! in `webhook_deliveries` so they survive restarts and are retried with
+//! exponential backoff. Payloads carry the link's public id, never its token or
+//! content.
+
+use chrono::{Duration as ChronoDuration, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sqlx::FromRow;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::{Config, WebhookConfig};
+use crate::crypto::{random_hex, to_hex};
+use crate::db::DbPool;
+use crate::metrics::Metrics;
+use crate::models::LinkRow;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const BATCH_SIZE: i64 = 20;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const FIRST_RETRY_SECS: i64 = 30;
+const MAX_RETRY_SECS: i64 = 6 * 3600;
+/// Deliveries that gave up are kept this long for inspection.
+pub const FAILED_RETENTION_DAYS: i64 = 7;
+pub const MAX_URL_LEN: usize = 2048;
+/// Prefix of per-link signing secrets.
+pub const SECRET_PREFIX: &str = "whsec_";
+
+#[derive(Clone, Copy, Debug)]
+pub enum Event {
+    Viewed,
+    Unlocked,
+    UnlockFailed,
+}
+
+impl Event {
+    fn as_str(self) -> &'static str {
+        match self {
+            Event::Viewed => "link.viewed",
+            Event::Unlocked => "link.unlocked",
+            Event::UnlockFailed => "link.unlock_failed",
+        }
+    }
+}
+
+/// Seconds to wait before retry number `attempts` (1-based): 30s doubling up to 6h.
+/// Shared with the email outbox.
+pub fn backoff_secs(attempts: i64) -> i64 {
+    (FIRST_RETRY_SECS << (attempts - 1).clamp(0, 20)).min(MAX_RETRY_SECS)
+}
+
+/// Checks that `url` is an absolute http(s) URL; the error completes a sentence
+/// starting with the setting or field name.
+pub fn check_url(url: &str) -> Result<(), &'static str> {
+    match url::Url::parse(url) {
+        Ok(_) if url.len() > MAX_URL_LEN => Err("is too long"),
+        Ok(u) if matches!(u.scheme(), "http" | "https") && u.host_str().is_some() => Ok(()),
+        Ok(_) => Err("must be an http:// or https:// URL"),
+        Err(_) => Err("is not a valid URL"),
+    }
+}
+
+/// A new signing secret for a link's own `notify_webhook`.
+pub fn generate_secret() -> String {
+    format!("{}{}", SECRET_PREFIX, random_hex(24))
+}
+
+/// Queues `event` for `row` if webhooks are enabled and the link (or the server
+/// default) has a URL. Errors are logged and never fail the request.
+pub async fn enqueue(pool: &DbPool, config: &Config, row: &LinkRow, event: Event) {
+    let Some(webhooks) = &config.webhooks else { return };
+    // The link's own URL is signed with its own secret; the server default with WEBHOOK_SECRET.
+    let (url, secret) = match (row.webhook_url.as_deref(), webhooks.default_url.as_deref()) {
+        (Some(url), _) => (url, row.webhook_secret.as_deref()),
+        (None, Some(url)) => (url, None),
+        (None, None) => return,
+    };
+    let Some(link_id) = row.public_id.as_deref() else { return };
+    let now = Utc::now().to_rfc3339();
+    let payload = serde_json::json!({
+        "id": random_hex(16),
+        "type": event.as_str(),
+        "link_id": link_id,
+        "created_at": now,
+    });
+    if let Err(e) = sqlx::query("INSERT INTO webhook_deliveries (url, secret, event, payload, next_attempt_at) VALUES (?, ?, ?, ?, ?)")
+        .bind(url)
+        .bind(secret)
+        .bind(event.as_str())
+        .bind(payload.to_string())
+        .bind(&now)
+        .execute(pool)
+        .await
+    {
+        tracing::warn!("webhook enqueue: {}", e);
+    }
+}
+
+/// Queues a `link.deleted` event for every link matching `expired` or `consumed`
+/// (SQL conditions where `?1` is `now`) before cleanup deletes them.
+pub async fn enqueue_deletions(
+    conn: &mut sqlx::SqliteConnection,
+    webhooks: &WebhookConfig,
+    expired: &str,
+    consumed: &str,
+    now: &str,
+) -> anyhow::Result<u64> {
+    let sql = format!(
+        "INSERT INTO webhook_deliveries (url, secret, event, payload, next_attempt_at) \
+         SELECT COALESCE(webhook_url, ?2), CASE WHEN webhook_url IS NOT NULL THEN webhook_secret END, 'link.deleted', \
+           json_object('id', lower(hex(randomblob(16))), 'type', 'link.deleted', 'link_id', public_id, \
+             'reason', CASE WHEN {expired} THEN 'expired' ELSE 'consumed' END, 'created_at', ?1), ?1 \
+         FROM links WHERE COALESCE(webhook_url, ?2) IS NOT NULL AND public_id IS NOT NULL AND ({expired} OR {consumed})",
+    );
+    let r = sqlx::query(&sql)
+        .bind(now)
+        .bind(webhooks.default_url.as_deref())
+        .execute(conn)
+        .await?;
+    Ok(r.rows_affected())
+}
+
+/// `sha256=` hex HMAC of `{timestamp}.{body}`, sent as `X-ShredLink-Signature`.
+/// Keyed with the link's own secret, or `WEBHOOK_SECRET` for the default URL.
+pub fn sign(secret: &str, timestamp: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("sha256={}", to_hex(&mac.finalize().into_bytes()))
+}
+
+/// Whether `ip` is a globally routable unicast address. IPv6 forms that embed an
+/// IPv4 address (mapped, NAT64) are judged by that address.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                || v4.is_documentation()
+                // 0.0.0.0/8, "this network"
+                || a == 0
+                // 100.64.0.0/10, carrier-grade NAT
+                || (a == 100 && (64..128).contains(&b))
+                // 198.18.0.0/15, benchmarking
+                || (a == 198 && (b & 0xfe) == 18)
+                // 240.0.0.0/4, reserved
+                || a >= 240)
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(v4));
+            }
+            let segments = v6.segments();
+            // 64:ff9b::/96, NAT64
+            if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+                let [.., a, b, c, d] = v6.octets();
+                return is_public(IpAddr::V4(std::net::Ipv4Addr::new(a, b, c, d)));
+            }
+            let first = segments[0];
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                // 64:ff9b:1::/48, local-use NAT64
+                || segments[..3] == [0x64, 0xff9b, 1]
+                // fc00::/7 unique local, fe80::/10 link-local
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// Why a delivery attempt failed, and whether retrying could help.
+struct Failure {
+    error: String,
+    permanent: bool,
+}
+
+impl Failure {
+    fn retry(error: impl ToString) -> Self {
+        Self {
+            error: error.to_string(),
+            permanent: false,
+        }
+    }
+
+    fn permanent(error: impl ToString) -> Self {
+        Self {
+            error: error.to_string(),
+            permanent: true,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct Delivery {
+    id: i64,
+    url: String,
+    secret: Option<String>,
+    event: String,
+    payload: String,
+    attempts: i64,
+}
+
+async fn send(config: &WebhookConfig, delivery: &Delivery) -> Result<(), Failure> {
+    let url = url::Url::parse(&delivery.url).map_err(Failure::permanent)?;
+    let host = url.host_str().ok_or_else(|| Failure::permanent("URL has no host"))?.to_string();
+    let port = url.port_or_known_default().unwrap_or(443);
+    let mut http = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .user_agent(concat!("shredlink-webhooks/", env!("CARGO_PKG_VERSION")));
+    if !config.allow_private_targets {
+        // Resolve once and pin the address so DNS cannot change between the check and the request.
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.trim_matches(['[', ']']), port))
+            .await
+            .map_err(Failure::retry)?
+            .collect();
+        let addr = match addrs.first() {
+            None => return Err(Failure::retry(format!("{} did not resolve", host))),
+            Some(_) if addrs.iter().any(|a| !is_public(a.ip())) => {
+                return Err(Failure::permanent(format!(
+                    "{} resolves to a non-public address (set WEBHOOK_ALLOW_PRIVATE_TARGETS to allow)",
+                    host
+                )));
+            }
+            Some(addr) => *addr,
+        };
+        http = http.resolve(&host, addr);
+    }
+    let http = http.build().map_err(Failure::retry)?;
+
+    let timestamp = Utc::now().timestamp().to_string();
+    let res = http
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header("X-ShredLink-Event", &delivery.event)
+        .header("X-ShredLink-Timestamp", &timestamp)
+        .header(
+            "X-ShredLink-Signature",
+            sign(delivery.secret.as_deref().unwrap_or(config.secret.expose()), &timestamp, &delivery.payload),
+        )
+        .body(delivery.payload.clone())
+        .send()
+        .await
+        .map_err(Failure::retry)?;
+    if res.status().is_success() {
+        Ok(())
+    } else {
+        Err(Failure::retry(format!("HTTP {}", res.status().as_u16())))
+    }
+}
+
+async fn deliver_due(pool: &DbPool, config: &WebhookConfig, metrics: &Metrics) -> anyhow::Result<()> {
+    let due: Vec<Delivery> = sqlx::query_as(
+        "SELECT id, url, secret, event, payload, attempts FROM webhook_deliveries \
+         WHERE failed_at IS NULL AND datetime(next_attempt_at) <= datetime(?) ORDER BY id LIMIT ?",
+    )
+    .bind(Utc::now().to_rfc3339())
+    .bind(BATCH_SIZE)
+    .fetch_all(pool)
+    .await?;
+
+    for delivery in due {
+        let failure = match send(config, &delivery).await {
+            Ok(()) => {
+                sqlx::query("DELETE FROM webhook_deliveries WHERE id = ?")
+                    .bind(delivery.id)
+                    .execute(pool)
+                    .await?;
+                metrics.webhooks_delivered.inc();
+                continue;
+            }
+            Err(f) => f,
+        };
+        let attempts = delivery.attempts + 1;
+        let now = Utc::now();
+        if failure.permanent || attempts >= config.max_attempts as i64 {
+            tracing::warn!("webhook delivery {} gave up after {} attempt(s): {}", delivery.id, attempts, failure.error);
+            metrics.webhooks_failed.inc();
+            sqlx::query("UPDATE webhook_deliveries SET attempts = ?, last_error = ?, failed_at = ? WHERE id = ?")
+                .bind(attempts)
+                .bind(&failure.error)
+                .bind(now.to_rfc3339())
+                .bind(delivery.id)
+                .execute(pool)
+                .await?;
+        } else {
+            let backoff = backoff_secs(attempts);
+            tracing::info!("webhook delivery {} failed ({}); retrying in {}s", delivery.id, failure.error, backoff);
+            sqlx::query("UPDATE webhook_deliveries SET attempts = ?, last_error = ?, next_attempt_at = ? WHERE id = ?")
+                .bind(attempts)
+                .bind(&failure.error)
+                .bind((now + ChronoDuration::seconds(backoff)).to_rfc3339())
+                .bind(delivery.id)
+                .execute(pool)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Delivers queued events until `shutdown` flips to true.
+pub async fn run_deliveries(
+    pool: Arc<DbPool>,
+    config: WebhookConfig,
+    metrics: Metrics,
+    mut shutdown: tokio::sync::watch::Receiver<bool>,
+) {
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.changed() => break,
+        }
+        if let Err(e) = deliver_due(pool.as_ref(), &config, &metrics).await {
+            tracing::warn!("webhook delivery: {}", e);
+        }
+    }
+    tracing::info!("webhook delivery stopped");
+}
+
+/// Drops deliveries that gave up more than a week ago.
+pub async fn delete_old_failures(pool: &DbPool) -> anyhow::Result<u64> {
+    let cutoff = Utc::now() - ChronoDuration::days(FAILED_RETENTION_DAYS);
+    let r = sqlx::query("DELETE FROM webhook_deliveries WHERE failed_at IS NOT NULL AND datetime(failed_at) < datetime(?)")
+        .bind(cutoff.to_rfc3339())
+        .execute(pool)
+        .await?;
+    Ok(r.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn public(ip: &str) -> bool {
+        is_public(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn sign_is_hmac_sha256_of_timestamp_and_body() {
+        assert_eq!(
+            sign("whsec", "1700000000", r#"{"type":"link.viewed"}"#),
+            "sha256=098e4537a3efb0c42e6fc01c230cdc32e86cf714cfe95b6e6b21281ee866bb45"
+        );
+        assert_ne!(sign("whsec", "1700000001", r#"{"type":"link.viewed"}"#), sign("whsec", "1700000000", r#"{"type":"link.viewed"}"#));
+    }
+
+    #[test]
+    fn public_addresses_are_allowed() {
+        for ip in ["93.184.216.34", "1.1.1.1", "2606:4700::1111", "::ffff:93.184.216.34", "64:ff9b::5db8:d822"] {
+            assert!(public(ip), "{} should be public", ip);
+        }
+    }
+
+    #[test]
+    fn special_ipv4_ranges_are_rejected() {
+        for ip in [
+            "0.0.0.0", "0.1.2.3", "127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254",
+            "100.64.0.1", "100.127.255.255", "198.18.0.1", "198.19.255.255", "192.0.2.1", "224.0.0.1",
+            "240.0.0.1", "255.255.255.255",
+        ] {
+            assert!(!public(ip), "{} should not be public", ip);
+        }
+        assert!(public("100.128.0.1"));
+        assert!(public("198.20.0.1"));
+    }
+
+    #[test]
+    fn special_ipv6_ranges_are_rejected() {
+        for ip in ["::", "::1", "fc00::1", "fd12::1", "fe80::1", "ff02::1", "64:ff9b:1::1"] {
+            assert!(!public(ip), "{} should not be public", ip);
+        }
+    }
+
+    #[test]
+    fn embedded_ipv4_is_judged_by_the_ipv4_address() {
+        for v4 in ["127.0.0.1", "10.0.0.1", "169.254.169.254", "198.18.0.1", "0.0.0.0"] {
+            let [a, b, c, d] = v4.parse::<std::net::Ipv4Addr>().unwrap().octets();
+            let nat64 = format!("64:ff9b::{:02x}{:02x}:{:02x}{:02x}", a, b, c, d);
+            assert!(!public(&format!("::ffff:{}", v4)), "mapped {}", v4);
+            assert!(!public(&nat64), "{}", nat64);
+        }
+    }
+}
@@ -0,0 +1,9 @@
+//! Wire types of the ShredLink HTTP API, shared by the server and clients.
+//!
+//! Each API version lives in its own module so a later version can change its
+//! models without touching earlier ones. The crate root re-exports the current
+//! stable version.
+
+pub mod v1;
+
+pub use v1::*;
@@ -0,0 +1,381 @@
+//! API version 1 (`/api/v1/...`).
+
+use serde::{Deserialize, Serialize};
+
+/// Body of `POST /api/v1/create`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateRequest {
+    /// Content to share; at most `MAX_TEXT_SIZE_BYTES` bytes.
+    pub text: String,
+    /// Optional password required to read the link.
+    pub password: Option<String>,
+    /// Minutes until expiry, added to `expire_hours`. No expiry if both are 0 or absent.
+    /// Counted from `not_before` when that is set.
+    #[serde(default)]
+    pub expire_minutes: Option<u32>,
+    /// Hours until expiry, added to `expire_minutes`.
+    #[serde(default)]
+    pub expire_hours: Option<u32>,
+    /// Fixed expiry time (RFC 3339). Cannot be combined with `expire_minutes`/`expire_hours`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+    /// Go-live time (RFC 3339); the link cannot be read before it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<String>,
+    /// Expire this many minutes after the last read (or after going live if never
+    /// read), in addition to any absolute expiry. Each read resets the timer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_timeout_minutes: Option<u32>,
+    /// URL that receives signed read-receipt events for this link. Requires
+    /// webhooks to be enabled on the server, which may also set a default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify_webhook: Option<String>,
+    /// Address that is emailed when the link is first opened or expires unread.
+    /// Requires SMTP on the server and a signed-in user or API key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify_email: Option<String>,
+    /// Language of those emails: `en` (default) or `fa`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify_lang: Option<String>,
+    /// Seal the text to this X25519 key (`slpub_...`), or to the key registered by
+    /// `@username` (needs a signed-in user or API key). Reads then return the sealed
+    /// payload (`shredlink:seal1:...`), which only the recipient's secret key opens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipient_public_key: Option<String>,
+    /// Names of the people to send the link to. Each gets their own one-time token
+    /// for the same content (see `CreateResponse::recipients`); the link's own token
+    /// then only identifies it to the creator and cannot be read. Needs a signed-in
+    /// user or API key, and cannot be combined with `one_time_view`/`one_time_password`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipient_labels: Vec<String>,
+    /// Networks the link may be opened from, as CIDRs (`10.0.0.0/8`) or single
+    /// addresses. From anywhere else the link answers 404, as if it did not exist.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_cidrs: Vec<String>,
+    /// Burn the link after the first view.
+    #[serde(default)]
+    pub one_time_view: bool,
+    /// Burn the link after the first successful password unlock.
+    #[serde(default)]
+    pub one_time_password: bool,
+}
+
+/// Response of `POST /api/v1/create`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateResponse {
+    pub token: String,
+    /// Public id that identifies the link in webhook events.
+    #[serde(default)]
+    pub link_id: String,
+    /// Share URL (`{BASE_URL}/s/{token}`). Not readable for multi-recipient links;
+    /// send each recipient their own URL from `recipients` instead.
+    pub url: String,
+    /// One entry per `recipient_labels` name, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipients: Vec<RecipientLink>,
+    /// Key for verifying `X-ShredLink-Signature` on events sent to `notify_webhook`.
+    /// Only returned here; store it with the receiver.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_secret: Option<String>,
+}
+
+/// A recipient's own one-time link to a multi-recipient link's content.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RecipientLink {
+    pub label: String,
+    pub token: String,
+    /// Share URL (`{BASE_URL}/s/{token}`).
+    pub url: String,
+}
+
+/// Whether one recipient of a multi-recipient link has read it
+/// (`GET /api/v1/links/{token}/recipients`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RecipientStatus {
+    pub label: String,
+    pub viewed: bool,
+    /// RFC 3339; absent until the recipient reads the link.
+    #[serde(default)]
+    pub viewed_at: Option<String>,
+}
+
+/// Body of `POST /api/v1/unlock/{token}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct UnlockRequest {
+    pub password: String,
+}
+
+/// Content returned by `POST /api/v1/unlock/{token}` and `POST /api/v1/read/{token}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct UnlockResponse {
+    /// The stored text. Client-side envelopes (`shredlink:v1:`) and content sealed to
+    /// a recipient key (`shredlink:seal1:`) are returned as-is for local decryption.
+    pub text: String,
+}
+
+/// Body of every JSON error response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+/// Body of `POST /api/v1/account/register` and `POST /api/v1/account/login`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AccountRequest {
+    /// 3–32 characters: letters, digits, `.`, `_` or `-`.
+    pub username: String,
+    /// At least 8 characters.
+    pub password: String,
+}
+
+/// The signed-in account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AccountResponse {
+    pub username: String,
+    /// Groups from the identity provider; empty for local accounts.
+    #[serde(default)]
+    pub groups: Vec<String>,
+    /// Registered recipient key (`slpub_...`) that links can be sealed to.
+    #[serde(default)]
+    pub public_key: Option<String>,
+    /// Verified address that `notify_email` may name.
+    #[serde(default)]
+    pub email: Option<String>,
+}
+
+/// Body of `PUT /api/v1/account/email`: the address to confirm.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct EmailRequest {
+    pub email: String,
+    /// Language of the confirmation message (`en` or `fa`).
+    #[serde(default)]
+    pub lang: Option<String>,
+}
+
+/// Body of `POST /api/v1/account/email/confirm`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct EmailConfirmRequest {
+    /// Code from the confirmation message.
+    pub code: String,
+}
+
+/// Body of `PUT /api/v1/account/public-key`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PublicKeyRequest {
+    /// X25519 public key, `slpub_` + base64url of 32 bytes.
+    pub public_key: String,
+}
+
+/// Which sign-in methods and policies the server has enabled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AccountOptions {
+    /// Local accounts can be created.
+    pub signup_enabled: bool,
+    /// Single sign-on via `GET /api/v1/account/oidc/login` is available.
+    pub sso_enabled: bool,
+    /// Creating links requires a signed-in account or an API key.
+    pub require_auth_for_create: bool,
+}
+
+/// State of a link as shown on the dashboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum LinkStatus {
+    Active,
+    /// Not readable until `not_before`.
+    Scheduled,
+    Expired,
+    /// One-time link that has already been viewed or unlocked, or a multi-recipient
+    /// link that every recipient has read.
+    Consumed,
+}
+
+/// Metadata of a link owned by the signed-in account. Never includes content or the
+/// token; the share URL is only returned once, when the link is created.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LinkSummary {
+    /// Public id, also used in webhook events and to revoke the link.
+    pub link_id: String,
+    pub status: LinkStatus,
+    pub views: i64,
+    pub password_protected: bool,
+    pub one_time_view: bool,
+    pub one_time_password: bool,
+    /// RFC 3339; absent if the link does not expire.
+    pub expires_at: Option<String>,
+    /// RFC 3339; absent if the link was readable from creation.
+    #[serde(default)]
+    pub not_before: Option<String>,
+    /// Inactivity expiry in minutes, if set.
+    #[serde(default)]
+    pub idle_timeout_minutes: Option<u32>,
+    /// RFC 3339; absent if the link was never read.
+    #[serde(default)]
+    pub last_accessed_at: Option<String>,
+    /// Networks the link can be opened from; empty for anywhere.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_cidrs: Vec<String>,
+    /// Per-recipient status of a multi-recipient link; empty otherwise.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipients: Vec<RecipientStatus>,
+    /// RFC 3339.
+    pub created_at: String,
+}
+
+/// Creation rules enforced by the server (`GET /api/v1/policy`). Requests that
+/// break them are rejected with `422` (`413` for size).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreatePolicy {
+    /// Lifetime applied when a request sets no expiry.
+    pub default_ttl_minutes: Option<u32>,
+    /// Longest lifetime allowed; when set, every link must expire.
+    pub max_ttl_minutes: Option<u32>,
+    pub require_password: bool,
+    /// Links must set `one_time_view` or `one_time_password`.
+    pub require_one_time: bool,
+    pub min_password_length: usize,
+    /// Character classes (lowercase, uppercase, digits, symbols) a password must mix.
+    pub min_password_classes: u8,
+    /// Size limit that applies to the caller of `GET /api/v1/policy`.
+    pub max_text_size_bytes: usize,
+}
+
+/// Body of `POST /api/v1/requests`: ask someone to send you a secret.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateSecretRequest {
+    /// Shown to the person answering, e.g. what to send. Up to 500 characters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// How long the request stays open and its answer readable (default 7 days, at most 30).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expire_minutes: Option<u32>,
+    /// Key (`slpub_...`) the answer is sealed to. Defaults to the signed-in user's
+    /// registered key; one of the two is required.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+}
+
+/// Response of `POST /api/v1/requests`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SecretRequestCreated {
+    /// Id used to read or delete the request.
+    pub id: String,
+    /// Link to send to the person who should answer (`{BASE_URL}/r/{token}`).
+    pub url: String,
+    /// RFC 3339.
+    pub expires_at: String,
+}
+
+/// State of a secret request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SecretRequestStatus {
+    /// Waiting for an answer.
+    Pending,
+    /// Answered; readable once by the owner.
+    Submitted,
+    Expired,
+}
+
+/// A secret request owned by the caller. Never includes the answer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SecretRequestSummary {
+    pub id: String,
+    pub url: String,
+    pub note: Option<String>,
+    /// Key the answer is sealed to; absent for requests from before sealing.
+    #[serde(default)]
+    pub public_key: Option<String>,
+    pub status: SecretRequestStatus,
+    /// RFC 3339.
+    pub expires_at: String,
+    /// RFC 3339; absent until answered.
+    pub submitted_at: Option<String>,
+    /// RFC 3339.
+    pub created_at: String,
+}
+
+/// What the person answering sees (`GET /api/v1/requests/submit/{token}`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SecretRequestInfo {
+    pub note: Option<String>,
+    /// RFC 3339.
+    pub expires_at: String,
+    pub max_text_size_bytes: usize,
+    /// Requester's `slpub_` key; the answer must be sealed to it.
+    #[serde(default)]
+    pub public_key: Option<String>,
+}
+
+/// Body of `POST /api/v1/requests/submit/{token}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SubmitSecretRequest {
+    /// The answer sealed to `public_key` (`shredlink:seal1:...`); plaintext is rejected.
+    pub text: String,
+}
+
+/// Body of `POST /api/v1/split`: an M-of-N split of `text` into one link per share.
+/// Every other field of [`CreateRequest`] applies to each share link.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SplitRequest {
+    /// Number of shares and links to create (N, 2–20).
+    pub shares: u8,
+    /// Shares needed to recover the secret (M, 2–N).
+    pub threshold: u8,
+    /// Optional `recipient_public_key` per share, in order, so each custodian's
+    /// link is sealed to their own key. Use instead of `recipient_public_key`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipients: Vec<String>,
+    #[serde(flatten)]
+    pub link: CreateRequest,
+}
+
+/// Response of `POST /api/v1/split`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SplitResponse {
+    pub threshold: u8,
+    /// One link per share; hand each to a different custodian.
+    pub links: Vec<CreateResponse>,
+}
+
+/// Proof-of-work challenge from `GET /api/v1/pow`, also the body of a 428 answer
+/// to an anonymous create or unlock sent without a valid proof. Find any
+/// `solution` such that SHA-256 of `{challenge}:{solution}` starts with
+/// `difficulty` zero bits, then repeat the request with the header
+/// `X-ShredLink-PoW: {challenge}:{solution}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PowChallenge {
+    /// Set on a 428 response; absent from `GET /api/v1/pow`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub challenge: String,
+    /// Required leading zero bits of the hash.
+    pub difficulty: u8,
+    /// Unix time after which the challenge is no longer accepted.
+    pub expires_at: i64,
+}